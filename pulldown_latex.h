@@ -0,0 +1,164 @@
+#ifndef PULLDOWN_LATEX_H
+#define PULLDOWN_LATEX_H
+
+/* This file is generated by cbindgen from `src/c_api.rs`, and should not be edited. */
+
+#include <stddef.h>
+
+// The input was parsed and rendered successfully.
+#define PULLDOWN_LATEX_OK 0
+
+// A pointer given as argument is null.
+#define PULLDOWN_LATEX_NULL_POINTER -1
+
+// The input is not valid UTF-8.
+#define PULLDOWN_LATEX_INVALID_UTF8 -2
+
+// The events parsed from the input could not be rendered to MathML.
+#define PULLDOWN_LATEX_RENDER_ERROR -3
+
+// The code of the `UnbalancedGroup` error kind.
+#define PULLDOWN_LATEX_ERROR_UNBALANCED_GROUP 1
+
+// The code of the `Environment` error kind.
+#define PULLDOWN_LATEX_ERROR_ENVIRONMENT 2
+
+// The code of the `MathShift` error kind.
+#define PULLDOWN_LATEX_ERROR_MATH_SHIFT 3
+
+// The code of the `UnbalancedMathShift` error kind.
+#define PULLDOWN_LATEX_ERROR_UNBALANCED_MATH_SHIFT 4
+
+// The code of the `HashSign` error kind.
+#define PULLDOWN_LATEX_ERROR_HASH_SIGN 5
+
+// The code of the `AlignmentChar` error kind.
+#define PULLDOWN_LATEX_ERROR_ALIGNMENT_CHAR 6
+
+// The code of the `EndOfInput` error kind.
+#define PULLDOWN_LATEX_ERROR_END_OF_INPUT 7
+
+// The code of the `DimensionArgument` error kind.
+#define PULLDOWN_LATEX_ERROR_DIMENSION_ARGUMENT 8
+
+// The code of the `DimensionUnit` error kind.
+#define PULLDOWN_LATEX_ERROR_DIMENSION_UNIT 9
+
+// The code of the `MathUnit` error kind.
+#define PULLDOWN_LATEX_ERROR_MATH_UNIT 10
+
+// The code of the `Delimiter` error kind.
+#define PULLDOWN_LATEX_ERROR_DELIMITER 11
+
+// The code of the `ControlSequence` error kind.
+#define PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE 12
+
+// The code of the `Number` error kind.
+#define PULLDOWN_LATEX_ERROR_NUMBER 13
+
+// The code of the `CharacterNumber` error kind.
+#define PULLDOWN_LATEX_ERROR_CHARACTER_NUMBER 14
+
+// The code of the `Argument` error kind.
+#define PULLDOWN_LATEX_ERROR_ARGUMENT 15
+
+// The code of the `DoubleSubscript` error kind.
+#define PULLDOWN_LATEX_ERROR_DOUBLE_SUBSCRIPT 16
+
+// The code of the `DoubleSuperscript` error kind.
+#define PULLDOWN_LATEX_ERROR_DOUBLE_SUPERSCRIPT 17
+
+// The code of the `NestingTooDeep` error kind.
+#define PULLDOWN_LATEX_ERROR_NESTING_TOO_DEEP 18
+
+// The code of the `EmptySubscript` error kind.
+#define PULLDOWN_LATEX_ERROR_EMPTY_SUBSCRIPT 19
+
+// The code of the `EmptySuperscript` error kind.
+#define PULLDOWN_LATEX_ERROR_EMPTY_SUPERSCRIPT 20
+
+// The code of the `UnknownPrimitive` error kind.
+#define PULLDOWN_LATEX_ERROR_UNKNOWN_PRIMITIVE 21
+
+// The code of the `ControlSequenceAsArgument` error kind.
+#define PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE_AS_ARGUMENT 22
+
+// The code of the `EmptyControlSequence` error kind.
+#define PULLDOWN_LATEX_ERROR_EMPTY_CONTROL_SEQUENCE 23
+
+// The code of the `UnknownColor` error kind.
+#define PULLDOWN_LATEX_ERROR_UNKNOWN_COLOR 24
+
+// The code of the `InvalidCharNumber` error kind.
+#define PULLDOWN_LATEX_ERROR_INVALID_CHAR_NUMBER 25
+
+// The code of the `Relax` error kind.
+#define PULLDOWN_LATEX_ERROR_RELAX 26
+
+// The code of the `MiddleOutsideLeftRight` error kind.
+#define PULLDOWN_LATEX_ERROR_MIDDLE_OUTSIDE_LEFT_RIGHT 27
+
+// The code of the `AmbiguousInfix` error kind.
+#define PULLDOWN_LATEX_ERROR_AMBIGUOUS_INFIX 28
+
+// The code of the `LimitControl` error kind.
+#define PULLDOWN_LATEX_ERROR_LIMIT_CONTROL 29
+
+// The code of the `UnterminatedConditional` error kind.
+#define PULLDOWN_LATEX_ERROR_UNTERMINATED_CONDITIONAL 30
+
+// The code of the `ConditionalEnd` error kind.
+#define PULLDOWN_LATEX_ERROR_CONDITIONAL_END 31
+
+// The code of the `Relation` error kind.
+#define PULLDOWN_LATEX_ERROR_RELATION 32
+
+// The code of the `ControlSequenceName` error kind.
+#define PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE_NAME 33
+
+// The code of the `ArrayColumns` error kind.
+#define PULLDOWN_LATEX_ERROR_ARRAY_COLUMNS 34
+
+// The code of the `DelimiterSize` error kind.
+#define PULLDOWN_LATEX_ERROR_DELIMITER_SIZE 35
+
+// The code of the `Untrusted` error kind.
+#define PULLDOWN_LATEX_ERROR_UNTRUSTED 36
+
+// The code of the `UntrustedUrl` error kind.
+#define PULLDOWN_LATEX_ERROR_UNTRUSTED_URL 37
+
+// The code of the `MacroParameters` error kind.
+#define PULLDOWN_LATEX_ERROR_MACRO_PARAMETERS 38
+
+// The code of the `MacroArguments` error kind.
+#define PULLDOWN_LATEX_ERROR_MACRO_ARGUMENTS 39
+
+// The code of the `MacroStorage` error kind.
+#define PULLDOWN_LATEX_ERROR_MACRO_STORAGE 40
+
+// Parse the input and render it to MathML, in display mode if `display` is non-zero.
+//
+// On success, `*out` is set to the rendered MathML. If the input contains an error, `*out` is set
+// to the message of the first error, and the code of its kind is returned. If the events cannot
+// be rendered, `*out` is set to the message of the rendering error, and
+// `PULLDOWN_LATEX_RENDER_ERROR` is returned. In all cases, the string is allocated by this
+// library, and must be freed using `pulldown_latex_free`.
+//
+// If `out_len` is not null, `*out_len` is set to the length of the string, in bytes, without the
+// terminating null character.
+//
+// # Safety
+//
+// `input` must be a valid pointer to a null-terminated string, and `out` must be a valid pointer
+// to write a pointer to. `out_len` must either be null, or a valid pointer to write a length to.
+int pulldown_latex_parse_mathml(const char *input, int display, char **out, size_t *out_len);
+
+// Free a string returned by this library.
+//
+// # Safety
+//
+// `ptr` must either be null, or a string returned by this library which is not already freed.
+void pulldown_latex_free(char *ptr);
+
+#endif  /* PULLDOWN_LATEX_H */
@@ -3,6 +3,7 @@ pub type Glue = (Dimension, Option<Dimension>, Option<Dimension>);
 
 /// Fonts
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Font {
     BoldScript,
     BoldItalic,
@@ -18,6 +19,14 @@ pub enum Font {
     SansSerifItalic,
     BoldSansSerif,
     UpRight,
+    /// Small capitals, for which lowercase letters are mapped to their small capital form.
+    SmallCaps,
+    /// Slanted upright letters, which have no Unicode equivalent, and must be styled by the
+    /// renderer.
+    Slanted,
+    /// Double struck italic letters, which only exist in Unicode for `D`, `d`, `e`, `i` and `j`.
+    /// Other letters are mapped to their double struck form.
+    DoubleStruckItalic,
 }
 
 impl Font {
@@ -168,6 +177,15 @@ impl Font {
             (Font::BoldSansSerif, '\u{03D6}') => c as u32 + 0x1D3B9,
             (Font::BoldSansSerif, '0'..='9') => c as u32 + 0x1D7BC,
 
+            // Small Caps mappings
+            (Font::SmallCaps, 'a'..='z') => SMALL_CAPITALS[c as usize - 'a' as usize] as u32,
+
+            // Double Struck Italic mappings
+            (Font::DoubleStruckItalic, 'D') => 0x2145,
+            (Font::DoubleStruckItalic, 'd' | 'e') => c as u32 + 0x20E2,
+            (Font::DoubleStruckItalic, 'i' | 'j') => c as u32 + 0x20DF,
+            (Font::DoubleStruckItalic, _) => Font::DoubleStruck.map_char(c) as u32,
+
             // Upright mappings (map to themselves) and unknown mappings
             (_, _) => c as u32,
         })
@@ -175,7 +193,14 @@ impl Font {
     }
 }
 
+/// The small capital form of the lowercase latin letters, when it exists in Unicode.
+const SMALL_CAPITALS: [char; 26] = [
+    'ᴀ', 'ʙ', 'ᴄ', 'ᴅ', 'ᴇ', 'ꜰ', 'ɢ', 'ʜ', 'ɪ', 'ᴊ', 'ᴋ', 'ʟ', 'ᴍ', 'ɴ', 'ᴏ', 'ᴘ', 'ꞯ', 'ʀ', 'ꜱ',
+    'ᴛ', 'ᴜ', 'ᴠ', 'ᴡ', 'x', 'ʏ', 'ᴢ',
+];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DimensionUnit {
     Em,
     Ex,
@@ -191,6 +216,25 @@ pub enum DimensionUnit {
     Mu,
 }
 
+impl std::fmt::Display for DimensionUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DimensionUnit::Em => "em",
+            DimensionUnit::Ex => "ex",
+            DimensionUnit::Pt => "pt",
+            DimensionUnit::Pc => "pc",
+            DimensionUnit::In => "in",
+            DimensionUnit::Bp => "bp",
+            DimensionUnit::Cm => "cm",
+            DimensionUnit::Mm => "mm",
+            DimensionUnit::Dd => "dd",
+            DimensionUnit::Cc => "cc",
+            DimensionUnit::Sp => "sp",
+            DimensionUnit::Mu => "mu",
+        })
+    }
+}
+
 /// Convert TeX units to CSS units.
 ///
 /// This is similar to the conversion table in the TeXbook p. 57, but is not exact.
@@ -230,3 +274,33 @@ pub fn tex_to_css_em(dim: Dimension) -> f32 {
         DimensionUnit::Mm => dim.0 * 6. / 25.4,
     }
 }
+
+/// Convert a TeX dimension to scaled points, the unit in which TeX compares dimensions (e.g., in
+/// `\ifdim`), following the conversion table in the TeXbook p. 57.
+///
+/// The font-relative units are those of the 10pt font of plain TeX: `1em` is `10pt`, `1ex` is
+/// `4.30554pt`, and `1mu` is `1/18em`.
+pub fn tex_to_scaled_points(dim: Dimension) -> i64 {
+    // The number is written as it was in the input, so that e.g., `72.27pt` is not affected by the
+    // rounding of `72.27` to an `f32`.
+    let value: f64 = dim
+        .0
+        .to_string()
+        .parse()
+        .expect("a float is written as a valid float");
+    let points = match dim.1 {
+        DimensionUnit::Pt => value,
+        DimensionUnit::Pc => value * 12.,
+        DimensionUnit::Bp => value * 72.27 / 72.,
+        DimensionUnit::Dd => value * 1238. / 1157.,
+        DimensionUnit::Cc => value * 12. * 1238. / 1157.,
+        DimensionUnit::Sp => value / 65536.,
+        DimensionUnit::Mu => value * 10. / 18.,
+        DimensionUnit::Em => value * 10.,
+        DimensionUnit::Ex => value * 4.30554,
+        DimensionUnit::In => value * 72.27,
+        DimensionUnit::Cm => value * 72.27 / 2.54,
+        DimensionUnit::Mm => value * 72.27 / 25.4,
+    };
+    (points * 65536.).round() as i64
+}
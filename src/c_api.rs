@@ -0,0 +1,292 @@
+//! A C-compatible interface to the parser and the `mathml` renderer, for use from other
+//! languages (e.g., through Python's `ctypes`, or from C++).
+//!
+//! This module is only available with the `c_api` feature. The matching C header,
+//! `pulldown_latex.h`, is generated by `cbindgen` at the root of the repository, and
+//! `cbindgen.toml` describes how to build the shared and static libraries.
+//!
+//! Every function returns a status code, which is [`PULLDOWN_LATEX_OK`] on success, a negative
+//! code if the arguments are invalid or the input cannot be rendered, or the positive code of the
+//! [`ErrorKind`] of the error found in the input.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+
+use crate::{
+    config::DisplayMode,
+    mathml::push_mathml,
+    parser::{ErrorKind, Storage},
+    Parser, ParserConfig, ParserError, RenderConfig,
+};
+
+/// The input was parsed and rendered successfully.
+pub const PULLDOWN_LATEX_OK: c_int = 0;
+/// A pointer given as argument is null.
+pub const PULLDOWN_LATEX_NULL_POINTER: c_int = -1;
+/// The input is not valid UTF-8.
+pub const PULLDOWN_LATEX_INVALID_UTF8: c_int = -2;
+/// The events parsed from the input could not be rendered to MathML.
+pub const PULLDOWN_LATEX_RENDER_ERROR: c_int = -3;
+
+/// The code of the `UnbalancedGroup` error kind.
+pub const PULLDOWN_LATEX_ERROR_UNBALANCED_GROUP: c_int = 1;
+/// The code of the `Environment` error kind.
+pub const PULLDOWN_LATEX_ERROR_ENVIRONMENT: c_int = 2;
+/// The code of the `MathShift` error kind.
+pub const PULLDOWN_LATEX_ERROR_MATH_SHIFT: c_int = 3;
+/// The code of the `UnbalancedMathShift` error kind.
+pub const PULLDOWN_LATEX_ERROR_UNBALANCED_MATH_SHIFT: c_int = 4;
+/// The code of the `HashSign` error kind.
+pub const PULLDOWN_LATEX_ERROR_HASH_SIGN: c_int = 5;
+/// The code of the `AlignmentChar` error kind.
+pub const PULLDOWN_LATEX_ERROR_ALIGNMENT_CHAR: c_int = 6;
+/// The code of the `EndOfInput` error kind.
+pub const PULLDOWN_LATEX_ERROR_END_OF_INPUT: c_int = 7;
+/// The code of the `DimensionArgument` error kind.
+pub const PULLDOWN_LATEX_ERROR_DIMENSION_ARGUMENT: c_int = 8;
+/// The code of the `DimensionUnit` error kind.
+pub const PULLDOWN_LATEX_ERROR_DIMENSION_UNIT: c_int = 9;
+/// The code of the `MathUnit` error kind.
+pub const PULLDOWN_LATEX_ERROR_MATH_UNIT: c_int = 10;
+/// The code of the `Delimiter` error kind.
+pub const PULLDOWN_LATEX_ERROR_DELIMITER: c_int = 11;
+/// The code of the `ControlSequence` error kind.
+pub const PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE: c_int = 12;
+/// The code of the `Number` error kind.
+pub const PULLDOWN_LATEX_ERROR_NUMBER: c_int = 13;
+/// The code of the `CharacterNumber` error kind.
+pub const PULLDOWN_LATEX_ERROR_CHARACTER_NUMBER: c_int = 14;
+/// The code of the `Argument` error kind.
+pub const PULLDOWN_LATEX_ERROR_ARGUMENT: c_int = 15;
+/// The code of the `DoubleSubscript` error kind.
+pub const PULLDOWN_LATEX_ERROR_DOUBLE_SUBSCRIPT: c_int = 16;
+/// The code of the `DoubleSuperscript` error kind.
+pub const PULLDOWN_LATEX_ERROR_DOUBLE_SUPERSCRIPT: c_int = 17;
+/// The code of the `NestingTooDeep` error kind.
+pub const PULLDOWN_LATEX_ERROR_NESTING_TOO_DEEP: c_int = 18;
+/// The code of the `EmptySubscript` error kind.
+pub const PULLDOWN_LATEX_ERROR_EMPTY_SUBSCRIPT: c_int = 19;
+/// The code of the `EmptySuperscript` error kind.
+pub const PULLDOWN_LATEX_ERROR_EMPTY_SUPERSCRIPT: c_int = 20;
+/// The code of the `UnknownPrimitive` error kind.
+pub const PULLDOWN_LATEX_ERROR_UNKNOWN_PRIMITIVE: c_int = 21;
+/// The code of the `ControlSequenceAsArgument` error kind.
+pub const PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE_AS_ARGUMENT: c_int = 22;
+/// The code of the `EmptyControlSequence` error kind.
+pub const PULLDOWN_LATEX_ERROR_EMPTY_CONTROL_SEQUENCE: c_int = 23;
+/// The code of the `UnknownColor` error kind.
+pub const PULLDOWN_LATEX_ERROR_UNKNOWN_COLOR: c_int = 24;
+/// The code of the `InvalidCharNumber` error kind.
+pub const PULLDOWN_LATEX_ERROR_INVALID_CHAR_NUMBER: c_int = 25;
+/// The code of the `Relax` error kind.
+pub const PULLDOWN_LATEX_ERROR_RELAX: c_int = 26;
+/// The code of the `MiddleOutsideLeftRight` error kind.
+pub const PULLDOWN_LATEX_ERROR_MIDDLE_OUTSIDE_LEFT_RIGHT: c_int = 27;
+/// The code of the `AmbiguousInfix` error kind.
+pub const PULLDOWN_LATEX_ERROR_AMBIGUOUS_INFIX: c_int = 28;
+/// The code of the `LimitControl` error kind.
+pub const PULLDOWN_LATEX_ERROR_LIMIT_CONTROL: c_int = 29;
+/// The code of the `UnterminatedConditional` error kind.
+pub const PULLDOWN_LATEX_ERROR_UNTERMINATED_CONDITIONAL: c_int = 30;
+/// The code of the `ConditionalEnd` error kind.
+pub const PULLDOWN_LATEX_ERROR_CONDITIONAL_END: c_int = 31;
+/// The code of the `Relation` error kind.
+pub const PULLDOWN_LATEX_ERROR_RELATION: c_int = 32;
+/// The code of the `ControlSequenceName` error kind.
+pub const PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE_NAME: c_int = 33;
+/// The code of the `ArrayColumns` error kind.
+pub const PULLDOWN_LATEX_ERROR_ARRAY_COLUMNS: c_int = 34;
+/// The code of the `DelimiterSize` error kind.
+pub const PULLDOWN_LATEX_ERROR_DELIMITER_SIZE: c_int = 35;
+/// The code of the `Untrusted` error kind.
+pub const PULLDOWN_LATEX_ERROR_UNTRUSTED: c_int = 36;
+/// The code of the `UntrustedUrl` error kind.
+pub const PULLDOWN_LATEX_ERROR_UNTRUSTED_URL: c_int = 37;
+/// The code of the `MacroParameters` error kind.
+pub const PULLDOWN_LATEX_ERROR_MACRO_PARAMETERS: c_int = 38;
+/// The code of the `MacroArguments` error kind.
+pub const PULLDOWN_LATEX_ERROR_MACRO_ARGUMENTS: c_int = 39;
+/// The code of the `MacroStorage` error kind.
+pub const PULLDOWN_LATEX_ERROR_MACRO_STORAGE: c_int = 40;
+
+/// Parse the input and render it to MathML, in display mode if `display` is non-zero.
+///
+/// On success, `*out` is set to the rendered MathML. If the input contains an error, `*out` is set
+/// to the message of the first error, and the code of its kind is returned. If the events cannot
+/// be rendered, `*out` is set to the message of the rendering error, and
+/// `PULLDOWN_LATEX_RENDER_ERROR` is returned. In all cases, the string is allocated by this
+/// library, and must be freed using `pulldown_latex_free`.
+///
+/// If `out_len` is not null, `*out_len` is set to the length of the string, in bytes, without the
+/// terminating null character.
+///
+/// # Safety
+///
+/// `input` must be a valid pointer to a null-terminated string, and `out` must be a valid pointer
+/// to write a pointer to. `out_len` must either be null, or a valid pointer to write a length to.
+#[no_mangle]
+pub unsafe extern "C" fn pulldown_latex_parse_mathml(
+    input: *const c_char,
+    display: c_int,
+    out: *mut *mut c_char,
+    out_len: *mut usize,
+) -> c_int {
+    if input.is_null() || out.is_null() {
+        return PULLDOWN_LATEX_NULL_POINTER;
+    }
+    let Ok(input) = CStr::from_ptr(input).to_str() else {
+        return PULLDOWN_LATEX_INVALID_UTF8;
+    };
+    let display_mode = if display != 0 {
+        DisplayMode::Block
+    } else {
+        DisplayMode::Inline
+    };
+    let config = ParserConfig {
+        display_mode,
+        ..Default::default()
+    };
+
+    let storage = Storage::new();
+    let events = Parser::with_storage(input, config, &storage).collect::<Result<Vec<_>, _>>();
+    let (mut output, code) = match events {
+        Ok(events) => {
+            let mut output = String::new();
+            let config = RenderConfig {
+                display_mode,
+                ..Default::default()
+            };
+            match push_mathml(
+                &mut output,
+                events.into_iter().map(Ok::<_, ParserError>),
+                config,
+            ) {
+                Ok(()) => (output, PULLDOWN_LATEX_OK),
+                Err(error) => (error.to_string(), PULLDOWN_LATEX_RENDER_ERROR),
+            }
+        }
+        Err(error) => (error.to_string(), error_code(error.kind())),
+    };
+    // A null character cannot be part of a C string, and can only come from a `\char` command.
+    output.retain(|c| c != '\0');
+    if !out_len.is_null() {
+        *out_len = output.len();
+    }
+    *out = CString::new(output)
+        .expect("null characters are removed")
+        .into_raw();
+    code
+}
+
+/// Free a string returned by this library.
+///
+/// # Safety
+///
+/// `ptr` must either be null, or a string returned by this library which is not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn pulldown_latex_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+/// The status code returned for an error of the given kind.
+fn error_code(kind: &ErrorKind) -> c_int {
+    match kind {
+        ErrorKind::UnbalancedGroup(_) => PULLDOWN_LATEX_ERROR_UNBALANCED_GROUP,
+        ErrorKind::Environment => PULLDOWN_LATEX_ERROR_ENVIRONMENT,
+        ErrorKind::ArrayColumns => PULLDOWN_LATEX_ERROR_ARRAY_COLUMNS,
+        ErrorKind::MathShift => PULLDOWN_LATEX_ERROR_MATH_SHIFT,
+        ErrorKind::UnbalancedMathShift => PULLDOWN_LATEX_ERROR_UNBALANCED_MATH_SHIFT,
+        ErrorKind::HashSign => PULLDOWN_LATEX_ERROR_HASH_SIGN,
+        ErrorKind::AlignmentChar => PULLDOWN_LATEX_ERROR_ALIGNMENT_CHAR,
+        ErrorKind::EndOfInput => PULLDOWN_LATEX_ERROR_END_OF_INPUT,
+        ErrorKind::DimensionArgument => PULLDOWN_LATEX_ERROR_DIMENSION_ARGUMENT,
+        ErrorKind::DimensionUnit => PULLDOWN_LATEX_ERROR_DIMENSION_UNIT,
+        ErrorKind::MathUnit => PULLDOWN_LATEX_ERROR_MATH_UNIT,
+        ErrorKind::Delimiter => PULLDOWN_LATEX_ERROR_DELIMITER,
+        ErrorKind::DelimiterSize => PULLDOWN_LATEX_ERROR_DELIMITER_SIZE,
+        ErrorKind::ControlSequence => PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE,
+        ErrorKind::Number => PULLDOWN_LATEX_ERROR_NUMBER,
+        ErrorKind::CharacterNumber => PULLDOWN_LATEX_ERROR_CHARACTER_NUMBER,
+        ErrorKind::Argument => PULLDOWN_LATEX_ERROR_ARGUMENT,
+        ErrorKind::DoubleSubscript => PULLDOWN_LATEX_ERROR_DOUBLE_SUBSCRIPT,
+        ErrorKind::DoubleSuperscript => PULLDOWN_LATEX_ERROR_DOUBLE_SUPERSCRIPT,
+        ErrorKind::NestingTooDeep => PULLDOWN_LATEX_ERROR_NESTING_TOO_DEEP,
+        ErrorKind::EmptySubscript => PULLDOWN_LATEX_ERROR_EMPTY_SUBSCRIPT,
+        ErrorKind::EmptySuperscript => PULLDOWN_LATEX_ERROR_EMPTY_SUPERSCRIPT,
+        ErrorKind::UnknownPrimitive => PULLDOWN_LATEX_ERROR_UNKNOWN_PRIMITIVE,
+        ErrorKind::ControlSequenceAsArgument => PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE_AS_ARGUMENT,
+        ErrorKind::EmptyControlSequence => PULLDOWN_LATEX_ERROR_EMPTY_CONTROL_SEQUENCE,
+        ErrorKind::UnknownColor => PULLDOWN_LATEX_ERROR_UNKNOWN_COLOR,
+        ErrorKind::InvalidCharNumber => PULLDOWN_LATEX_ERROR_INVALID_CHAR_NUMBER,
+        ErrorKind::Relax => PULLDOWN_LATEX_ERROR_RELAX,
+        ErrorKind::MiddleOutsideLeftRight => PULLDOWN_LATEX_ERROR_MIDDLE_OUTSIDE_LEFT_RIGHT,
+        ErrorKind::AmbiguousInfix => PULLDOWN_LATEX_ERROR_AMBIGUOUS_INFIX,
+        ErrorKind::LimitControl => PULLDOWN_LATEX_ERROR_LIMIT_CONTROL,
+        ErrorKind::UnterminatedConditional => PULLDOWN_LATEX_ERROR_UNTERMINATED_CONDITIONAL,
+        ErrorKind::ConditionalEnd => PULLDOWN_LATEX_ERROR_CONDITIONAL_END,
+        ErrorKind::Relation => PULLDOWN_LATEX_ERROR_RELATION,
+        ErrorKind::ControlSequenceName => PULLDOWN_LATEX_ERROR_CONTROL_SEQUENCE_NAME,
+        ErrorKind::Untrusted => PULLDOWN_LATEX_ERROR_UNTRUSTED,
+        ErrorKind::UntrustedUrl => PULLDOWN_LATEX_ERROR_UNTRUSTED_URL,
+        ErrorKind::MacroParameters => PULLDOWN_LATEX_ERROR_MACRO_PARAMETERS,
+        ErrorKind::MacroArguments => PULLDOWN_LATEX_ERROR_MACRO_ARGUMENTS,
+        ErrorKind::MacroStorage => PULLDOWN_LATEX_ERROR_MACRO_STORAGE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    fn parse(input: &CStr, display: c_int) -> (c_int, String) {
+        let mut out = ptr::null_mut();
+        let mut out_len = 0;
+        unsafe {
+            let code = pulldown_latex_parse_mathml(input.as_ptr(), display, &mut out, &mut out_len);
+            let output = CStr::from_ptr(out).to_str().unwrap().to_owned();
+            pulldown_latex_free(out);
+            assert_eq!(output.len(), out_len);
+            (code, output)
+        }
+    }
+
+    #[test]
+    fn parse_mathml() {
+        assert_eq!(
+            parse(c"x^2", 0),
+            (
+                PULLDOWN_LATEX_OK,
+                "<math display=\"inline\"><msup><mi>x</mi><mn>2</mn></msup></math>".to_owned()
+            )
+        );
+        let (code, output) = parse(c"x", 1);
+        assert_eq!(code, PULLDOWN_LATEX_OK);
+        assert!(output.starts_with("<math display=\"block\">"));
+        let (code, message) = parse(c"x^a^b", 0);
+        assert_eq!(code, PULLDOWN_LATEX_ERROR_DOUBLE_SUPERSCRIPT);
+        assert!(message.contains("superscript twice"));
+        let (code, output) = parse(c"\\def\\swap#1#2{#2#1}\\swap ab", 0);
+        assert_eq!(code, PULLDOWN_LATEX_OK);
+        assert!(output.contains("<mi>b</mi><mi>a</mi>"));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let mut out = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                pulldown_latex_parse_mathml(ptr::null(), 0, &mut out, ptr::null_mut()),
+                PULLDOWN_LATEX_NULL_POINTER
+            );
+            assert_eq!(
+                pulldown_latex_parse_mathml(c"\xff".as_ptr(), 0, &mut out, ptr::null_mut()),
+                PULLDOWN_LATEX_INVALID_UTF8
+            );
+            assert!(out.is_null());
+            pulldown_latex_free(out);
+        }
+    }
+}
@@ -1,8 +1,94 @@
-//! Configuration options for the renderer.
+//! Configuration options for the parser and the renderer.
 //!
-//! The configuration of the `mathml` renderer is done through the [`RenderConfig`] struct.
+//! The configuration of the [`Parser`] is done through the [`ParserConfig`] struct, and the
+//! configuration of the `mathml` renderer is done through the [`RenderConfig`] struct.
+//!
+//! [`Parser`]: crate::parser::Parser
 use std::fmt::Display;
 
+/// Configuration for the [`Parser`].
+///
+/// The default value is: [`ParserConfig::default`].
+///
+/// [`Parser`]: crate::parser::Parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// See [`DisplayMode`].
+    ///
+    /// When set to [`DisplayMode::Block`], the input is parsed in `\displaystyle`, which affects
+    /// commands such as `\mathchoice` and the spacing of `\pmod`. No style change is emitted for
+    /// the whole input: renderers place the limits of big operators such as `\sum` above and
+    /// below them in display mode (see [`RenderConfig::display_mode`]). Integrals keep their
+    /// limits on their right, unless `\limits` is used.
+    pub display_mode: DisplayMode,
+    /// See [`ErrorPolicy`].
+    pub on_unknown_command: ErrorPolicy,
+    /// Whether non-breaking spaces (`~`, `\nobreakspace` and `\ `) are output as the `&nbsp;`
+    /// HTML entity, instead of the U+00A0 character.
+    ///
+    /// The entity is only understood by HTML documents, this option exists for compatibility with
+    /// previous versions of the parser.
+    pub nbsp_entity: bool,
+    /// The maximum number of nested groups, including the arguments of commands, that the input
+    /// can contain.
+    ///
+    /// A group nested deeper than this is reported as an error. This bounds the nesting of the
+    /// events, which renderers may handle recursively, when parsing untrusted input.
+    pub max_nesting_depth: usize,
+    /// Whether commands which attach HTML attributes to their content (`\htmlClass`, `\htmlId`
+    /// and `\htmlStyle`) are allowed, along with `\href` links to URLs whose protocol is neither
+    /// `http`, `https`, nor `mailto` (e.g., `javascript:`).
+    ///
+    /// These let the input inject attributes and scripts in the rendered document, so they should
+    /// only be allowed for trusted input. When disabled, they are reported as an error.
+    pub trust: bool,
+}
+
+impl Default for ParserConfig {
+    /// # Default Value
+    /// ```rust
+    /// # use pulldown_latex::config::{ParserConfig, DisplayMode, ErrorPolicy};
+    /// const DEFAULT: ParserConfig = ParserConfig {
+    ///     display_mode: DisplayMode::Inline,
+    ///     on_unknown_command: ErrorPolicy::Fail,
+    ///     nbsp_entity: false,
+    ///     max_nesting_depth: 256,
+    ///     trust: false,
+    /// };
+    /// assert_eq!(ParserConfig::default(), DEFAULT);
+    /// ```
+    fn default() -> Self {
+        Self {
+            display_mode: DisplayMode::Inline,
+            on_unknown_command: ErrorPolicy::Fail,
+            nbsp_entity: false,
+            max_nesting_depth: 256,
+            trust: false,
+        }
+    }
+}
+
+/// What the [`Parser`] does when it encounters a command that it does not know.
+///
+/// [`Parser`]: crate::parser::Parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// The parser outputs an error.
+    ///
+    /// __This is the default value.__
+    #[default]
+    Fail,
+    /// The command is ignored, and parsing continues after it.
+    Skip,
+    /// The parser outputs an [`Event::UnknownCommand`] event, which a renderer can render as an
+    /// error marker.
+    ///
+    /// [`Event::UnknownCommand`]: crate::event::Event::UnknownCommand
+    EmitPlaceholder,
+    /// The command is outputed as an identifier named after it (e.g., `\foo` becomes `foo`).
+    EmitIdentifier,
+}
+
 /// Configuration for the `mathml` renderer.
 ///
 /// The default value is: [`RenderConfig::default`].
@@ -21,6 +107,11 @@ pub struct RenderConfig<'a> {
     /// This namespace is unnecessary for modern browsers but can be helpful for other user agents,
     /// such as Microsoft Word.
     pub xml: bool,
+    /// If Some, the `id` attribute of the `<math>` element.
+    pub id: Option<&'a str>,
+    /// If Some, the `class` attribute of the `<math>` element, e.g., to style the equations of a
+    /// document with CSS.
+    pub class: Option<&'a str>,
     /// See [`MathStyle`].
     pub math_style: MathStyle,
 }
@@ -44,6 +135,8 @@ impl<'a> Default for RenderConfig<'a> {
     ///     annotation: None,
     ///     error_color: (178, 34, 34),
     ///     xml: false,
+    ///     id: None,
+    ///     class: None,
     ///     math_style: MathStyle::TeX,
     /// };
     /// assert_eq!(RenderConfig::default(), DEFAULT);
@@ -54,6 +147,8 @@ impl<'a> Default for RenderConfig<'a> {
             annotation: None,
             error_color: (178, 34, 34),
             xml: false,
+            id: None,
+            class: None,
             math_style: MathStyle::TeX,
         }
     }
@@ -16,8 +16,10 @@ use crate::attribute::{Dimension, Font};
 /// When an [`Event`] is referreing to an "_element_", it is referring to the next logical unit of
 /// content in the stream. This can be a single content element, a group, a visual element, etc.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event<'a> {
     /// The event is a [`Content`] element.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     Content(Content<'a>),
     /// The events following this one constitute a "group" which counts as a single _element_
     /// (i.e., a set of elements within `{}` in `LaTeX`), until the [`Event::EndGroup`] event
@@ -46,6 +48,7 @@ pub enum Event<'a> {
     /// This event specifies a state change in the renderer.
     ///
     /// This state change only applies to the current group nesting and deeper groups.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     StateChange(StateChange<'a>),
     /// This event specifies an alignment mark in a mathematical environment.
     /// 
@@ -55,12 +58,55 @@ pub enum Event<'a> {
     ///
     /// This event is only used when inside a `Grouping` that allows it.
     NewLine,
+    /// A command (without its leading backslash) that the parser does not know.
+    ///
+    /// This event is only produced when the parser is configured to do so, using
+    /// [`ErrorPolicy::EmitPlaceholder`].
+    ///
+    /// [`ErrorPolicy::EmitPlaceholder`]: crate::config::ErrorPolicy::EmitPlaceholder
+    UnknownCommand(&'a str),
+    /// The element following this one is annotated with an HTML attribute, as produced by
+    /// `\htmlClass`, `\htmlId` and `\htmlStyle`.
+    ///
+    /// This event is only produced when the parser is configured to do so, using
+    /// [`ParserConfig::trust`].
+    ///
+    /// [`ParserConfig::trust`]: crate::config::ParserConfig::trust
+    Annotation {
+        kind: AnnotationKind,
+        value: &'a str,
+    },
+    /// The element following this one is a link to the given URL, as produced by `\href`.
+    ///
+    /// URLs whose protocol is neither `http`, `https`, nor `mailto` (e.g., `javascript:`) are
+    /// only produced when the parser is configured to do so, using [`ParserConfig::trust`].
+    ///
+    /// [`ParserConfig::trust`]: crate::config::ParserConfig::trust
+    Link { url: &'a str },
+}
+
+/// The HTML attribute set by an [`Event::Annotation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnnotationKind {
+    /// The `class` attribute (`\htmlClass`).
+    Class,
+    /// The `id` attribute (`\htmlId`).
+    Id,
+    /// The `style` attribute (`\htmlStyle`).
+    Style,
 }
 
 /// Base events that produce `mathml` nodes
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Content<'a> {
-    /// Text content that should be typeset following the rules of `LaTeX`'s `text` mode.
+    /// Text content, as read in `LaTeX`'s `text` mode (e.g., with its runs of spaces collapsed,
+    /// and its accents composed).
+    ///
+    /// Without a [`Storage`](crate::parser::Storage), a text which is not written as is in the
+    /// input is split into consecutive events, grouped in a [`Grouping::Internal`] when they
+    /// form a single element.
     Text(&'a str),
     /// A number, which can include decimal points and commas.
     Number(&'a str),
@@ -69,6 +115,7 @@ pub enum Content<'a> {
     /// If the identifier is a single character, then the character follows the typesetting rules
     /// of single character variables. If the identifier is a string, even if that string is a
     /// single character, it is typeset as a function name.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     Identifier(Identifier<'a>),
     /// A mathematical operator.
     ///
@@ -134,6 +181,7 @@ pub enum Content<'a> {
 /// > braces, parentheses, and "absolute value" bars; separators such as comma and semicolon; and
 /// > mathematical accents such as a bar or tilde over a symbol.
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Operator {
     /// The operator's unicode content.
     pub content: char,
@@ -168,6 +216,7 @@ pub struct Operator {
 /// An identifier can either be a single character, or a string (e.g., a command such as `sin`,
 /// `lim`, etc.).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Identifier<'a> {
     Str(&'a str),
     Char(char),
@@ -175,6 +224,7 @@ pub enum Identifier<'a> {
 
 /// Modifies the visual representation of the following event(s)
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Visual {
     /// The following element is the content of the root.
     SquareRoot,
@@ -185,6 +235,18 @@ pub enum Visual {
     /// If the content of the variant is `None`, then the size of the middle line is set to the
     /// default size, otherwise the size is set to the specified size.
     Fraction(Option<Dimension>),
+    /// The 2 following elements are the first and second line of a fraction split across two
+    /// lines, as produced by `\splitfrac`.
+    ///
+    /// There is no line between the two elements, the first one is aligned to the left, and the
+    /// second one is aligned to the right.
+    SplitFraction,
+    /// The 2 following elements are the numerator and denominator of a continued fraction, as
+    /// produced by `\cfrac`.
+    ///
+    /// Both elements are parsed in display style, and the numerator is aligned inside of the fraction
+    /// following the given alignment.
+    ContinuedFraction(BoxAlignment),
     /// The "negation" operator as in "not equal" (≠) or "does not exist" (∄). This applies to the
     /// next event in the stream.
     /// 
@@ -193,6 +255,70 @@ pub enum Visual {
     /// generates an akward looking negation across the next element, when it does not correspond
     /// to a commonly negated element.
     Negation,
+    /// The 2 following elements are the base and the accent placed above it, as produced by
+    /// commands such as `\hat` or `\dot`.
+    ///
+    /// Accents are placed closer to their base than superscripts placed above the base, which
+    /// allows multiple accents on the same base to be stacked (e.g., `\dot{\hat{q}}`).
+    Accent,
+    /// The following element is drawn inside of a box, as produced by `\boxed`.
+    Boxed,
+    /// The following element is drawn inside of a frame, as produced by `\fbox` and `\framebox`.
+    ///
+    /// If the width of the frame is specified, the content is aligned inside of the frame
+    /// following the given alignment.
+    Framed {
+        width: Option<Dimension>,
+        alignment: BoxAlignment,
+    },
+    /// The following element is rendered in a box of zero width, such that it overlaps the
+    /// content around it, as produced by `\rlap`, `\llap`, `\clap` and their `\math...`
+    /// variants.
+    Lap(LapDirection),
+    /// The following element is shifted upwards by the given dimension, or downwards if the
+    /// dimension is negative, as produced by `\raise`, `\lower` and `\raisebox`.
+    Raise(Dimension),
+    /// The following element is vertically centered on the math axis, as produced by
+    /// `\vcenter`.
+    VCenter,
+    /// The following element is struck out by one or more lines, as produced by `\cancel`,
+    /// `\bcancel`, `\xcancel` and `\sout`.
+    Strike(StrikeKind),
+}
+
+/// The horizontal alignment of content inside of a box that is wider than it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoxAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// The direction towards which the content of a `Lap` overlaps its surroundings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LapDirection {
+    /// The content overlaps the content to its left (`\llap`).
+    Left,
+    /// The content overlaps the content to its right (`\rlap`).
+    Right,
+    /// The content is centered, and overlaps on both sides (`\clap`).
+    Center,
+}
+
+/// The lines striking out the content of a `Strike`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StrikeKind {
+    /// A line from the bottom left to the top right corner (`\cancel`).
+    UpDiagonal,
+    /// A line from the top left to the bottom right corner (`\bcancel`).
+    DownDiagonal,
+    /// Both diagonal lines (`\xcancel`).
+    Cross,
+    /// A horizontal line through the middle (`\sout`).
+    Horizontal,
 }
 
 /// Logical type of the script. This is used to determine how to render the scripts.
@@ -200,6 +326,7 @@ pub enum Visual {
 /// Things like subscripts, underscripts, and movable scripts can be represented when using this
 /// `enum` in conjunction with the [`ScriptPosition`] `enum`.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScriptType {
     /// The 2 following elements are the base and and the subscript
     Subscript,
@@ -211,6 +338,7 @@ pub enum ScriptType {
 
 /// Position of the script. This is used to determine how to render the scripts.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScriptPosition {
     /// The scripts are rendered to the (bottom and top) right of the operator.
     Right,
@@ -221,12 +349,24 @@ pub enum ScriptPosition {
     ///
     /// This is used by the `lim` and `sum` (Σ) operators for example.
     Movable,
+    /// The scripts are rendered to the (bottom and top) left of the base, as prescripts.
+    ///
+    /// This is produced by scripts attached to an empty group followed by the base, as in
+    /// `{}^{14}_6\mathrm{C}`.
+    Prescript,
+    /// The scripts are rendered to the right of the base, after the scripts the base already has,
+    /// as the staggered indices of a tensor.
+    ///
+    /// The base of such scripts is itself an element with scripts. This is produced by scripts
+    /// attached to an empty group following scripts, as in `T^a{}_b{}^c`.
+    Tensor,
 }
 
 /// Represents a state change for the following content.
 ///
 /// State changes take effect for the current group nesting and all deeper groups.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StateChange<'a> {
     /// Changes the font of the content.
     ///
@@ -234,9 +374,45 @@ pub enum StateChange<'a> {
     /// the specified font.
     Font(Option<Font>),
     /// Changes the color of the content.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     Color(ColorChange<'a>),
     /// Changes the style of the content (mostly affects the sizing of the content).
     Style(Style),
+    /// Changes the current style to its cramped variant, where superscripts are raised less than
+    /// usual (TeXbook p. 140).
+    ///
+    /// This is produced by the `\cramped` command, and stays in effect when the style is changed
+    /// within the same group.
+    Cramped,
+    /// Changes the font of the text in the content, e.g., `\textbf`.
+    ///
+    /// Text fonts do not affect math content, and compose with the text fonts of the enclosing
+    /// groups, e.g., `\textbf{\textit{x}}` is both bold and italic.
+    TextFont(TextFont),
+}
+
+/// A text font, which changes either the series, the shape, or the family of the text.
+///
+/// A text font only replaces the fonts that change the same attribute, e.g., `Italic` replaces
+/// `SmallCaps` but not `Bold`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextFont {
+    /// A bold series (`\textbf`).
+    Bold,
+    /// An italic shape (`\textit`).
+    Italic,
+    /// A small capitals shape (`\textsc`).
+    SmallCaps,
+    /// A roman (serif) family (`\textrm`).
+    Roman,
+    /// A sans serif family (`\textsf`).
+    SansSerif,
+    /// A monospace family (`\texttt`).
+    Monospace,
+    /// An emphasized shape (`\emph`), which is italic, unless the enclosing text is already
+    /// italic, in which case it is upright.
+    Emphasis,
 }
 
 /// The style of the content.
@@ -244,6 +420,7 @@ pub enum StateChange<'a> {
 /// This is analogous to the different "modes" in `LaTeX`, such as `display`, `text`, etc., which
 /// are set by commands like `\displaystyle`, `\textstyle`, etc.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Style {
     Display,
     Text,
@@ -253,6 +430,7 @@ pub enum Style {
 
 /// Represents a color change.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorChange<'a> {
     /// The color to change to.
     ///
@@ -267,6 +445,7 @@ pub struct ColorChange<'a> {
 
 /// The target of the color change.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorTarget {
     /// The text of the content.
     Text,
@@ -277,13 +456,282 @@ pub enum ColorTarget {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Grouping {
     Internal,
     Normal,
     // TODO: make LeftRight own its delimiters, when changing the Event API.
     LeftRight,
-    Array,
+    /// An `array` environment, with the columns given by its column specification.
+    Array(ArrayColumns),
     Matrix,
     Cases,
     Align,
+    /// The group contains two operators along with their limits, as produced by
+    /// `\adjustlimits`. The limits of both operators should be rendered above and below them,
+    /// and aligned with each other.
+    AdjustLimits,
+    /// The group forms a single atom of the given class, as produced by `\mathbin`, `\mathrel`,
+    /// etc. The content should be spaced according to the class of the atom.
+    AtomClass(AtomClass),
+}
+
+/// The columns of an `array` environment, as given by its column specification (e.g., `{l|cr}`).
+///
+/// The columns are stored inline, so that events remain `Copy`, which limits their number to
+/// [`ArrayColumns::CAPACITY`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArrayColumns {
+    columns: [ArrayColumn; ArrayColumns::CAPACITY],
+    len: u8,
+}
+
+impl ArrayColumns {
+    /// The maximum number of columns and vertical lines in a column specification.
+    pub const CAPACITY: usize = 24;
+
+    /// Create a column specification from the given columns and vertical lines, or return `None`
+    /// if there are more than [`ArrayColumns::CAPACITY`] of them.
+    pub fn new(columns: &[ArrayColumn]) -> Option<Self> {
+        let mut array = [ArrayColumn::VerticalLine; Self::CAPACITY];
+        array.get_mut(..columns.len())?.copy_from_slice(columns);
+        Some(Self {
+            columns: array,
+            len: columns.len() as u8,
+        })
+    }
+
+    /// The columns and vertical lines, from left to right.
+    pub fn as_slice(&self) -> &[ArrayColumn] {
+        &self.columns[..self.len as usize]
+    }
+}
+
+/// An element of the column specification of an `array` environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArrayColumn {
+    /// A column, whose cells are aligned as given (`l`, `c`, or `r`).
+    Column(ColumnAlignment),
+    /// A vertical line between columns, or on the side of the array (`|`).
+    VerticalLine,
+}
+
+/// The alignment of the cells of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColumnAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// The class of an atom, which determines the spacing surrounding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AtomClass {
+    /// An ordinary atom, e.g., `x` (`\mathord`).
+    Ordinary,
+    /// A large operator, e.g., `\sum` (`\mathop`).
+    Operator,
+    /// A binary operator, e.g., `+` (`\mathbin`).
+    Binary,
+    /// A relation, e.g., `=` (`\mathrel`).
+    Relation,
+    /// An opening delimiter, e.g., `(` (`\mathopen`).
+    Opening,
+    /// A closing delimiter, e.g., `)` (`\mathclose`).
+    Closing,
+    /// A punctuation mark, e.g., `,` (`\mathpunct`).
+    Punctuation,
+    /// An inner atom, e.g., a `\left ... \right` group (`\mathinner`).
+    Inner,
+}
+
+/// An owned version of [`Event`], which does not borrow from the input.
+///
+/// This allows for storing the events produced by the [`Parser`] after the input it has parsed is
+/// dropped. It can be created from an [`Event`] using [`Event::into_owned`], and an [`Event`] can
+/// be borrowed from it using [`OwnedEvent::as_event`], e.g., to render it.
+///
+/// [`Parser`]: crate::parser::Parser
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedEvent {
+    /// See [`Event::Content`].
+    Content(OwnedContent),
+    /// See [`Event::Begin`].
+    Begin(Grouping),
+    /// See [`Event::End`].
+    End,
+    /// See [`Event::Visual`].
+    Visual(Visual),
+    /// See [`Event::Script`].
+    Script {
+        ty: ScriptType,
+        position: ScriptPosition,
+    },
+    /// See [`Event::Space`].
+    Space {
+        width: Option<Dimension>,
+        height: Option<Dimension>,
+        depth: Option<Dimension>,
+    },
+    /// See [`Event::StateChange`].
+    StateChange(OwnedStateChange),
+    /// See [`Event::Alignment`].
+    Alignment,
+    /// See [`Event::NewLine`].
+    NewLine,
+    /// See [`Event::UnknownCommand`].
+    UnknownCommand(String),
+    /// See [`Event::Annotation`].
+    Annotation {
+        kind: AnnotationKind,
+        value: String,
+    },
+    /// See [`Event::Link`].
+    Link { url: String },
+}
+
+/// An owned version of [`Content`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedContent {
+    Text(String),
+    Number(String),
+    Identifier(OwnedIdentifier),
+    Operator(Operator),
+}
+
+/// An owned version of [`Identifier`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedIdentifier {
+    Str(String),
+    Char(char),
+}
+
+/// An owned version of [`StateChange`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedStateChange {
+    Font(Option<Font>),
+    /// The color change, with the color as an owned string, and its target.
+    Color {
+        color: String,
+        target: ColorTarget,
+    },
+    Style(Style),
+    Cramped,
+    TextFont(TextFont),
+}
+
+impl Event<'_> {
+    /// Convert the event into an [`OwnedEvent`], by cloning the strings it borrows.
+    pub fn into_owned(self) -> OwnedEvent {
+        match self {
+            Event::Content(content) => OwnedEvent::Content(match content {
+                Content::Text(text) => OwnedContent::Text(text.to_owned()),
+                Content::Number(number) => OwnedContent::Number(number.to_owned()),
+                Content::Identifier(Identifier::Str(str)) => {
+                    OwnedContent::Identifier(OwnedIdentifier::Str(str.to_owned()))
+                }
+                Content::Identifier(Identifier::Char(char)) => {
+                    OwnedContent::Identifier(OwnedIdentifier::Char(char))
+                }
+                Content::Operator(operator) => OwnedContent::Operator(operator),
+            }),
+            Event::Begin(grouping) => OwnedEvent::Begin(grouping),
+            Event::End => OwnedEvent::End,
+            Event::Visual(visual) => OwnedEvent::Visual(visual),
+            Event::Script { ty, position } => OwnedEvent::Script { ty, position },
+            Event::Space {
+                width,
+                height,
+                depth,
+            } => OwnedEvent::Space {
+                width,
+                height,
+                depth,
+            },
+            Event::StateChange(state_change) => OwnedEvent::StateChange(match state_change {
+                StateChange::Font(font) => OwnedStateChange::Font(font),
+                StateChange::Color(ColorChange { color, target }) => OwnedStateChange::Color {
+                    color: color.to_owned(),
+                    target,
+                },
+                StateChange::Style(style) => OwnedStateChange::Style(style),
+                StateChange::Cramped => OwnedStateChange::Cramped,
+                StateChange::TextFont(font) => OwnedStateChange::TextFont(font),
+            }),
+            Event::Alignment => OwnedEvent::Alignment,
+            Event::NewLine => OwnedEvent::NewLine,
+            Event::UnknownCommand(name) => OwnedEvent::UnknownCommand(name.to_owned()),
+            Event::Annotation { kind, value } => OwnedEvent::Annotation {
+                kind,
+                value: value.to_owned(),
+            },
+            Event::Link { url } => OwnedEvent::Link {
+                url: url.to_owned(),
+            },
+        }
+    }
+}
+
+impl OwnedEvent {
+    /// Borrow the owned event as an [`Event`].
+    pub fn as_event(&self) -> Event<'_> {
+        match self {
+            OwnedEvent::Content(content) => Event::Content(match content {
+                OwnedContent::Text(text) => Content::Text(text),
+                OwnedContent::Number(number) => Content::Number(number),
+                OwnedContent::Identifier(OwnedIdentifier::Str(str)) => {
+                    Content::Identifier(Identifier::Str(str))
+                }
+                OwnedContent::Identifier(OwnedIdentifier::Char(char)) => {
+                    Content::Identifier(Identifier::Char(*char))
+                }
+                OwnedContent::Operator(operator) => Content::Operator(*operator),
+            }),
+            OwnedEvent::Begin(grouping) => Event::Begin(*grouping),
+            OwnedEvent::End => Event::End,
+            OwnedEvent::Visual(visual) => Event::Visual(*visual),
+            OwnedEvent::Script { ty, position } => Event::Script {
+                ty: *ty,
+                position: *position,
+            },
+            OwnedEvent::Space {
+                width,
+                height,
+                depth,
+            } => Event::Space {
+                width: *width,
+                height: *height,
+                depth: *depth,
+            },
+            OwnedEvent::StateChange(state_change) => Event::StateChange(match state_change {
+                OwnedStateChange::Font(font) => StateChange::Font(*font),
+                OwnedStateChange::Color { color, target } => StateChange::Color(ColorChange {
+                    color,
+                    target: *target,
+                }),
+                OwnedStateChange::Style(style) => StateChange::Style(*style),
+                OwnedStateChange::Cramped => StateChange::Cramped,
+                OwnedStateChange::TextFont(font) => StateChange::TextFont(*font),
+            }),
+            OwnedEvent::Alignment => Event::Alignment,
+            OwnedEvent::NewLine => Event::NewLine,
+            OwnedEvent::UnknownCommand(name) => Event::UnknownCommand(name),
+            OwnedEvent::Annotation { kind, value } => Event::Annotation { kind: *kind, value },
+            OwnedEvent::Link { url } => Event::Link { url },
+        }
+    }
+}
+
+impl From<Event<'_>> for OwnedEvent {
+    fn from(event: Event<'_>) -> Self {
+        event.into_owned()
+    }
 }
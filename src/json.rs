@@ -0,0 +1,431 @@
+//! A JSON encoding of the event stream.
+//!
+//! The events are written as a JSON array, one object per event, as they are consumed, without
+//! collecting them first. This makes it possible to render the events with tools written in other
+//! languages, e.g., by piping the output to a JavaScript program.
+//!
+//! Unlike the `serde` implementations of the events, the encoding is compact and does not follow
+//! the layout of the Rust types. It is stable, and described below.
+//!
+//! # Format
+//!
+//! Each object has a `"type"` key, which is the name of the [`Event`] variant, and the other keys
+//! depend on the type:
+//!
+//! | `"type"`           | Other keys                                                          |
+//! | ------------------ | ------------------------------------------------------------------- |
+//! | `"Content"`        | `"variant"`: `"Text"`, `"Number"`, `"Identifier"`, `"Function"` (an identifier made of a string, e.g., `sin`), or `"Operator"`; `"value"`: the content as a string. |
+//! | `"Begin"`          | `"grouping"`: the name of the [`Grouping`]; `"columns"`: the column specification of an `"Array"` (e.g., `"l|cr"`); `"class"`: the class of an `"AtomClass"`. |
+//! | `"End"`            |                                                                     |
+//! | `"Visual"`         | `"variant"`: the name of the [`Visual`]; `"value"`: its content, if any, except for `"Framed"`, which has `"width"` and `"alignment"`. |
+//! | `"Script"`         | `"variant"`: the name of the [`ScriptType`]; `"position"`: the name of the [`ScriptPosition`]. |
+//! | `"Space"`          | `"width"`, `"height"`, and `"depth"`, when they are specified.      |
+//! | `"StateChange"`    | `"variant"`: the name of the [`StateChange`]; `"value"`: its content, if any (`null` for the default font), and `"target"` for a `"Color"`. |
+//! | `"Alignment"`      |                                                                     |
+//! | `"NewLine"`        |                                                                     |
+//! | `"UnknownCommand"` | `"value"`: the name of the command.                                 |
+//! | `"Annotation"`     | `"variant"`: the name of the [`AnnotationKind`]; `"value"`: the value of the attribute. |
+//! | `"Link"`           | `"url"`: the URL of the link.                                       |
+//! | `"Error"`          | `"message"`: the message of an error in the events.                 |
+//!
+//! Operators also have the fields of the [`Operator`] which are not set to their default value,
+//! using the names of the fields (e.g., `"stretchy": false`). Dimensions are written as strings
+//! with their unit (e.g., `"0.5em"`), and the values of enums as the names of their variants.
+//!
+//! ```rust
+//! use pulldown_latex::{json::push_json, Parser};
+//!
+//! let mut json = String::new();
+//! push_json(&mut json, Parser::new(r"x^2")).unwrap();
+//! assert_eq!(
+//!     json,
+//!     "[{\"type\":\"Script\",\"variant\":\"Superscript\",\"position\":\"Right\"},\
+//!      {\"type\":\"Content\",\"variant\":\"Identifier\",\"value\":\"x\"},\
+//!      {\"type\":\"Content\",\"variant\":\"Number\",\"value\":\"2\"}]"
+//! );
+//! ```
+
+use std::{fmt, io};
+
+use crate::{
+    attribute::Dimension,
+    event::{
+        AnnotationKind, ArrayColumn, ColorChange, ColumnAlignment, Content, Event, Grouping,
+        Identifier, Operator, StateChange, Visual,
+    },
+    mathml::FmtWriter,
+};
+
+struct JsonWriter<W> {
+    writer: W,
+}
+
+impl<W: io::Write> JsonWriter<W> {
+    fn write<'a, I, E>(&mut self, events: I) -> io::Result<()>
+    where
+        I: Iterator<Item = Result<Event<'a>, E>>,
+        E: fmt::Display,
+    {
+        self.writer.write_all(b"[")?;
+        for (index, event) in events.enumerate() {
+            if index > 0 {
+                self.writer.write_all(b",")?;
+            }
+            match event {
+                Ok(event) => self.write_event(event)?,
+                Err(error) => {
+                    self.open("Error")?;
+                    self.field("message", &error.to_string())?;
+                }
+            }
+            self.writer.write_all(b"}")?;
+        }
+        self.writer.write_all(b"]")
+    }
+
+    /// Open the object of an event of the given type, which is closed by the caller.
+    fn open(&mut self, ty: &str) -> io::Result<()> {
+        write!(self.writer, "{{\"type\":\"{ty}\"")
+    }
+
+    /// Write a field whose value is a string.
+    fn field(&mut self, name: &str, value: &str) -> io::Result<()> {
+        write!(self.writer, ",\"{name}\":")?;
+        self.string(value)
+    }
+
+    /// Write a field whose value is the name of a variant, or the JSON representation of a
+    /// number or a boolean.
+    fn raw_field(&mut self, name: &str, value: impl fmt::Debug) -> io::Result<()> {
+        write!(self.writer, ",\"{name}\":{value:?}")
+    }
+
+    fn variant_field(&mut self, name: &str, value: impl fmt::Debug) -> io::Result<()> {
+        write!(self.writer, ",\"{name}\":\"{value:?}\"")
+    }
+
+    fn dimension_field(&mut self, name: &str, (value, unit): Dimension) -> io::Result<()> {
+        write!(self.writer, ",\"{name}\":\"{value}{unit}\"")
+    }
+
+    fn string(&mut self, value: &str) -> io::Result<()> {
+        self.writer.write_all(b"\"")?;
+        let mut rest = value;
+        while let Some(index) = rest.find(|c: char| matches!(c, '"' | '\\') || c.is_control()) {
+            self.writer.write_all(&rest.as_bytes()[..index])?;
+            let c = rest[index..]
+                .chars()
+                .next()
+                .expect("the index is a character boundary");
+            match c {
+                '"' => self.writer.write_all(b"\\\"")?,
+                '\\' => self.writer.write_all(b"\\\\")?,
+                '\n' => self.writer.write_all(b"\\n")?,
+                '\t' => self.writer.write_all(b"\\t")?,
+                c => write!(self.writer, "\\u{:04x}", c as u32)?,
+            }
+            rest = &rest[index + c.len_utf8()..];
+        }
+        self.writer.write_all(rest.as_bytes())?;
+        self.writer.write_all(b"\"")
+    }
+
+    fn write_event(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::Content(content) => {
+                self.open("Content")?;
+                match content {
+                    Content::Text(text) => {
+                        self.field("variant", "Text")?;
+                        self.field("value", text)
+                    }
+                    Content::Number(number) => {
+                        self.field("variant", "Number")?;
+                        self.field("value", number)
+                    }
+                    Content::Identifier(Identifier::Char(c)) => {
+                        self.field("variant", "Identifier")?;
+                        self.field("value", c.encode_utf8(&mut [0; 4]))
+                    }
+                    Content::Identifier(Identifier::Str(name)) => {
+                        self.field("variant", "Function")?;
+                        self.field("value", name)
+                    }
+                    Content::Operator(operator) => self.write_operator(operator),
+                }
+            }
+            Event::Begin(grouping) => {
+                self.open("Begin")?;
+                match grouping {
+                    Grouping::Array(columns) => {
+                        self.field("grouping", "Array")?;
+                        let spec: String = columns
+                            .as_slice()
+                            .iter()
+                            .map(|column| match column {
+                                ArrayColumn::Column(ColumnAlignment::Left) => 'l',
+                                ArrayColumn::Column(ColumnAlignment::Center) => 'c',
+                                ArrayColumn::Column(ColumnAlignment::Right) => 'r',
+                                ArrayColumn::VerticalLine => '|',
+                            })
+                            .collect();
+                        self.field("columns", &spec)
+                    }
+                    Grouping::AtomClass(class) => {
+                        self.field("grouping", "AtomClass")?;
+                        self.variant_field("class", class)
+                    }
+                    grouping => self.variant_field("grouping", grouping),
+                }
+            }
+            Event::End => self.open("End"),
+            Event::Visual(visual) => {
+                self.open("Visual")?;
+                self.write_visual(visual)
+            }
+            Event::Script { ty, position } => {
+                self.open("Script")?;
+                self.variant_field("variant", ty)?;
+                self.variant_field("position", position)
+            }
+            Event::Space {
+                width,
+                height,
+                depth,
+            } => {
+                self.open("Space")?;
+                for (name, dimension) in [("width", width), ("height", height), ("depth", depth)] {
+                    if let Some(dimension) = dimension {
+                        self.dimension_field(name, dimension)?;
+                    }
+                }
+                Ok(())
+            }
+            Event::StateChange(state_change) => {
+                self.open("StateChange")?;
+                match state_change {
+                    StateChange::Font(font) => {
+                        self.field("variant", "Font")?;
+                        match font {
+                            Some(font) => self.variant_field("value", font),
+                            None => self.writer.write_all(b",\"value\":null"),
+                        }
+                    }
+                    StateChange::Color(ColorChange { color, target }) => {
+                        self.field("variant", "Color")?;
+                        self.field("value", color)?;
+                        self.variant_field("target", target)
+                    }
+                    StateChange::Style(style) => {
+                        self.field("variant", "Style")?;
+                        self.variant_field("value", style)
+                    }
+                    StateChange::Cramped => self.field("variant", "Cramped"),
+                    StateChange::TextFont(font) => {
+                        self.field("variant", "TextFont")?;
+                        self.variant_field("value", font)
+                    }
+                }
+            }
+            Event::Alignment => self.open("Alignment"),
+            Event::NewLine => self.open("NewLine"),
+            Event::UnknownCommand(name) => {
+                self.open("UnknownCommand")?;
+                self.field("value", name)
+            }
+            Event::Annotation { kind, value } => {
+                self.open("Annotation")?;
+                self.field(
+                    "variant",
+                    match kind {
+                        AnnotationKind::Class => "Class",
+                        AnnotationKind::Id => "Id",
+                        AnnotationKind::Style => "Style",
+                    },
+                )?;
+                self.field("value", value)
+            }
+            Event::Link { url } => {
+                self.open("Link")?;
+                self.field("url", url)
+            }
+        }
+    }
+
+    fn write_operator(&mut self, operator: Operator) -> io::Result<()> {
+        let Operator {
+            content,
+            stretchy,
+            deny_movable_limits,
+            unicode_variant,
+            left_space,
+            right_space,
+            size,
+        } = operator;
+        self.field("variant", "Operator")?;
+        self.field("value", content.encode_utf8(&mut [0; 4]))?;
+        if let Some(stretchy) = stretchy {
+            self.raw_field("stretchy", stretchy)?;
+        }
+        if deny_movable_limits {
+            self.raw_field("deny_movable_limits", true)?;
+        }
+        if unicode_variant {
+            self.raw_field("unicode_variant", true)?;
+        }
+        for (name, dimension) in [
+            ("left_space", left_space),
+            ("right_space", right_space),
+            ("size", size),
+        ] {
+            if let Some(dimension) = dimension {
+                self.dimension_field(name, dimension)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_visual(&mut self, visual: Visual) -> io::Result<()> {
+        match visual {
+            Visual::Fraction(bar) => {
+                self.field("variant", "Fraction")?;
+                match bar {
+                    Some(bar) => self.dimension_field("value", bar),
+                    None => Ok(()),
+                }
+            }
+            Visual::ContinuedFraction(alignment) => {
+                self.field("variant", "ContinuedFraction")?;
+                self.variant_field("value", alignment)
+            }
+            Visual::Framed { width, alignment } => {
+                self.field("variant", "Framed")?;
+                if let Some(width) = width {
+                    self.dimension_field("width", width)?;
+                }
+                self.variant_field("alignment", alignment)
+            }
+            Visual::Lap(direction) => {
+                self.field("variant", "Lap")?;
+                self.variant_field("value", direction)
+            }
+            Visual::Raise(dimension) => {
+                self.field("variant", "Raise")?;
+                self.dimension_field("value", dimension)
+            }
+            Visual::Strike(kind) => {
+                self.field("variant", "Strike")?;
+                self.variant_field("value", kind)
+            }
+            Visual::SquareRoot
+            | Visual::Root
+            | Visual::SplitFraction
+            | Visual::Negation
+            | Visual::Accent
+            | Visual::Boxed
+            | Visual::VCenter => self.variant_field("variant", visual),
+        }
+    }
+}
+
+/// Takes a [`Parser`], or any `Iterator<Item = Result<Event<'_>, E>>`, as input and writes the
+/// JSON encoding of the events into the given [`fmt::Write`], such as a `&mut String`.
+///
+/// Errors in the events are written as objects of type `"Error"`, and the events following them
+/// are still written. See the [module documentation](self) for the format.
+///
+/// [`Parser`]: crate::parser::Parser
+pub fn push_json<'a, W, I, E>(writer: W, parser: I) -> fmt::Result
+where
+    W: fmt::Write,
+    I: Iterator<Item = Result<Event<'a>, E>>,
+    E: fmt::Display,
+{
+    JsonWriter {
+        writer: FmtWriter(writer),
+    }
+    .write(parser)
+    .map_err(|_| fmt::Error)
+}
+
+/// Takes a [`Parser`], or any `Iterator<Item = Result<Event<'_>, E>>`, as input and writes the
+/// JSON encoding of the events into the given writer.
+///
+/// See [`push_json`] for more details.
+///
+/// [`Parser`]: crate::parser::Parser
+pub fn write_json<'a, W, I, E>(writer: W, parser: I) -> io::Result<()>
+where
+    W: io::Write,
+    I: Iterator<Item = Result<Event<'a>, E>>,
+    E: fmt::Display,
+{
+    JsonWriter { writer }.write(parser)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{ErrorPolicy, ParserConfig},
+        parser::Parser,
+    };
+
+    use super::*;
+
+    fn json(input: &str) -> String {
+        let config = ParserConfig {
+            on_unknown_command: ErrorPolicy::EmitPlaceholder,
+            ..Default::default()
+        };
+        let mut output = String::new();
+        push_json(&mut output, Parser::with_config(input, config)).unwrap();
+        output
+    }
+
+    #[test]
+    fn events() {
+        assert_eq!(
+            json(r"\frac{a}{\sin} \left( \mathbf{x} \right)"),
+            r#"[{"type":"Visual","variant":"Fraction"},{"type":"Begin","grouping":"Normal"},{"type":"Content","variant":"Identifier","value":"a"},{"type":"End"},{"type":"Begin","grouping":"Normal"},{"type":"Content","variant":"Function","value":"sin"},{"type":"End"},{"type":"Begin","grouping":"LeftRight"},{"type":"Content","variant":"Operator","value":"("},{"type":"Begin","grouping":"Internal"},{"type":"StateChange","variant":"Font","value":"Bold"},{"type":"Content","variant":"Identifier","value":"x"},{"type":"End"},{"type":"Content","variant":"Operator","value":")"},{"type":"End"}]"#
+        );
+        assert_eq!(
+            json(r#"\begin{array}{l|c} a \\ b \end{array} \kern1em \foo \big( \text{"a"}"#),
+            r#"[{"type":"Begin","grouping":"Array","columns":"l|c"},{"type":"Content","variant":"Identifier","value":"a"},{"type":"NewLine"},{"type":"Content","variant":"Identifier","value":"b"},{"type":"End"},{"type":"Space","width":"1em"},{"type":"UnknownCommand","value":"foo"},{"type":"Content","variant":"Operator","value":"(","size":"1.2em"},{"type":"Content","variant":"Text","value":"\"a\""}]"#
+        );
+    }
+
+    #[test]
+    fn valid_json() {
+        let inputs = [
+            r"\color{red} x \fbox{y} \framebox[2em][l]{z} \rlap{a} \cancel{b} \cfrac[r]{1}{2}",
+            r"\mathbin{+} \genfrac{}{}{0.5pt}{}{a}{b} \shortmid \sum_{i}^{n} \varsubsetneq",
+            "\\text{\u{1}\n} \\frac{a}",
+        ];
+        for input in inputs {
+            let output = json(input);
+            let value: serde_json::Value = serde_json::from_str(&output)
+                .unwrap_or_else(|error| panic!("{input}: {error}\n{output}"));
+            assert!(value.is_array());
+        }
+        let output = json(r"\frac{a}");
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[value.as_array().unwrap().len() - 1]["type"], "Error");
+    }
+
+    #[test]
+    fn string_escapes() {
+        let mut writer = JsonWriter { writer: Vec::new() };
+        writer.string("a\"b\\c\n\u{1}é").unwrap();
+        assert_eq!(writer.writer, "\"a\\\"b\\\\c\\n\\u0001é\"".as_bytes());
+    }
+
+    #[test]
+    fn io_writer() {
+        let mut output = Vec::new();
+        write_json(&mut output, Parser::new("x")).unwrap();
+        assert_eq!(
+            output,
+            br#"[{"type":"Content","variant":"Identifier","value":"x"}]"#
+        );
+    }
+}
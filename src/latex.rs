@@ -0,0 +1,2090 @@
+//! A `LaTeX` printer, which turns a stream of events back into `LaTeX` source.
+//!
+//! The printer is available through the [`push_latex`] function. Each event is printed using the
+//! shortest command which the [`Parser`] maps to it, such that parsing the printed source yields
+//! the same events as the original source. This makes it possible to normalize expressions, e.g.,
+//! `x^{2} \quad \mathbf y` is printed as `x^{2}\quad\mathbf{y}`.
+//!
+//! The event stream does not retain every detail of the source it was parsed from. Commands
+//! producing the same events are printed using a single form (e.g., both `\to` and `→` are printed
+//! as `\rightarrow`). Some events can only be produced as part of a larger construct, and a few of
+//! those cannot be reproduced when they occur on their own (e.g., a [`StateChange::Cramped`]
+//! outside of a `\cramped` group), in which case the closest available command is printed.
+//!
+//! [`Parser`]: crate::parser::Parser
+
+use thiserror::Error;
+
+use crate::{
+    attribute::{Dimension, DimensionUnit, Font},
+    event::{
+        AnnotationKind, ArrayColumn, AtomClass, BoxAlignment, ColorChange, ColorTarget,
+        ColumnAlignment, Content, Event, Grouping, Identifier, LapDirection, Operator,
+        ScriptPosition, ScriptType, StateChange, StrikeKind, Style, TextFont, Visual,
+    },
+    parser::{is_delimiter, symbols},
+};
+
+struct LatexWriter<'a, 'b> {
+    events: &'b [Event<'a>],
+    index: usize,
+    output: &'b mut String,
+    /// Whether the last thing written is a control word (e.g., `\alpha`) or a dimension (e.g.,
+    /// `2em`), which must be separated from a following letter.
+    after_word: bool,
+    /// Whether the last thing written is a number, which must be separated from a following digit.
+    after_number: bool,
+    /// The bounds of the sequence of elements being written, such as the content of a group.
+    sequence: (usize, usize),
+    /// Whether the next element is written as part of a sequence, rather than as the argument of
+    /// a command or script.
+    in_sequence: bool,
+    /// Whether the last thing written is a script to the right of its base, after which
+    /// prescripts attached to an empty group would be the indices of a tensor.
+    after_scripts: bool,
+    /// The reason why the events could not be written, if the writer found an element which does
+    /// not match the elements checked before writing.
+    unsupported: Option<&'static str>,
+}
+
+impl<'a, 'b> LatexWriter<'a, 'b> {
+    fn new(events: &'b [Event<'a>], output: &'b mut String) -> Self {
+        Self {
+            events,
+            index: 0,
+            output,
+            after_word: false,
+            after_number: false,
+            sequence: (0, events.len()),
+            in_sequence: false,
+            after_scripts: false,
+            unsupported: None,
+        }
+    }
+
+    fn write_str(&mut self, s: &str) {
+        let Some(first) = s.chars().next() else {
+            return;
+        };
+        if (self.after_word && first.is_ascii_alphabetic())
+            || (self.after_number && matches!(first, '0'..='9' | '.' | ','))
+        {
+            self.output.push(' ');
+        }
+        self.output.push_str(s);
+        self.after_word = false;
+        self.after_number = false;
+        self.after_scripts = false;
+    }
+
+    fn write_char(&mut self, c: char) {
+        self.write_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    fn write_control_sequence(&mut self, name: &str) {
+        self.write_str("\\");
+        self.output.push_str(name);
+        self.after_word = name.chars().all(|c| c.is_ascii_alphabetic());
+    }
+
+    /// Write an argument whose content is used verbatim, such as the argument of `\href`.
+    fn write_verbatim_argument(&mut self, content: &str) {
+        self.write_str("{");
+        self.output.push_str(content);
+        self.write_str("}");
+    }
+
+    /// Write an argument made of text, such as the argument of `\text`.
+    fn write_text_argument(&mut self, text: &str) {
+        self.write_str("{");
+        self.write_text(text);
+        self.write_str("}");
+    }
+
+    /// Write text such that it is read back as is in text mode, escaping its special characters
+    /// and the spaces which would be collapsed.
+    fn write_text(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                    self.output.push('\\');
+                    self.output.push(c);
+                }
+                '\u{a0}' => self.output.push('~'),
+                ' ' if self.output.ends_with(' ') => self.output.push_str("\\ "),
+                c => self.output.push(c),
+            }
+        }
+    }
+
+    fn write_dimension(&mut self, (value, unit): Dimension) {
+        self.write_str(&format!("{value}{unit}"));
+        self.after_word = true;
+    }
+
+    fn write_delimiter(&mut self, delimiter: char) {
+        match delimiter {
+            '{' => self.write_control_sequence("{"),
+            '}' => self.write_control_sequence("}"),
+            '\\' => self.write_control_sequence("backslash"),
+            _ => self.write_char(delimiter),
+        }
+    }
+
+    /// Returns the index of the `End` event closing the group whose content starts at `start`.
+    fn group_end(&self, start: usize) -> usize {
+        let mut depth = 0usize;
+        for (index, event) in self.events.iter().enumerate().skip(start) {
+            match event {
+                Event::Begin(_) => depth += 1,
+                Event::End if depth == 0 => return index,
+                Event::End => depth -= 1,
+                _ => {}
+            }
+        }
+        self.events.len()
+    }
+
+    /// Returns the index following the element starting at `start`.
+    fn element_end(&self, start: usize) -> usize {
+        match self.events.get(start) {
+            None => start,
+            Some(Event::Begin(_)) => self.group_end(start + 1) + 1,
+            Some(event) => (0..child_count(event)).fold(start + 1, |end, _| self.element_end(end)),
+        }
+    }
+
+    /// Returns the index following the element starting at `start`, or the reason why the events
+    /// do not form a complete element.
+    ///
+    /// The printer relies on the elements being complete, which is checked before printing.
+    fn check_element(&self, start: usize) -> Result<usize, &'static str> {
+        match self.events.get(start) {
+            None => Err("the events end before an element is complete"),
+            Some(Event::End) => Err("an `End` event does not close a group"),
+            Some(Event::Begin(_)) => {
+                let mut child = start + 1;
+                loop {
+                    match self.events.get(child) {
+                        None => return Err("the events end inside of a group"),
+                        Some(Event::End) => return Ok(child + 1),
+                        Some(_) => child = self.check_element(child)?,
+                    }
+                }
+            }
+            Some(event) => {
+                (0..child_count(event)).try_fold(start + 1, |end, _| self.check_element(end))
+            }
+        }
+    }
+
+    /// Returns the delimiter at the given index, if it comes before `end` and is a delimiter as
+    /// produced by `\left` and `\right`.
+    fn delimiter_at(&self, index: usize, end: usize) -> Option<char> {
+        match self.events.get(index).filter(|_| index < end)? {
+            Event::Content(Content::Operator(operator))
+                if *operator == plain(operator.content) && is_delimiter(operator.content) =>
+            {
+                Some(operator.content)
+            }
+            _ => None,
+        }
+    }
+
+    /// The default position of the scripts attached to the element at the given index, if the
+    /// element allows for limit controls (`\limits` and `\nolimits`).
+    fn limits(&self, index: usize) -> Option<ScriptPosition> {
+        match self.events.get(index)? {
+            Event::Content(Content::Operator(operator)) => operator_limits(*operator),
+            Event::Content(Content::Identifier(Identifier::Str(name))) => function_limits(name),
+            Event::Begin(Grouping::AtomClass(AtomClass::Operator)) => Some(ScriptPosition::Movable),
+            Event::Script {
+                position: ScriptPosition::Prescript,
+                ..
+            } => self.limits(index + 1),
+            Event::Script { ty, position } => {
+                if self.decorated_limit(index + 1, *ty, *position).is_some() {
+                    return Some(ScriptPosition::Movable);
+                }
+                // The scripts of braces are set with `\limits`.
+                let Event::Content(Content::Operator(operator)) =
+                    self.events.get(self.element_end(index + 1))?
+                else {
+                    return None;
+                };
+                match (ty, position) {
+                    (ScriptType::Superscript, ScriptPosition::AboveBelow)
+                        if overscript_command(*operator) == Some("overbrace") =>
+                    {
+                        Some(ScriptPosition::AboveBelow)
+                    }
+                    (ScriptType::Subscript, ScriptPosition::AboveBelow)
+                        if underscript_command(*operator) == Some("underbrace") =>
+                    {
+                        Some(ScriptPosition::AboveBelow)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the command producing the script whose base is at the given index, if it is a
+    /// decorated limit such as `\varliminf`.
+    fn decorated_limit(
+        &self,
+        base: usize,
+        ty: ScriptType,
+        position: ScriptPosition,
+    ) -> Option<&'static str> {
+        let [Event::Content(Content::Identifier(Identifier::Str("lim"))), Event::Content(Content::Operator(decoration))] =
+            self.events.get(base..base + 2)?
+        else {
+            return None;
+        };
+        if position != ScriptPosition::AboveBelow || *decoration != plain(decoration.content) {
+            return None;
+        }
+        Some(match (ty, decoration.content) {
+            (ScriptType::Subscript, '_') => "varliminf",
+            (ScriptType::Superscript, '‾') => "varlimsup",
+            (ScriptType::Subscript, '→') => "varinjlim",
+            (ScriptType::Subscript, '←') => "varprojlim",
+            _ => return None,
+        })
+    }
+
+    fn write(mut self) -> Result<(), &'static str> {
+        let mut end = 0;
+        while end < self.events.len() {
+            end = self.check_element(end)?;
+        }
+        self.write_until(self.events.len());
+        self.unsupported.map_or(Ok(()), Err)
+    }
+
+    fn next_event(&mut self) -> Option<Event<'a>> {
+        let event = self.events.get(self.index).copied();
+        self.index += 1;
+        event
+    }
+
+    /// Write the next element of the stream.
+    fn write_element(&mut self) {
+        let in_sequence = std::mem::take(&mut self.in_sequence);
+        let Some(event) = self.next_event() else {
+            return;
+        };
+        match event {
+            Event::Content(content) => self.write_content(content),
+            Event::Begin(grouping) => self.write_group(grouping, in_sequence),
+            Event::End => {
+                self.unsupported
+                    .get_or_insert("an `End` event does not close a group");
+            }
+            Event::Visual(visual) => self.write_visual(visual, in_sequence),
+            Event::Script { ty, position } => self.write_script(ty, position, in_sequence),
+            Event::Space {
+                width,
+                height,
+                depth,
+            } => self.write_space(width, height, depth),
+            Event::StateChange(state_change) => self.write_state_change(state_change),
+            Event::Alignment => self.write_str("&"),
+            Event::NewLine => self.write_control_sequence("\\"),
+            Event::UnknownCommand(name) => self.write_control_sequence(name),
+            Event::Annotation { kind, value } => {
+                self.write_control_sequence(match kind {
+                    AnnotationKind::Class => "htmlClass",
+                    AnnotationKind::Id => "htmlId",
+                    AnnotationKind::Style => "htmlStyle",
+                });
+                self.write_verbatim_argument(value);
+                self.write_element();
+            }
+            Event::Link { url } => {
+                self.write_control_sequence("href");
+                self.write_verbatim_argument(url);
+                self.write_element();
+            }
+        }
+    }
+
+    fn write_content(&mut self, content: Content) {
+        match content {
+            Content::Text("\u{a0}") => self.write_str("~"),
+            Content::Text("&nbsp;") => self.write_control_sequence("nobreakspace"),
+            Content::Text(text) => {
+                self.write_control_sequence("text");
+                self.write_text_argument(text);
+            }
+            Content::Number(number) => {
+                self.write_str(number);
+                self.after_number = true;
+            }
+            Content::Identifier(Identifier::Char(c)) => match c {
+                '#' | '%' | '&' | '$' | '_' => {
+                    self.write_control_sequence(c.encode_utf8(&mut [0; 4]))
+                }
+                '\\' => self.write_control_sequence("backslash"),
+                _ => match identifier_command(c) {
+                    Some(name) => self.write_control_sequence(name),
+                    None => self.write_char(c),
+                },
+            },
+            Content::Identifier(Identifier::Str(name)) => self.write_function_name(name, false),
+            Content::Operator(operator) => self.write_operator(operator),
+        }
+    }
+
+    /// Write a function name, using `\operatorname` if the name has no command of its own, or if
+    /// `operatorname` is true.
+    fn write_function_name(&mut self, name: &str, operatorname: bool) {
+        match name {
+            "inj lim" => self.write_control_sequence("injlim"),
+            "proj lim" => self.write_control_sequence("projlim"),
+            _ if LIMIT_FUNCTIONS.contains(&name) => self.write_control_sequence(name),
+            _ if FUNCTIONS.contains(&name) && !operatorname => self.write_control_sequence(name),
+            _ => {
+                self.write_control_sequence("operatorname");
+                self.write_verbatim_argument(name);
+            }
+        }
+    }
+
+    fn write_operator(&mut self, operator: Operator) {
+        let content = operator.content;
+        if matches!(content, '^' | '_') {
+            // These only appear as the accent of an element.
+            self.unsupported
+                .get_or_insert("a script character is an operator outside of an accent");
+            return;
+        }
+        if operator
+            == (Operator {
+                stretchy: Some(true),
+                ..plain(content)
+            })
+            && is_delimiter(content)
+        {
+            self.write_control_sequence("middle");
+            return self.write_delimiter(content);
+        }
+        if operator.deny_movable_limits {
+            if let Some(name) = large_operator(content) {
+                if operator
+                    == (Operator {
+                        deny_movable_limits: true,
+                        ..plain(content)
+                    })
+                {
+                    return self.write_control_sequence(name);
+                }
+            }
+        }
+        if operator == plain(content) {
+            if let Some(name) = integral(content) {
+                return self.write_control_sequence(name);
+            }
+            if let Some(name) = operator_command(content) {
+                return self.write_control_sequence(name);
+            }
+        }
+        if operator
+            == (Operator {
+                stretchy: Some(false),
+                ..plain(content)
+            })
+        {
+            return match content {
+                '{' => self.write_control_sequence("{"),
+                '}' => self.write_control_sequence("}"),
+                '∥' => self.write_control_sequence("|"),
+                _ => self.write_char(content),
+            };
+        }
+        if operator
+            == (Operator {
+                unicode_variant: true,
+                ..plain(content)
+            })
+        {
+            if let Some(name) = unicode_variant(content) {
+                return self.write_control_sequence(name);
+            }
+        }
+        if let Some(size @ (value, DimensionUnit::Em)) = operator.size {
+            if operator
+                == (Operator {
+                    size: Some(size),
+                    ..plain(content)
+                })
+            {
+                if let Some(name) = sized_delimiter(operator) {
+                    self.write_control_sequence(name);
+                    return self.write_delimiter(content);
+                }
+                if let Some(name) = small_operator(content).filter(|_| value == 0.7) {
+                    return self.write_control_sequence(name);
+                }
+            }
+        }
+        self.write_char(content);
+    }
+
+    fn write_group(&mut self, grouping: Grouping, in_sequence: bool) {
+        let end = self.group_end(self.index);
+        match grouping {
+            Grouping::Normal => self.write_normal_group(end),
+            Grouping::Internal => self.write_internal_group(end),
+            Grouping::LeftRight => self.write_left_right(end, in_sequence),
+            Grouping::Array(columns) => {
+                let spec = columns
+                    .as_slice()
+                    .iter()
+                    .map(|column| match column {
+                        ArrayColumn::Column(ColumnAlignment::Left) => 'l',
+                        ArrayColumn::Column(ColumnAlignment::Center) => 'c',
+                        ArrayColumn::Column(ColumnAlignment::Right) => 'r',
+                        ArrayColumn::VerticalLine => '|',
+                    })
+                    .collect::<String>();
+                self.write_environment("array", Some(&spec), end);
+            }
+            Grouping::Matrix => self.write_environment("matrix", None, end),
+            Grouping::Cases => self.write_environment("cases", None, end),
+            Grouping::Align => self.write_environment("align", None, end),
+            Grouping::AdjustLimits => {
+                self.write_control_sequence("adjustlimits");
+                self.write_until(end);
+            }
+            Grouping::AtomClass(class) => {
+                // Sized delimiters of these classes are produced by commands such as `\bigl`.
+                let suffix = match class {
+                    AtomClass::Opening => "l",
+                    AtomClass::Closing => "r",
+                    AtomClass::Relation => "m",
+                    _ => "",
+                };
+                if let [Event::Content(Content::Operator(operator))] = self.events[self.index..end]
+                {
+                    if let Some(name) = fence_command(class, operator) {
+                        self.write_control_sequence(name);
+                        self.index = end + 1;
+                        return;
+                    }
+                    if let Some(name) = sized_delimiter(operator).filter(|_| !suffix.is_empty()) {
+                        self.write_control_sequence(&format!("{name}{suffix}"));
+                        self.write_delimiter(operator.content);
+                        self.index = end + 1;
+                        return;
+                    }
+                }
+                self.write_control_sequence(match class {
+                    AtomClass::Ordinary => "mathord",
+                    AtomClass::Operator => "mathop",
+                    AtomClass::Binary => "mathbin",
+                    AtomClass::Relation => "mathrel",
+                    AtomClass::Opening => "mathopen",
+                    AtomClass::Closing => "mathclose",
+                    AtomClass::Punctuation => "mathpunct",
+                    AtomClass::Inner => "mathinner",
+                });
+                self.write_braced_until(end);
+            }
+        }
+        self.index = end + 1;
+    }
+
+    /// Write the sequence of elements up to `end`.
+    fn write_until(&mut self, end: usize) {
+        let sequence = std::mem::replace(&mut self.sequence, (self.index, end));
+        while self.index < end {
+            self.in_sequence = true;
+            self.write_element();
+        }
+        self.sequence = sequence;
+    }
+
+    fn write_braced_until(&mut self, end: usize) {
+        self.write_str("{");
+        self.write_until(end);
+        self.write_str("}");
+    }
+
+    /// Write a group delimited by braces, or one of the commands producing such a group along
+    /// with a state change, such as `\colorbox`.
+    fn write_normal_group(&mut self, end: usize) {
+        let events = self.events;
+        match events[self.index..end] {
+            [Event::StateChange(StateChange::Color(ColorChange {
+                color,
+                target: ColorTarget::Background,
+            })), ..]
+                if self.text_at(self.index + 1) && self.element_end(self.index + 1) == end =>
+            {
+                self.write_control_sequence("colorbox");
+                self.write_verbatim_argument(color);
+                self.index += 1;
+                self.write_text_element();
+            }
+            [Event::StateChange(StateChange::Color(ColorChange {
+                color: frame,
+                target: ColorTarget::Border,
+            })), Event::StateChange(StateChange::Color(ColorChange {
+                color: background,
+                target: ColorTarget::Background,
+            })), ..]
+                if self.text_at(self.index + 2) && self.element_end(self.index + 2) == end =>
+            {
+                self.write_control_sequence("fcolorbox");
+                self.write_verbatim_argument(frame);
+                self.write_verbatim_argument(background);
+                self.index += 2;
+                self.write_text_element();
+            }
+            [Event::StateChange(StateChange::TextFont(font)), ..]
+                if self.text_until(self.index + 1, end) =>
+            {
+                self.write_control_sequence(text_font_command(font));
+                self.index += 1;
+                self.write_str("{");
+                self.write_text_until(end);
+                self.write_str("}");
+            }
+            [Event::StateChange(StateChange::Style(Style::Text)), ..]
+                if self.text_at(self.index + 1) && self.element_end(self.index + 1) == end =>
+            {
+                self.write_control_sequence("mbox");
+                self.index += 1;
+                self.write_text_element();
+            }
+            [Event::StateChange(StateChange::Style(style)), Event::StateChange(StateChange::Cramped), ..]
+                if self.element_end(self.index + 2) == end =>
+            {
+                self.write_control_sequence("cramped");
+                self.write_str("[");
+                self.write_control_sequence(style_command(style));
+                self.write_str("]");
+                self.index += 2;
+                self.write_element();
+            }
+            [Event::StateChange(StateChange::Cramped), ..]
+                if self.element_end(self.index + 1) == end =>
+            {
+                self.write_control_sequence("cramped");
+                self.index += 1;
+                self.write_element();
+            }
+            _ => self.write_braced_until(end),
+        }
+    }
+
+    /// Write a group which is produced by a command, such as `\mathbf` or `\dfrac`.
+    fn write_internal_group(&mut self, end: usize) {
+        let events = self.events;
+        match events[self.index..end] {
+            [] => {}
+            [Event::StateChange(StateChange::Font(font)), ..] => match font_command(font) {
+                Some(name) => {
+                    self.write_control_sequence(name);
+                    self.index += 1;
+                    self.write_braced_until(end);
+                }
+                None => self.write_braced_until(end),
+            },
+            [Event::StateChange(StateChange::Style(style)), Event::Visual(visual), ..]
+                if self.element_end(self.index + 1) == end =>
+            {
+                let name = match (style, visual) {
+                    (Style::Display, Visual::Fraction(None)) => "dfrac",
+                    (Style::Text, Visual::Fraction(None)) => "tfrac",
+                    (Style::Display, Visual::SplitFraction) => "splitdfrac",
+                    (Style::Text, Visual::SplitFraction) => "splitfrac",
+                    // The alignment of `\cfrac` is written along with the fraction.
+                    (Style::Display, Visual::ContinuedFraction(_)) => {
+                        self.index += 1;
+                        return self.write_element();
+                    }
+                    _ => return self.write_braced_until(end),
+                };
+                self.write_control_sequence(name);
+                self.index += 2;
+                self.write_element();
+                self.write_element();
+            }
+            [Event::Visual(Visual::SquareRoot), Event::Space {
+                width: Some((0., DimensionUnit::Em)),
+                height: Some((0.7, DimensionUnit::Em)),
+                depth: None,
+            }] => {
+                self.write_control_sequence("surd");
+                self.index = end;
+            }
+            // The pieces of a text which is not written as is in the input.
+            [Event::Content(Content::Text(_)), ..] if self.text_at(self.index - 1) => {
+                self.write_control_sequence("text");
+                self.write_str("{");
+                self.write_text_until(end);
+                self.write_str("}");
+            }
+            _ => self.write_braced_until(end),
+        }
+    }
+
+    /// Write `\pmod` or `\pod`, whose space is followed by the modulus in parentheses, as in
+    /// `\pmod{n}`.
+    ///
+    /// Returns false if the events following the space are not such a modulus.
+    fn write_modulo(&mut self) -> bool {
+        let events = self.events;
+        // The scripts following the modulus are attached to it.
+        let (script, start) = match events.get(self.index) {
+            Some(Event::Script {
+                ty,
+                position: ScriptPosition::Right,
+            }) => (Some(*ty), self.index + 1),
+            _ => (None, self.index),
+        };
+        let Some(Event::Begin(Grouping::Internal)) = events.get(start) else {
+            return false;
+        };
+        let end = self.group_end(start + 1);
+        let content = &events[start + 1..end];
+        let [Event::Content(Content::Operator(left)), .., Event::Content(Content::Operator(right))] =
+            *content
+        else {
+            return false;
+        };
+        if left != plain('(') || right != plain(')') {
+            return false;
+        }
+        let (name, argument) = match content[1] {
+            Event::Content(Content::Identifier(Identifier::Str("mod"))) => ("pmod", start + 3),
+            _ => ("pod", start + 2),
+        };
+        if argument >= end - 1 || self.element_end(argument) != end - 1 {
+            return false;
+        }
+        self.write_control_sequence(name);
+        self.index = argument;
+        self.write_element();
+        self.index = end + 1;
+        if let Some(ty) = script {
+            self.write_scripts(ty);
+            self.after_scripts = true;
+        }
+        true
+    }
+
+    fn write_left_right(&mut self, end: usize, in_sequence: bool) {
+        if self.write_delimited_fraction(end, in_sequence) {
+            return;
+        }
+        self.write_control_sequence("left");
+        match self.delimiter_at(self.index, end) {
+            Some(left) => {
+                self.write_delimiter(left);
+                self.index += 1;
+            }
+            None => self.write_str("."),
+        }
+        let right = self
+            .delimiter_at(end.wrapping_sub(1), end)
+            .filter(|_| self.index < end);
+        let content_end = if right.is_some() { end - 1 } else { end };
+        let sequence = std::mem::replace(&mut self.sequence, (self.index, content_end));
+        while self.index < content_end {
+            self.in_sequence = true;
+            self.write_element();
+        }
+        self.sequence = sequence;
+        self.write_control_sequence("right");
+        match right {
+            Some(right) => self.write_delimiter(right),
+            None => self.write_str("."),
+        }
+    }
+
+    /// Write a fraction surrounded by delimiters, as produced by `\binom`, `\genfrac`, and
+    /// `\choose`.
+    ///
+    /// Returns false if the content of the group is not such a fraction.
+    fn write_delimited_fraction(&mut self, end: usize, in_sequence: bool) -> bool {
+        let events = self.events;
+        let group = self.index - 1;
+        let mut index = self.index;
+        let style = match events.get(index) {
+            Some(Event::StateChange(StateChange::Style(style))) => {
+                index += 1;
+                Some(*style)
+            }
+            _ => None,
+        };
+        let left = self.delimiter_at(index, end);
+        if left.is_some() {
+            index += 1;
+        }
+        let Some(Event::Visual(Visual::Fraction(bar))) = events.get(index) else {
+            return false;
+        };
+        let fraction = index;
+        let fraction_end = self.element_end(fraction);
+        let right = self.delimiter_at(fraction_end, end);
+        if fraction_end + usize::from(right.is_some()) != end {
+            return false;
+        }
+
+        if style.is_none() && self.infix_arguments(fraction) {
+            if !in_sequence || !self.alone(group, end + 1) {
+                return false;
+            }
+            self.index = fraction + 1;
+            self.write_infix_fraction(left, right, *bar);
+            return true;
+        }
+        if style.is_none() && bar.is_none() {
+            if left != Some('(') || right != Some(')') {
+                return false;
+            }
+            self.write_control_sequence("binom");
+        } else {
+            self.write_control_sequence("genfrac");
+            for delimiter in [left, right] {
+                self.write_str("{");
+                if let Some(delimiter) = delimiter {
+                    self.write_delimiter(delimiter);
+                }
+                self.write_str("}");
+            }
+            self.write_str("{");
+            if let Some(bar) = bar {
+                self.write_dimension(*bar);
+            }
+            self.write_str("}{");
+            if let Some(style) = style {
+                self.write_char(match style {
+                    Style::Display => '0',
+                    Style::Text => '1',
+                    Style::Script => '2',
+                    Style::ScriptScript => '3',
+                });
+            }
+            self.write_str("}");
+        }
+        self.index = fraction + 1;
+        self.write_element();
+        self.write_element();
+        true
+    }
+
+    /// Whether the numerator and denominator of the fraction at the given index are internal
+    /// groups, as produced by infix commands such as `\over`.
+    fn infix_arguments(&self, fraction: usize) -> bool {
+        let numerator = fraction + 1;
+        matches!(
+            self.events.get(numerator),
+            Some(Event::Begin(Grouping::Internal))
+        ) && matches!(
+            self.events.get(self.element_end(numerator)),
+            Some(Event::Begin(Grouping::Internal))
+        )
+    }
+
+    /// Whether the element from `start` to `end` is the only element of the sequence being
+    /// written, or of an environment cell, as is the case of fractions produced by infix commands.
+    fn alone(&self, start: usize, end: usize) -> bool {
+        let sequence_start = self.sequence.0;
+        (start == sequence_start || (start > sequence_start && self.cell_boundary(start - 1)))
+            && self.last(end)
+    }
+
+    /// Whether the element ending at `end` is the last element of the sequence being written, or
+    /// of an environment cell.
+    fn last(&self, end: usize) -> bool {
+        let sequence_end = self.sequence.1;
+        end == sequence_end || (end < sequence_end && self.cell_boundary(end))
+    }
+
+    fn cell_boundary(&self, index: usize) -> bool {
+        matches!(
+            self.events.get(index),
+            Some(Event::Alignment | Event::NewLine)
+        )
+    }
+
+    /// Write the numerator and denominator of a fraction separated by the infix command producing
+    /// it, as in `a \over b`.
+    fn write_infix_fraction(
+        &mut self,
+        left: Option<char>,
+        right: Option<char>,
+        bar: Option<Dimension>,
+    ) {
+        let no_bar = bar == Some((0., DimensionUnit::Em));
+        let delimited = left.is_some() || right.is_some();
+        let name = match (delimited, bar) {
+            _ if no_bar && left == Some('(') && right == Some(')') => "choose",
+            (false, None) => "over",
+            (false, Some(_)) if no_bar => "atop",
+            (false, Some(_)) => "above",
+            (true, None) => "overwithdelims",
+            (true, Some(_)) if no_bar => "atopwithdelims",
+            (true, Some(_)) => "abovewithdelims",
+        };
+        let numerator = self.index;
+        let denominator = self.element_end(numerator);
+        self.index = numerator + 1;
+        self.write_until(denominator - 1);
+        self.write_control_sequence(name);
+        if delimited && name != "choose" {
+            for delimiter in [left, right] {
+                match delimiter {
+                    Some(delimiter) => self.write_delimiter(delimiter),
+                    None => self.write_str("."),
+                }
+            }
+        }
+        if let Some(bar) = bar.filter(|_| !no_bar) {
+            self.write_dimension(bar);
+        }
+        let end = self.element_end(denominator);
+        self.index = denominator + 1;
+        self.write_until(end - 1);
+        self.index = end;
+    }
+
+    fn write_environment(&mut self, name: &str, spec: Option<&str>, end: usize) {
+        self.write_control_sequence("begin");
+        self.write_verbatim_argument(name);
+        if let Some(spec) = spec {
+            self.write_verbatim_argument(spec);
+        }
+        self.write_until(end);
+        self.write_control_sequence("end");
+        self.write_verbatim_argument(name);
+    }
+
+    fn write_visual(&mut self, visual: Visual, in_sequence: bool) {
+        match visual {
+            Visual::SquareRoot => {
+                self.write_control_sequence("sqrt");
+                self.write_element();
+            }
+            Visual::Root => {
+                // The radicand comes before the index in the event stream.
+                let radicand = self.index;
+                self.index = self.element_end(radicand);
+                self.write_control_sequence("sqrt");
+                self.write_str("[");
+                self.write_element();
+                self.write_str("]");
+                let end = self.index;
+                self.index = radicand;
+                self.write_element();
+                self.index = end;
+            }
+            Visual::Fraction(bar)
+                if in_sequence
+                    && self.infix_arguments(self.index - 1)
+                    && self.alone(self.index - 1, self.element_end(self.index - 1)) =>
+            {
+                self.write_infix_fraction(None, None, bar);
+            }
+            Visual::Fraction(Some(bar)) => {
+                self.write_control_sequence("genfrac");
+                self.write_str("{}{}{");
+                self.write_dimension(bar);
+                self.write_str("}{}");
+                self.write_element();
+                self.write_element();
+            }
+            Visual::Fraction(None) | Visual::SplitFraction => {
+                self.write_control_sequence(if visual == Visual::SplitFraction {
+                    "splitfrac"
+                } else {
+                    "frac"
+                });
+                self.write_element();
+                self.write_element();
+            }
+            Visual::ContinuedFraction(alignment) => {
+                self.write_control_sequence("cfrac");
+                match alignment {
+                    BoxAlignment::Left => self.write_str("[l]"),
+                    BoxAlignment::Center => {}
+                    BoxAlignment::Right => self.write_str("[r]"),
+                }
+                self.write_element();
+                self.write_element();
+            }
+            Visual::Negation => {
+                self.write_control_sequence("not");
+                self.write_element();
+            }
+            Visual::Strike(kind) => {
+                self.write_control_sequence(match kind {
+                    StrikeKind::UpDiagonal => "cancel",
+                    StrikeKind::DownDiagonal => "bcancel",
+                    StrikeKind::Cross => "xcancel",
+                    StrikeKind::Horizontal => "sout",
+                });
+                self.write_element();
+            }
+            Visual::Accent => self.write_accent(),
+            Visual::Boxed => {
+                self.write_control_sequence("boxed");
+                self.write_element();
+            }
+            Visual::Framed { width, alignment } => {
+                match width {
+                    None => self.write_control_sequence("fbox"),
+                    Some(width) => {
+                        self.write_control_sequence("framebox");
+                        self.write_str("[");
+                        self.write_dimension(width);
+                        self.write_str("]");
+                        match alignment {
+                            BoxAlignment::Left => self.write_str("[l]"),
+                            BoxAlignment::Center => {}
+                            BoxAlignment::Right => self.write_str("[r]"),
+                        }
+                    }
+                }
+                self.write_text_element();
+            }
+            Visual::Lap(direction) => {
+                let text = self.text_at(self.index);
+                self.write_control_sequence(match (direction, text) {
+                    (LapDirection::Left, true) => "llap",
+                    (LapDirection::Right, true) => "rlap",
+                    (LapDirection::Center, true) => "clap",
+                    (LapDirection::Left, false) => "mathllap",
+                    (LapDirection::Right, false) => "mathrlap",
+                    (LapDirection::Center, false) => "mathclap",
+                });
+                self.write_text_element();
+            }
+            Visual::Raise(dimension) => {
+                if self.text_at(self.index) {
+                    self.write_control_sequence("raisebox");
+                    self.write_str("{");
+                    self.write_dimension(dimension);
+                    self.write_str("}");
+                } else {
+                    self.write_control_sequence("raise");
+                    self.write_dimension(dimension);
+                }
+                self.write_text_element();
+            }
+            Visual::VCenter => {
+                self.write_control_sequence("vcenter");
+                self.write_element();
+            }
+        }
+    }
+
+    /// Whether the element starting at `index` can be written as text, i.e., it is text, the
+    /// pieces of a text, or a non-empty group of text and inline formulas.
+    ///
+    /// A group containing a single span of text is not written as text, which would be parsed
+    /// without the group.
+    fn text_at(&self, index: usize) -> bool {
+        match self.events.get(index) {
+            Some(Event::Content(Content::Text(_))) => true,
+            Some(Event::Begin(Grouping::Internal)) => {
+                let end = self.group_end(index + 1);
+                end > index + 1
+                    && self.events[index + 1..end]
+                        .iter()
+                        .all(|event| matches!(event, Event::Content(Content::Text(_))))
+            }
+            Some(Event::Begin(Grouping::Normal)) => {
+                let end = self.group_end(index + 1);
+                let single_text = end == index + 2
+                    && matches!(self.events[index + 1], Event::Content(Content::Text(_)));
+                end > index + 1 && !single_text && self.text_until(index + 1, end)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the elements from `start` to `end` are text and inline formulas.
+    fn text_until(&self, start: usize, end: usize) -> bool {
+        let mut child = start;
+        while child < end {
+            match self.events[child..] {
+                [Event::Content(Content::Text(_)), ..] => child += 1,
+                // These state changes are written along with their group, which cannot be an
+                // inline formula.
+                [Event::Begin(Grouping::Normal), Event::StateChange(
+                    StateChange::Cramped
+                    | StateChange::Color(ColorChange {
+                        target: ColorTarget::Background | ColorTarget::Border,
+                        ..
+                    }),
+                ), ..] => return false,
+                [Event::Begin(Grouping::Normal), ..] => child = self.element_end(child),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Write the next element as the argument of a command taking text, such as `\fbox`.
+    ///
+    /// The subgroups of a group written as text are written as inline formulas.
+    fn write_text_element(&mut self) {
+        match self.events.get(self.index) {
+            Some(Event::Content(Content::Text(text))) => {
+                self.index += 1;
+                self.write_text_argument(text);
+            }
+            Some(Event::Begin(_)) if self.text_at(self.index) => {
+                let end = self.group_end(self.index + 1);
+                self.index += 1;
+                self.write_str("{");
+                self.write_text_until(end);
+                self.write_str("}");
+                self.index = end + 1;
+            }
+            _ => self.write_element(),
+        }
+    }
+
+    /// Write the text and inline formulas up to `end`, where groups starting with a text font
+    /// are written as text font commands.
+    fn write_text_until(&mut self, end: usize) {
+        while self.index < end {
+            if let Event::Content(Content::Text(text)) = self.events[self.index] {
+                self.write_text(text);
+                self.index += 1;
+                continue;
+            }
+            let group_end = self.group_end(self.index + 1);
+            match self.events[self.index..] {
+                [Event::Begin(_), Event::StateChange(StateChange::TextFont(font)), ..] => {
+                    self.write_control_sequence(text_font_command(font));
+                    self.index += 2;
+                    self.write_str("{");
+                    self.write_text_until(group_end);
+                    self.write_str("}");
+                }
+                _ => {
+                    self.index += 1;
+                    self.write_str("$");
+                    self.write_until(group_end);
+                    self.write_str("$");
+                }
+            }
+            self.index = group_end + 1;
+        }
+    }
+
+    fn write_accent(&mut self) {
+        let base = self.index;
+        let accent = self.element_end(base);
+        let name = match self.events.get(accent) {
+            Some(Event::Content(Content::Operator(accent))) => accent_command(*accent),
+            _ => None,
+        };
+        match name {
+            Some(name) => {
+                self.write_control_sequence(name);
+                self.write_element();
+            }
+            None => {
+                self.write_control_sequence("overset");
+                self.index = accent;
+                self.write_element();
+                self.index = base;
+                self.write_element();
+            }
+        }
+        self.index = self.element_end(accent);
+    }
+
+    fn write_script(&mut self, ty: ScriptType, position: ScriptPosition, in_sequence: bool) {
+        let base = self.index;
+        let script = self.element_end(base);
+        if position == ScriptPosition::Right
+            && matches!(
+                self.events[base..],
+                [Event::Begin(Grouping::Normal), Event::End, ..]
+            )
+        {
+            // The scripts of an empty group would be the indices of a tensor when following other
+            // scripts, or the prescripts of the element following them. The group is then written
+            // as the base of `\prescript` without prescripts.
+            let end = self.element_end(base - 1);
+            if self.after_scripts || (in_sequence && !self.last(end)) {
+                self.write_control_sequence("prescript");
+                self.write_str("{}{}");
+            }
+        }
+        if position == ScriptPosition::Prescript {
+            self.index = script;
+            // The prescripts are attached to an empty group preceding the base, unless they are
+            // the argument of a command or script, or follow other scripts, of which they would be
+            // the indices. In that case, `\prescript` is used, whose base must be a single
+            // argument.
+            if !in_sequence || self.after_scripts {
+                let (subscript, superscript) = match ty {
+                    ScriptType::Subscript => (Some(script), None),
+                    ScriptType::Superscript => (None, Some(script)),
+                    ScriptType::SubSuperscript => (Some(script), Some(self.element_end(script))),
+                };
+                self.write_control_sequence("prescript");
+                for script in [superscript, subscript] {
+                    match script {
+                        Some(script) => {
+                            self.index = script;
+                            self.write_element();
+                        }
+                        None => self.write_str("{}"),
+                    }
+                }
+            } else {
+                self.write_str("{}");
+                self.write_scripts(ty);
+            }
+            let end = self.element_end(script);
+            let end = if ty == ScriptType::SubSuperscript {
+                self.element_end(end)
+            } else {
+                end
+            };
+            self.index = base;
+            self.write_element();
+            self.index = end;
+            return;
+        }
+        if position == ScriptPosition::Tensor {
+            if !in_sequence {
+                // The indices would otherwise attach to what precedes the tensor.
+                return self.write_tensor(base - 1);
+            }
+            // The base is an element with scripts, which the indices follow.
+            self.in_sequence = true;
+            self.write_element();
+            self.write_str("{}");
+            self.write_scripts(ty);
+            self.after_scripts = true;
+            return;
+        }
+        if let Some(name) = self.decorated_limit(base, ty, position) {
+            self.index = script + 1;
+            return self.write_control_sequence(name);
+        }
+        if position == ScriptPosition::AboveBelow && ty != ScriptType::SubSuperscript {
+            if let Some(Event::Content(Content::Operator(op))) = self.events.get(script) {
+                let name = if ty == ScriptType::Subscript {
+                    underscript_command(*op)
+                } else {
+                    overscript_command(*op)
+                };
+                if let Some(name) = name {
+                    self.write_control_sequence(name);
+                    self.write_element();
+                    self.index = script + 1;
+                    return;
+                }
+            }
+        }
+
+        if let (
+            ScriptPosition::Right,
+            ScriptType::Superscript,
+            [Event::Begin(Grouping::Normal), Event::End, Event::Content(Content::Operator(prime)), ..],
+        ) = (position, ty, &self.events[base..])
+        {
+            if let Some(count) = prime_count(*prime) {
+                // An empty group followed by a superscript would form the prescripts of what
+                // follows it.
+                self.write_str("{}");
+                self.write_str(&"'".repeat(count));
+                self.index = script + 1;
+                self.after_scripts = true;
+                return;
+            }
+        }
+
+        let mut limits = self.limits(base);
+        match self.events[base] {
+            Event::Content(Content::Identifier(Identifier::Str(name)))
+                if limits.is_none() && position != ScriptPosition::Right =>
+            {
+                // Function names such as `\sin` do not allow for limit controls, unlike
+                // `\operatorname`.
+                self.index += 1;
+                self.write_function_name(name, true);
+                limits = Some(ScriptPosition::Right);
+            }
+            _ if limits.is_none()
+                && position == ScriptPosition::AboveBelow
+                && ty != ScriptType::SubSuperscript =>
+            {
+                // `\overset` and `\underset` take the script before the base.
+                self.write_control_sequence(if ty == ScriptType::Superscript {
+                    "overset"
+                } else {
+                    "underset"
+                });
+                let script = self.element_end(base);
+                self.index = script;
+                self.write_element();
+                let end = self.index;
+                self.index = base;
+                self.write_element();
+                self.index = end;
+                return;
+            }
+            // The base is followed by its scripts in the source, such that prescripts of the base
+            // can be attached to an empty group. A tensor is written as `\tensor`, such that its
+            // indices do not take the scripts.
+            _ => {
+                self.in_sequence = !matches!(
+                    self.events[base],
+                    Event::Script {
+                        position: ScriptPosition::Tensor,
+                        ..
+                    }
+                );
+                self.write_element();
+            }
+        }
+        match (limits, position) {
+            (Some(ScriptPosition::Movable | ScriptPosition::Right), ScriptPosition::AboveBelow) => {
+                self.write_control_sequence("limits")
+            }
+            (Some(ScriptPosition::Movable | ScriptPosition::AboveBelow), ScriptPosition::Right) => {
+                self.write_control_sequence("nolimits")
+            }
+            _ => {}
+        }
+        self.write_scripts(ty);
+        self.after_scripts = position == ScriptPosition::Right;
+    }
+
+    /// Write the tensor starting at the given index using `\tensor`, whose indices are single
+    /// scripts.
+    fn write_tensor(&mut self, start: usize) {
+        let mut types = Vec::new();
+        let mut index = start;
+        let base = loop {
+            match self.events[index] {
+                Event::Script {
+                    ty: ty @ (ScriptType::Subscript | ScriptType::Superscript),
+                    position,
+                } => {
+                    types.push(ty);
+                    index += 1;
+                    if position == ScriptPosition::Right {
+                        break index;
+                    }
+                }
+                _ => {
+                    self.index = self.element_end(start);
+                    self.unsupported.get_or_insert(
+                        "a tensor with sub-superscript indices is not in a sequence",
+                    );
+                    return;
+                }
+            }
+        };
+        self.write_control_sequence("tensor");
+        self.index = base;
+        self.write_element();
+        self.write_str("{");
+        for ty in types.into_iter().rev() {
+            self.write_scripts(ty);
+        }
+        self.write_str("}");
+        self.after_scripts = false;
+    }
+
+    /// Write the scripts of the given type, in the order they appear in the stream.
+    fn write_scripts(&mut self, ty: ScriptType) {
+        match ty {
+            ScriptType::Subscript => {
+                self.write_str("_");
+                self.write_element();
+            }
+            ScriptType::Superscript => {
+                self.write_str("^");
+                self.write_element();
+            }
+            ScriptType::SubSuperscript => {
+                self.write_str("_");
+                self.write_element();
+                self.write_str("^");
+                self.write_element();
+            }
+        }
+    }
+
+    fn write_space(
+        &mut self,
+        width: Option<Dimension>,
+        height: Option<Dimension>,
+        depth: Option<Dimension>,
+    ) {
+        match (width, height, depth) {
+            (None, Some(height), Some(depth))
+                if height == (0.7, DimensionUnit::Em) && depth == (0.3, DimensionUnit::Em) =>
+            {
+                self.write_control_sequence("mathstrut")
+            }
+            // The space of `\pmod` and `\pod` in inline mode.
+            (Some(width), None, None)
+                if width == (8. / 18., DimensionUnit::Em) && self.write_modulo() => {}
+            (Some(width), _, _) => match NAMED_SPACES
+                .iter()
+                .find(|(space, _)| (*space, DimensionUnit::Em) == width)
+                .filter(|_| height.is_none() && depth.is_none())
+            {
+                Some((_, name)) => self.write_control_sequence(name),
+                None => {
+                    self.write_control_sequence("kern");
+                    self.write_dimension(width);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn write_state_change(&mut self, state_change: StateChange) {
+        match state_change {
+            StateChange::Font(font) => {
+                if let Some(name) = font.and_then(font_switch) {
+                    self.write_control_sequence(name);
+                }
+            }
+            StateChange::Color(ColorChange {
+                color,
+                target: ColorTarget::Text,
+            }) => {
+                self.write_control_sequence("color");
+                self.write_verbatim_argument(color);
+            }
+            // Other color targets, cramped styles, and text fonts are only produced along with
+            // the group they apply to.
+            StateChange::Color(_) | StateChange::Cramped | StateChange::TextFont(_) => {}
+            StateChange::Style(style) => self.write_control_sequence(style_command(style)),
+        }
+    }
+}
+
+/// Takes a [`Parser`], or any `Iterator<Item = Result<Event<'_>, E>>`, as input and prints the
+/// `LaTeX` source of the events into the given string.
+///
+/// If the input contains an error, the error is returned and nothing is printed. The same goes
+/// for events which do not form valid elements, which are reported as
+/// [`PrintError::Unsupported`].
+///
+/// ```rust
+/// use pulldown_latex::{latex::push_latex, Parser};
+///
+/// let mut latex = String::new();
+/// push_latex(&mut latex, Parser::new(r"x^{2} \quad \mathbf y")).unwrap();
+/// assert_eq!(latex, r"x^{2}\quad\mathbf{y}");
+/// ```
+///
+/// [`Parser`]: crate::parser::Parser
+pub fn push_latex<'a, I, E>(string: &mut String, parser: I) -> Result<(), PrintError<E>>
+where
+    I: Iterator<Item = Result<Event<'a>, E>>,
+{
+    let events = parser
+        .collect::<Result<Vec<_>, E>>()
+        .map_err(PrintError::Event)?;
+    LatexWriter::new(&events, string)
+        .write()
+        .map_err(PrintError::Unsupported)
+}
+
+/// An error encountered while printing events to `LaTeX`.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PrintError<E> {
+    /// An error found in the events, such as a [`ParserError`].
+    ///
+    /// [`ParserError`]: crate::parser::ParserError
+    #[error(transparent)]
+    Event(E),
+    /// The events do not form valid elements, e.g., an [`Event::End`] which does not close a
+    /// group, or the events end before an element is complete.
+    #[error("unsupported event: {0}")]
+    Unsupported(&'static str),
+}
+
+/// The number of elements following the given event which are part of its element, unless it
+/// is the start of a group.
+fn child_count(event: &Event) -> usize {
+    match event {
+        Event::Visual(
+            Visual::Root
+            | Visual::Fraction(_)
+            | Visual::SplitFraction
+            | Visual::ContinuedFraction(_)
+            | Visual::Accent,
+        ) => 2,
+        Event::Visual(_) | Event::Annotation { .. } | Event::Link { .. } => 1,
+        Event::Script {
+            ty: ScriptType::SubSuperscript,
+            ..
+        } => 3,
+        Event::Script { .. } => 2,
+        _ => 0,
+    }
+}
+
+/// Function names which have their own command, and whose scripts are placed above and below them
+/// in display mode.
+const LIMIT_FUNCTIONS: [&str; 9] = [
+    "lim", "Pr", "sup", "liminf", "max", "inf", "gcd", "limsup", "min",
+];
+
+/// Function names which have their own command, and whose scripts are placed on their right.
+const FUNCTIONS: [&str; 24] = [
+    "arccos", "cos", "csc", "exp", "ker", "sinh", "arcsin", "cosh", "deg", "lg", "ln", "arctan",
+    "cot", "det", "hom", "log", "sec", "tan", "arg", "coth", "dim", "sin", "tanh", "sgn",
+];
+
+const DELIMITER_SIZES: [(f32, &str); 4] = [(1.2, "big"), (1.8, "Big"), (2.4, "bigg"), (3., "Bigg")];
+
+const NAMED_SPACES: [(f32, &str); 9] = [
+    (3. / 18., ","),
+    (4. / 18., ":"),
+    (5. / 18., ";"),
+    (-3. / 18., "!"),
+    (-4. / 18., "negmedspace"),
+    (-5. / 18., "negthickspace"),
+    (0.5, "enspace"),
+    (1., "quad"),
+    (2., "qquad"),
+];
+
+/// An operator with the given content, and all other fields set to their default value.
+fn plain(content: char) -> Operator {
+    Operator {
+        content,
+        ..Default::default()
+    }
+}
+
+fn stretchy(content: char) -> Operator {
+    Operator {
+        stretchy: Some(true),
+        ..plain(content)
+    }
+}
+
+/// The number of primes forming the given operator, as produced by `'`.
+fn prime_count(operator: Operator) -> Option<usize> {
+    if operator != plain(operator.content) {
+        return None;
+    }
+    Some(match operator.content {
+        '′' => 1,
+        '″' => 2,
+        '‴' => 3,
+        '⁗' => 4,
+        _ => return None,
+    })
+}
+
+fn operator_limits(operator: Operator) -> Option<ScriptPosition> {
+    let content = operator.content;
+    if large_operator(content).is_some()
+        && operator
+            == (Operator {
+                deny_movable_limits: true,
+                ..plain(content)
+            })
+    {
+        Some(ScriptPosition::Movable)
+    } else if (integral(content).is_some() && operator == plain(content))
+        || operator
+            == (Operator {
+                size: Some((0.7, DimensionUnit::Em)),
+                ..plain('∫')
+            })
+    {
+        Some(ScriptPosition::Right)
+    } else {
+        None
+    }
+}
+
+fn function_limits(name: &str) -> Option<ScriptPosition> {
+    if matches!(name, "inj lim" | "proj lim") || LIMIT_FUNCTIONS.contains(&name) {
+        Some(ScriptPosition::Movable)
+    } else if FUNCTIONS.contains(&name) {
+        None
+    } else {
+        // The name is printed using `\operatorname`.
+        Some(ScriptPosition::Right)
+    }
+}
+
+/// The command producing the given delimiter at its size, as in `\big(`.
+fn sized_delimiter(operator: Operator) -> Option<&'static str> {
+    let size @ (value, DimensionUnit::Em) = operator.size? else {
+        return None;
+    };
+    if operator
+        != (Operator {
+            size: Some(size),
+            ..plain(operator.content)
+        })
+        || !is_delimiter(operator.content)
+    {
+        return None;
+    }
+    DELIMITER_SIZES
+        .iter()
+        .find(|(delimiter_size, _)| *delimiter_size == value)
+        .map(|(_, name)| *name)
+}
+
+/// The command producing the given fence as a delimiter of the given class, as in `\lvert`.
+fn fence_command(class: AtomClass, operator: Operator) -> Option<&'static str> {
+    if operator
+        != (Operator {
+            stretchy: Some(false),
+            ..plain(operator.content)
+        })
+    {
+        return None;
+    }
+    Some(match (class, operator.content) {
+        (AtomClass::Opening, '|') => "lvert",
+        (AtomClass::Closing, '|') => "rvert",
+        (AtomClass::Opening, '‖') => "lVert",
+        (AtomClass::Closing, '‖') => "rVert",
+        _ => return None,
+    })
+}
+
+fn large_operator(c: char) -> Option<&'static str> {
+    Some(match c {
+        '∑' => "sum",
+        '∏' => "prod",
+        '∐' => "coprod",
+        '⋁' => "bigvee",
+        '⋀' => "bigwedge",
+        '⋃' => "bigcup",
+        '⋂' => "bigcap",
+        '⨄' => "biguplus",
+        '⨁' => "bigoplus",
+        '⨂' => "bigotimes",
+        '⨀' => "bigodot",
+        '⨆' => "bigsqcup",
+        '⨅' => "bigsqcap",
+        '⨉' => "bigtimes",
+        _ => return None,
+    })
+}
+
+fn integral(c: char) -> Option<&'static str> {
+    Some(match c {
+        '∫' => "int",
+        '∬' => "iint",
+        '∭' => "iiint",
+        '⨌' => "iiiint",
+        '⨙' => "intcap",
+        '⨚' => "intcup",
+        '∮' => "oint",
+        '∲' => "varointclockwise",
+        '∱' => "intclockwise",
+        '∯' => "oiint",
+        '⨕' => "pointint",
+        '⨒' => "rppolint",
+        '⨓' => "scpolint",
+        '∰' => "oiiint",
+        '⨗' => "intlarhk",
+        '⨖' => "sqint",
+        '⨘' => "intx",
+        '⨍' => "intbar",
+        '⨎' => "intBar",
+        '⨏' => "fint",
+        _ => return None,
+    })
+}
+
+/// Operators whose variant form, selected with a variation selector, is produced by their own
+/// command, such as `\varsubsetneq`.
+fn unicode_variant(c: char) -> Option<&'static str> {
+    Some(match c {
+        '⊊' => "varsubsetneq",
+        '⫋' => "varsubsetneqq",
+        '⫌' => "varsupsetneqq",
+        '≨' => "lvertneqq",
+        '≩' => "gvertneqq",
+        _ => return None,
+    })
+}
+
+/// Operators which have a smaller form produced by their own command, such as `\shortmid`.
+fn small_operator(c: char) -> Option<&'static str> {
+    Some(match c {
+        '∫' => "smallint",
+        '∖' => "smallsetminus",
+        '∣' => "shortmid",
+        '∥' => "shortparallel",
+        '⌣' => "smallsmile",
+        '∦' => "nshortparallel",
+        _ => return None,
+    })
+}
+
+fn accent_command(accent: Operator) -> Option<&'static str> {
+    let stretchy = if accent == plain(accent.content) {
+        false
+    } else if accent
+        == (Operator {
+            stretchy: Some(false),
+            ..plain(accent.content)
+        })
+    {
+        true
+    } else {
+        return None;
+    };
+    Some(match (accent.content, stretchy) {
+        ('´', false) => "acute",
+        ('‾', false) => "bar",
+        ('˘', false) => "breve",
+        ('ˇ', true) => "check",
+        ('˙', false) => "dot",
+        ('¨', false) => "ddot",
+        ('\u{20DB}', false) => "dddot",
+        ('\u{20DC}', false) => "ddddot",
+        ('`', false) => "grave",
+        ('^', true) => "hat",
+        ('~', true) => "tilde",
+        ('→', true) => "vec",
+        ('˚', false) => "mathring",
+        ('←', false) => "overleftarrow",
+        ('→', false) => "overrightarrow",
+        ('⇒', false) => "Overrightarrow",
+        ('↔', false) => "overleftrightarrow",
+        ('↼', false) => "overleftharpoon",
+        ('⇀', false) => "overrightharpoon",
+        ('ˇ', false) => "widecheck",
+        ('^', false) => "widehat",
+        ('~', false) => "widetilde",
+        ('⏜', false) => "wideparen",
+        ('⏠', false) => "overgroup",
+        ('⎴', false) => "overbracket",
+        _ => return None,
+    })
+}
+
+fn overscript_command(content: Operator) -> Option<&'static str> {
+    // Braces are stretchy, unlike the other scripts.
+    (content == stretchy(content.content) && content.content == '⏞').then_some("overbrace")
+}
+
+fn underscript_command(content: Operator) -> Option<&'static str> {
+    if content == stretchy(content.content) && content.content == '⏟' {
+        return Some("underbrace");
+    }
+    if content != plain(content.content) {
+        return None;
+    }
+    Some(match content.content {
+        '_' => "underline",
+        '←' => "underleftarrow",
+        '→' => "underrightarrow",
+        '↔' => "underleftrightarrow",
+        '⏡' => "undergroup",
+        '⏝' => "underparen",
+        '⎵' => "underbracket",
+        _ => return None,
+    })
+}
+
+/// The command changing the font of its argument, as in `\mathbf{x}`.
+fn font_command(font: Option<Font>) -> Option<&'static str> {
+    Some(match font {
+        None => "mathnormal",
+        Some(Font::Bold) => "mathbf",
+        Some(Font::Script) => "mathcal",
+        Some(Font::Italic) => "mathit",
+        Some(Font::UpRight) => "mathrm",
+        Some(Font::SansSerif) => "mathsf",
+        Some(Font::Monospace) => "mathtt",
+        Some(Font::DoubleStruck) => "mathbb",
+        Some(Font::DoubleStruckItalic) => "mathbbit",
+        Some(Font::Fraktur) => "mathfrak",
+        Some(Font::BoldScript) => "mathbfcal",
+        Some(Font::SansSerifItalic) => "mathsfit",
+        Some(Font::BoldItalic) => "mathbfit",
+        Some(Font::BoldFraktur) => "mathbffrak",
+        Some(Font::BoldSansSerif) => "mathbfsfup",
+        Some(Font::SansSerifBoldItalic) => "mathbfsfit",
+        Some(Font::SmallCaps | Font::Slanted) => return None,
+    })
+}
+
+/// The command changing the font of the rest of the group, as in `{\bf x}`.
+fn font_switch(font: Font) -> Option<&'static str> {
+    Some(match font {
+        Font::Bold => "bf",
+        Font::Script => "cal",
+        Font::Italic => "it",
+        Font::UpRight => "rm",
+        Font::SansSerif => "sf",
+        Font::Monospace => "tt",
+        Font::SmallCaps => "sc",
+        Font::Slanted => "sl",
+        Font::DoubleStruckItalic => "bbit",
+        _ => return None,
+    })
+}
+
+/// The command writing its argument in the given text font, as in `\textbf{x}`.
+fn text_font_command(font: TextFont) -> &'static str {
+    match font {
+        TextFont::Bold => "textbf",
+        TextFont::Italic => "textit",
+        TextFont::SmallCaps => "textsc",
+        TextFont::Roman => "textrm",
+        TextFont::SansSerif => "textsf",
+        TextFont::Monospace => "texttt",
+        TextFont::Emphasis => "emph",
+    }
+}
+
+fn style_command(style: Style) -> &'static str {
+    match style {
+        Style::Display => "displaystyle",
+        Style::Text => "textstyle",
+        Style::Script => "scriptstyle",
+        Style::ScriptScript => "scriptscriptstyle",
+    }
+}
+
+/// The command producing the given non-ASCII character as an identifier.
+fn identifier_command(c: char) -> Option<&'static str> {
+    symbols::name(c).filter(|&name| !c.is_ascii() && symbols::identifier(name) == Some(c))
+}
+
+/// The command producing the given non-ASCII character as an operator.
+fn operator_command(c: char) -> Option<&'static str> {
+    symbols::name(c)
+        .filter(|&name| !c.is_ascii() && symbols::operator(name).is_some_and(|(op, _)| op == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::ParserConfig,
+        parser::{Parser, Storage},
+    };
+
+    use super::*;
+
+    fn print(input: &str) -> String {
+        let storage = Storage::new();
+        let mut latex = String::new();
+        push_latex(
+            &mut latex,
+            Parser::with_storage(input, ParserConfig::default(), &storage),
+        )
+        .unwrap();
+        latex
+    }
+
+    #[test]
+    fn html_annotations() {
+        let config = ParserConfig {
+            trust: true,
+            ..Default::default()
+        };
+        let mut latex = String::new();
+        push_latex(
+            &mut latex,
+            Parser::with_config(r"\htmlId{eq1}{x} \htmlClass {a b} y^2", config),
+        )
+        .unwrap();
+        assert_eq!(latex, r"\htmlId{eq1}{x}\htmlClass{a b}y^2");
+    }
+
+    #[test]
+    fn unsupported_events() {
+        let x = Event::Content(Content::Identifier(Identifier::Char('x')));
+        let superscript = Event::Script {
+            ty: ScriptType::Superscript,
+            position: ScriptPosition::Right,
+        };
+        for events in [
+            // A fraction missing its denominator.
+            vec![Event::Visual(Visual::Fraction(None)), x],
+            vec![Event::Begin(Grouping::Normal), x],
+            vec![x, Event::End],
+            vec![Event::Visual(Visual::SquareRoot), Event::End],
+            vec![superscript, x],
+            vec![Event::Link { url: "#x" }],
+        ] {
+            let mut latex = String::new();
+            let result = push_latex(&mut latex, events.into_iter().map(Ok::<_, std::fmt::Error>));
+            assert!(
+                matches!(result, Err(PrintError::Unsupported(_))),
+                "{result:?}"
+            );
+            assert!(latex.is_empty());
+        }
+        let result = push_latex(&mut String::new(), Parser::new(r"\hat\pmod{p}"));
+        assert!(
+            matches!(result, Err(PrintError::Unsupported(_))),
+            "{result:?}"
+        );
+        let error = push_latex(&mut String::new(), Parser::new(r"x^")).unwrap_err();
+        assert!(matches!(error, PrintError::Event(_)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = [
+            r"x^2 + y_1 = z_{ij}^{2n}",
+            r"\frac{a}{b} + \dfrac{1}{2} - \tfrac{c}{d} \splitfrac{a}{b}",
+            r"\cfrac[l]{1}{1 + \cfrac[r]{1}{x}} + \cfrac{a}{b}",
+            r"\sqrt{x} + \sqrt[3]{x + 1}",
+            r"\left( \frac{a}{b} \middle| c \right) \left. x \right\} \left\{ y \right.",
+            r"\left\langle a, b \right\rangle \bigl( \Big\{ \bigg) \Biggr]",
+            r"\{ a \} [b] |c| \| d \|",
+            r"\sum_{i=1}^n i \prod\limits_k \int_0^1 \int\limits_a^b \sum\nolimits_j",
+            r"\lim_{x \to 0} \sin^2 x + \cos x \operatorname{foo}_a \operatorname{sin}\limits_b",
+            r"\varliminf_{n} a_n \injlim_k \max\nolimits_i",
+            r"\mathbf{x} + \mathrm{d}x + \mathbb R + \mathcal{A} \mathnormal{y}",
+            r"{\bf a} {\it b} {\displaystyle c} {\color{red} d}",
+            r"\hat{x} \dot y \overline{ab} \vec v \widehat{xyz} \overbrace{a+b}^n",
+            r"\overbrace{x}\nolimits^2 \underbrace{y}_1^2 \underbrace{y}^2 {\overbrace{x}}^n",
+            r"\underbrace{a+b}_{n} \underline{x} \overset{!}{=} \underset{a}{b}",
+            r"a\,b\:c\;d\!e\quad f\qquad g \kern2pt h \hspace{-0.5em} i \mathstrut",
+            r"\text{if } x \mbox{else} \colorbox{red}{text} \fcolorbox{red}{blue}{text}",
+            r"T^a{}_b{}^c R^{ab}{}_{cd} \Gamma^\mu_\nu{}^\rho {}^a_b{}^c X",
+            r"\cancel{x} \bcancel 5 \xcancel{a+b} \sout{\alpha} \not=",
+            r"\textbf{a \textit{b $x$}} \text{c \textsc{d}} \fbox{\texttt e} \textrm{}",
+            r"\emph{a \emph{b}} \text{\textit{\emph{c}}}",
+            r"\text{if $x^2 > 0$, then $y$} \fbox{$\frac{a}{b}$} \mbox{$x$}",
+            r"\llap{a $b$} \mathllap{{}} \raisebox{1pt}{$x$ y} \colorbox{red}{$x$} \fcolorbox{red}{blue}{$x$}",
+            r"\alpha\beta \Gamma x \varepsilon y",
+            r"\not= \not\in \neq",
+            r"\binom{n}{k} \genfrac{[}{]}{0pt}{}{a}{b} \genfrac{}{}{}{0}{c}{d}",
+            r"\begin{matrix} a & b \\ c & d \end{matrix} \begin{pmatrix} 1 \end{pmatrix}",
+            r"\begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \end{cases}",
+            r"\begin{array}{|l|c r|} a & b & c \\ d \end{array}",
+            r"\mathbin{\star} \mathop{x}_a \mathrel{R} \bmod",
+            r"\boxed{x} \boxed{y} \fbox{z} \framebox[2em][l]{w}",
+            r"\rlap{a} \mathllap{b} \raise2em{c} \raisebox{-1pt}{d} \vcenter{e}",
+            r"\cramped{x^2} \cramped[\scriptstyle]{y}",
+            r"\lvert x \rvert \lVert y \rVert \mathopen{|} \bigl\lvert",
+            r"\DeclarePairedDelimiter\abs|| \abs{x}^2 \abs*{y} \abs[\Big]{\frac{1}{2}}",
+            r"1 2 3.5 x' f'' g'_1^2 h''''' '' \prime'",
+            r"\# \% \& \$ \_ \backslash a~b",
+            r"\adjustlimits\lim_{n}\max_{m} a",
+            r"\shortmid \smallint_a^b",
+            r"{}^{14}_{6}\mathrm{C} {}_a x {}^b y^c {}^d \prescript{a}{}{x} \prescript{1}{2}34",
+            r"{}^a \prescript{}{b}{\prescript{c}{d}x} \prescript{\mathrm{e}}{}f",
+            r"\tensor{T}{^a_b^c} \tensor R{_{ij}^k}",
+            r"a \pmod{m} b \pod n \mod{k} \surd \sqrt{x} a\rq \lq",
+            r"\varsubsetneq \varsubsetneqq \varsupsetneqq \lvertneqq \gvertneqq \subsetneq",
+            r"\href{https://example.com/?a=1&b=2}{x + 1} \href{#eq}y^2",
+            r"{a \over b} {a \atop b} + {a \above 2pt b} {a \choose b} {a \abovewithdelims[]1pt b}",
+            r"\atop",
+            r"x^\prescript{a}{b}{c} \hat\tensor{T}{^a_b} \tensor{T}{^a_b}^c",
+            r"\left( a \middle|^2 b \right) \mod x_1 \pmod{n}^2",
+            r"\text{caf\'e \%  \_}^2 \fbox{a\ \ b} \colorbox{red}{\'e~x} \text{if $x$ \c{c}a}",
+        ];
+        // Without a storage, the text is split into pieces, which are printed as a whole.
+        let storage = Storage::new();
+        for input in inputs {
+            let events = Parser::with_storage(input, ParserConfig::default(), &storage)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_else(|error| panic!("{input}: {error}"));
+            let latex = print(input);
+            let reparsed = Parser::with_storage(&latex, ParserConfig::default(), &storage)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_else(|error| panic!("{input} printed as {latex}: {error}"));
+            assert_eq!(events, reparsed, "{input} printed as {latex}");
+        }
+    }
+
+    /// A generator of well-formed inputs, nesting commands, scripts, and infix fractions.
+    struct Generator(u64);
+
+    impl Generator {
+        const ATOMS: &'static [&'static str] = &[
+            "x",
+            "2",
+            "1.5",
+            "+",
+            "=",
+            "(",
+            ")",
+            "~",
+            r"\alpha",
+            r"\sum",
+            r"\int",
+            r"\lim",
+            r"\lim\limits",
+            r"\sin",
+            r"\varliminf",
+            r"\injlim",
+            r"\quad",
+            r"\,",
+            r"\kern1em",
+            r"\{",
+            r"\lvert",
+            r"\bigl(",
+            r"\bigr)",
+            r"\big(",
+            r"\middle|",
+            r"\surd",
+            r"\mathstrut",
+            r"\prime",
+            r"\limits",
+            r"\nolimits",
+            r"\not=",
+            r"\displaystyle",
+            r"\bf",
+            r"\color{red}",
+            r"\text{a b}",
+            r"\textbf{a $x$}",
+            r"\text{a $x^2$}",
+            r"\mbox{c}",
+            r"\fbox{a}",
+            r"\colorbox{red}{a}",
+            r"\operatorname{f}",
+            r"\mathop{x}",
+            r"\tensor{T}{^a_b}",
+            r"\overbrace{x}^{n}",
+            r"\underbrace{y}_1",
+        ];
+        const COMMANDS: &'static [(&'static str, usize)] = &[
+            ("frac", 2),
+            ("dfrac", 2),
+            ("tfrac", 2),
+            ("cfrac", 2),
+            ("binom", 2),
+            ("genfrac{(}{)}{1pt}{0}", 2),
+            ("sqrt", 1),
+            ("sqrt[3]", 1),
+            ("hat", 1),
+            ("vec", 1),
+            ("widehat", 1),
+            ("overline", 1),
+            ("underline", 1),
+            ("overbrace", 1),
+            ("underbrace", 1),
+            ("overset", 2),
+            ("underset", 2),
+            ("prescript", 3),
+            ("mathbf", 1),
+            ("mathrm", 1),
+            ("mathop", 1),
+            ("mathrel", 1),
+            ("mathbin", 1),
+            ("mathllap", 1),
+            ("boxed", 1),
+            ("fbox", 1),
+            ("cancel", 1),
+            ("sout", 1),
+            ("cramped", 1),
+        ];
+        const INFIX: &'static [&'static str] = &[
+            r"\over",
+            r"\atop",
+            r"\above 2pt",
+            r"\choose",
+            r"\atopwithdelims[]",
+            r"\overwithdelims.\}",
+        ];
+
+        fn next(&mut self) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 as usize
+        }
+
+        fn element(&mut self, depth: usize) -> String {
+            let choice = if depth == 0 { 0 } else { self.next() % 10 };
+            match choice {
+                0..=3 => Self::ATOMS[self.next() % Self::ATOMS.len()].to_owned(),
+                4..=6 => {
+                    let (name, count) = Self::COMMANDS[self.next() % Self::COMMANDS.len()];
+                    let mut element = format!("\\{name}");
+                    for _ in 0..count {
+                        element += &format!("{{{}}}", self.sequence(depth - 1));
+                    }
+                    element
+                }
+                7 => {
+                    let numerator = self.sequence(depth - 1);
+                    let infix = Self::INFIX[self.next() % Self::INFIX.len()];
+                    format!("{{{numerator} {infix} {}}}", self.sequence(depth - 1))
+                }
+                8 => format!(r"\left( {} \right)", self.sequence(depth - 1)),
+                _ => format!("{{{}}}", self.sequence(depth - 1)),
+            }
+        }
+
+        fn sequence(&mut self, depth: usize) -> String {
+            let mut sequence = String::new();
+            for _ in 0..self.next() % 4 {
+                sequence += &self.element(depth);
+                match self.next() % 6 {
+                    0 => sequence += &format!("^{{{}}}", self.sequence(depth.saturating_sub(1))),
+                    1 => sequence += &format!("_{{{}}}", self.sequence(depth.saturating_sub(1))),
+                    2 => sequence.push('\''),
+                    _ => {}
+                }
+                sequence.push(' ');
+            }
+            sequence
+        }
+    }
+
+    #[test]
+    fn generated_round_trip() {
+        let mut generator = Generator(0x2545_f491_4f6c_dd1d);
+        let storage = Storage::new();
+        for _ in 0..2000 {
+            let input = generator.sequence(3);
+            let Ok(events) = Parser::with_storage(&input, ParserConfig::default(), &storage)
+                .collect::<Result<Vec<_>, _>>()
+            else {
+                continue;
+            };
+            let latex = print(&input);
+            let reparsed = Parser::with_storage(&latex, ParserConfig::default(), &storage)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_else(|error| panic!("{input} printed as {latex}: {error}"));
+            assert_eq!(events, reparsed, "{input} printed as {latex}");
+        }
+    }
+
+    #[test]
+    fn shortest_forms() {
+        assert_eq!(print(r"\frac {a} {b}"), r"\frac{a}{b}");
+        assert_eq!(print(r"\left ( x \right )"), r"\left(x\right)");
+        assert_eq!(print(r"\alpha x"), r"\alpha x");
+        assert_eq!(print(r"\cfrac[c]{1}{2}"), r"\cfrac{1}{2}");
+        assert_eq!(print(r"\left(\frac{n}{k}\right)"), r"\binom{n}{k}");
+        assert_eq!(print(r"\thinspace \hskip 3em"), r"\,\kern3em");
+        assert_eq!(print(r"\sum\limits_{i} x_{i}"), r"\sum\limits_{i}x_{i}");
+        assert_eq!(print("x ≤ y → ℵ"), r"x\leq y\rightarrow\aleph");
+        assert_eq!(print(r"a \le b \to c"), r"a\leq b\rightarrow c");
+        assert_eq!(print(r"a~b\ c\nobreakspace d"), "a~b~c~d");
+        assert_eq!(
+            print(r"\bigl( x \Bigr) \biggm| \big|"),
+            r"\bigl(x\Bigr)\biggm|\big|"
+        );
+    }
+}
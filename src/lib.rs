@@ -3,16 +3,29 @@
 //! specification.
 
 pub(crate) mod attribute;
+#[cfg(feature = "c_api")]
+pub mod c_api;
 pub mod config;
 pub mod event;
+pub mod json;
+pub mod latex;
 pub mod mathml;
 pub mod parser;
+pub mod tree;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[doc(inline)]
 pub use parser::{Parser, ParserError};
 #[doc(inline)]
-pub use config::RenderConfig;
+pub use config::{ParserConfig, RenderConfig};
 #[doc(inline)]
-pub use mathml::{push_mathml, write_mathml};
+pub use mathml::{push_mathml, write_mathml, MathmlWriter, RenderError};
+#[doc(inline)]
+pub use json::{push_json, write_json};
+#[doc(inline)]
+pub use latex::{push_latex, PrintError};
+#[doc(inline)]
+pub use tree::{parse_tree, MathNode, TreeError};
 #[doc(inline)]
 pub use event::Event;
@@ -1,28 +1,35 @@
 //! A simple MathML Core renderer.
 //!
 //! This crate provides a "simple" `mathml` renderer which is available through the
-//! [`push_mathml`] and [`write_mathml`] functions.
+//! [`push_mathml`] and [`write_mathml`] functions, or through a [`MathmlWriter`] when the events
+//! are produced one at a time.
 
-use std::{io, iter::Peekable};
+use std::{borrow::Cow, convert::Infallible, fmt, io, iter::Peekable};
+
+use thiserror::Error;
 
 use crate::{
     attribute::{tex_to_css_em, Font},
     config::{DisplayMode, RenderConfig},
     event::{
-        ColorChange, ColorTarget, Content, Event, Identifier, Operator, ScriptPosition, ScriptType,
-        StateChange, Style, Visual,
+        AnnotationKind, ArrayColumn, AtomClass, BoxAlignment, ColorChange, ColorTarget,
+        ColumnAlignment, Content, Event, Grouping, Identifier, LapDirection, Operator,
+        ScriptPosition, ScriptType, StateChange, StrikeKind, Style, TextFont, Visual,
     },
+    parser::is_delimiter,
 };
 
-struct MathmlWriter<'a, I: Iterator, W> {
+struct Renderer<'a, I: Iterator, W> {
     input: Peekable<I>,
     writer: W,
     config: RenderConfig<'a>,
     env_stack: Vec<Environment>,
     state_stack: Vec<State<'a>>,
+    /// Whether the next operator is the opening delimiter of a `\left ... \right` group.
+    opening_fence: bool,
 }
 
-impl<'a, I, W, E> MathmlWriter<'a, I, W>
+impl<'a, I, W, E> Renderer<'a, I, W>
 where
     I: Iterator<Item = Result<Event<'a>, E>>,
     W: io::Write,
@@ -37,6 +44,9 @@ where
             border_color: None,
             background_color: None,
             style: None,
+            cramped: false,
+            adjust_limits: false,
+            text_font: TextFonts::default(),
         });
         let env_stack = Vec::with_capacity(32);
         Self {
@@ -45,6 +55,7 @@ where
             config,
             env_stack,
             state_stack,
+            opening_fence: false,
         }
     }
 
@@ -59,13 +70,18 @@ where
             border_color,
             background_color,
             style,
+            cramped,
             font: _,
+            adjust_limits: _,
+            text_font: _,
         } = *self.state();
         write!(self.writer, "<{}", tag)?;
         if text_color.is_none()
             && border_color.is_none()
             && background_color.is_none()
             && style.is_none()
+            && !cramped
+            && additional_style.is_none()
         {
             if close {
                 return self.writer.write_all(b">");
@@ -76,13 +92,18 @@ where
 
         let mut style_written = false;
         if let Some(text_color) = text_color {
-            write!(self.writer, "style=\"color: {}", text_color)?;
+            write!(self.writer, " style=\"color: {}", text_color)?;
+            style_written = true;
         }
         if let Some(border_color) = border_color {
             if style_written {
                 write!(self.writer, "; border: 0.06em solid {}", border_color)?;
             } else {
-                write!(self.writer, "style=\"border: 0.06em solid {}", border_color)?;
+                write!(
+                    self.writer,
+                    " style=\"border: 0.06em solid {}",
+                    border_color
+                )?;
                 style_written = true;
             }
         }
@@ -92,17 +113,25 @@ where
             } else {
                 write!(
                     self.writer,
-                    "style=\"background-color: {}",
+                    " style=\"background-color: {}",
                     background_color
                 )?;
                 style_written = true;
             }
         }
+        if cramped {
+            if style_written {
+                self.writer.write_all(b"; math-shift: compact")?;
+            } else {
+                self.writer.write_all(b" style=\"math-shift: compact")?;
+                style_written = true;
+            }
+        }
         if let Some(additional_style) = additional_style {
             if style_written {
                 write!(self.writer, "; {}", additional_style)?;
             } else {
-                write!(self.writer, "style=\"{}\"", additional_style)?;
+                write!(self.writer, " style=\"{}", additional_style)?;
                 style_written = true;
             }
         }
@@ -132,16 +161,54 @@ where
         self.state_stack.last().expect("state stack is empty")
     }
 
-    fn write_event(&mut self, event: Result<Event<'a>, E>) -> io::Result<()> {
-        match event {
+    /// Write the invisible function application operator following a function name (e.g.,
+    /// `\sin`).
+    ///
+    /// A thin space is added after the operator, unless the function is followed by an operator
+    /// or a delimited group, as in `\sin(x)`, or is at the end of its group.
+    fn write_function_application(&mut self) -> io::Result<()> {
+        self.writer.write_all("<mo>\u{2061}</mo>".as_bytes())?;
+        match self.input.peek() {
+            None
+            | Some(Ok(
+                Event::Content(Content::Operator(_))
+                | Event::Begin(Grouping::LeftRight)
+                | Event::End,
+            )) => Ok(()),
+            _ => self.writer.write_all(b"<mspace width=\"0.1667em\" />"),
+        }
+    }
+
+    /// The additional style needed to render the current font, when it has no Unicode
+    /// equivalent.
+    fn font_style(&self) -> Option<&'static str> {
+        (self.state().font == Some(Font::Slanted)).then_some("font-style: oblique")
+    }
+
+    /// Write the space surrounding an atom of a given class, if any.
+    fn write_atom_space(&mut self, width: &str) -> io::Result<()> {
+        if width.is_empty() {
+            return Ok(());
+        }
+        write!(self.writer, "<mspace width=\"{}\" />", width)
+    }
+
+    fn write_event(&mut self, event: Result<Event<'a>, E>) -> Result<(), RenderError> {
+        Ok(match event {
             Ok(Event::Content(content)) => match content {
                 Content::Text(text) => {
-                    self.open_tag("mtext", None, true)?;
+                    let font_style = self.state().text_font.style();
+                    self.open_tag("mtext", font_style.as_deref(), true)?;
                     self.writer.write_all(text.as_bytes())?;
+                    // The text of an argument can be split into consecutive events.
+                    while let Some(Ok(Event::Content(Content::Text(text)))) = self.input.peek() {
+                        self.writer.write_all(text.as_bytes())?;
+                        self.input.next();
+                    }
                     self.writer.write_all(b"</mtext>")
                 }
                 Content::Number(number) => {
-                    self.open_tag("mn", None, true)?;
+                    self.open_tag("mn", self.font_style(), true)?;
                     let buf = &mut [0u8; 4];
                     number.chars().try_for_each(|c| {
                         let content = self.state().font.map_or(c, |v| v.map_char(c));
@@ -161,25 +228,32 @@ where
                         self.writer.write_all(str.as_bytes())?;
                         self.writer.write_all(b"</mi>")?;
 
-                        let to_append = "<mo>\u{2061}</mo><mspace width=\"0.1667em\" />";
                         if let Some(Environment {
-                            env: EnvironmentType::Script { append, .. },
+                            env:
+                                EnvironmentType::Script {
+                                    function_application,
+                                    ..
+                                },
                             ..
                         }) = self.env_stack.last_mut()
                         {
-                            *append = to_append;
+                            *function_application = true;
                             Ok(())
                         } else {
-                            self.writer.write_all(to_append.as_bytes())
+                            self.write_function_application()
                         }
                     }
                     Identifier::Char(content) => {
-                        self.open_tag("mi", None, false)?;
+                        self.open_tag("mi", self.font_style(), false)?;
                         let content = match (
                             self.state().font,
                             self.config.math_style.should_be_upright(content),
                         ) {
-                            (Some(Font::UpRight), _) | (None, true) => {
+                            (Some(font @ (Font::UpRight | Font::SmallCaps | Font::Slanted)), _) => {
+                                self.writer.write_all(b" mathvariant=\"normal\">")?;
+                                font.map_char(content)
+                            }
+                            (None, true) => {
                                 self.writer.write_all(b" mathvariant=\"normal\">")?;
                                 content
                             }
@@ -214,7 +288,17 @@ where
                     size,
                 }) => {
                     self.open_tag("mo", None, false)?;
-                    if let Some(stretchy) = stretchy {
+                    // The delimiters of a `\left ... \right` group stretch to the height of its
+                    // content.
+                    let fence = std::mem::take(&mut self.opening_fence)
+                        || (self.env_stack.last().map(|env| env.env)
+                            == Some(EnvironmentType::LeftRight)
+                            && matches!(self.input.peek(), Some(Ok(Event::End)))
+                            && is_delimiter(content));
+                    if fence {
+                        self.writer
+                            .write_all(b" stretchy=\"true\" fence=\"true\"")?;
+                    } else if let Some(stretchy) = stretchy {
                         write!(self.writer, " stretchy=\"{}\"", stretchy)?;
                     }
                     if deny_movable_limits {
@@ -245,8 +329,10 @@ where
                     self.writer.write_all(b"</mo>")
                 }
             },
-            Ok(Event::Begin(_)) => {
+            Ok(Event::Begin(grouping)) => {
                 let mut font = self.state().font;
+                let mut text_font = self.state().text_font;
+                let saved_state = *self.state();
                 let old_state = self.state_stack.last_mut().expect("state stack is empty");
                 while let Some(Ok(Event::StateChange(state_change))) = self.input.peek() {
                     match state_change {
@@ -257,33 +343,102 @@ where
                             ColorTarget::Border => old_state.border_color = Some(color),
                         },
                         StateChange::Style(style) => old_state.style = Some(*style),
+                        StateChange::Cramped => old_state.cramped = true,
+                        StateChange::TextFont(new_font) => text_font.set(*new_font),
                     }
                     self.input.next();
                 }
-                self.open_tag("mrow", None, true)?;
+                let env = match grouping {
+                    Grouping::Array(_) | Grouping::Matrix | Grouping::Cases | Grouping::Align => {
+                        let mut columns = 0;
+                        if grouping == Grouping::Cases {
+                            self.open_tag("mrow", None, true)?;
+                            self.writer
+                                .write_all(b"<mo>{</mo><mtable columnalign=\"left\">")?;
+                        } else if grouping == Grouping::Align {
+                            self.open_tag("mtable", None, false)?;
+                            self.writer
+                                .write_all(b" displaystyle=\"true\" columnalign=\"right left\">")?;
+                        } else if let Grouping::Array(spec) = grouping {
+                            columns = self.write_array_table(spec.as_slice())?;
+                        } else {
+                            self.open_tag("mtable", None, true)?;
+                        }
+                        self.writer.write_all(b"<mtr><mtd>")?;
+                        EnvironmentType::Table {
+                            grouping,
+                            column: 0,
+                            columns,
+                        }
+                    }
+                    Grouping::AtomClass(class) => {
+                        self.open_tag("mrow", None, true)?;
+                        self.write_atom_space(atom_spacing(class).0)?;
+                        EnvironmentType::Group(Some(class))
+                    }
+                    Grouping::LeftRight => {
+                        self.open_tag("mrow", None, true)?;
+                        self.opening_fence = matches!(
+                            self.input.peek(),
+                            Some(Ok(Event::Content(Content::Operator(Operator { content, .. }))))
+                                if is_delimiter(*content)
+                        );
+                        EnvironmentType::LeftRight
+                    }
+                    _ => {
+                        self.open_tag("mrow", None, true)?;
+                        EnvironmentType::Group(None)
+                    }
+                };
+                // The state changes only apply to the `mrow` of the group, and must not leak into
+                // the following elements.
+                *self.state_stack.last_mut().expect("state stack is empty") = saved_state;
                 self.state_stack.push(State {
                     font,
                     text_color: None,
                     border_color: None,
                     background_color: None,
                     style: None,
+                    cramped: false,
+                    adjust_limits: grouping == Grouping::AdjustLimits,
+                    text_font,
                 });
-                self.env_stack
-                    .push(Environment::new(EnvironmentType::Group));
+                self.env_stack.push(Environment::new(env));
                 Ok(())
             }
             Ok(Event::End) => {
-                let env = self
-                    .env_stack
-                    .pop()
-                    .expect("cannot pop an environment in group end");
-                if env.env != EnvironmentType::Group {
-                    panic!("unexpected environment in group end");
-                }
-                self.state_stack
-                    .pop()
-                    .expect("cannot pop a state in group end");
-                self.writer.write_all(b"</mrow>")
+                let Some(env) = self.env_stack.pop() else {
+                    return Err(RenderError::Unsupported(
+                        "an `End` event which does not close a group",
+                    ));
+                };
+                self.state_stack.pop();
+                match env.env {
+                    EnvironmentType::Group(class) => {
+                        if let Some(class) = class {
+                            self.write_atom_space(atom_spacing(class).1)?;
+                        }
+                        self.writer.write_all(b"</mrow>")
+                    }
+                    EnvironmentType::LeftRight => self.writer.write_all(b"</mrow>"),
+                    EnvironmentType::Table {
+                        grouping,
+                        column,
+                        columns,
+                    } => {
+                        self.pad_row(column, columns)?;
+                        self.writer.write_all(b"</mtd></mtr></mtable>")?;
+                        if grouping == Grouping::Cases {
+                            self.writer.write_all(b"</mrow>")?;
+                        }
+                        Ok(())
+                    }
+                    _ => {
+                        return Err(RenderError::Unsupported(
+                            "an `End` event in place of an element",
+                        ))
+                    }
+                }
             }
             Ok(Event::Visual(visual)) => match visual {
                 Visual::Fraction(dim) => {
@@ -295,6 +450,23 @@ where
                     }
                     self.writer.write_all(b">")
                 }
+                Visual::SplitFraction => {
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::Fraction));
+                    self.open_tag("mfrac", None, false)?;
+                    self.writer
+                        .write_all(b" linethickness=\"0\" numalign=\"left\" denomalign=\"right\">")
+                }
+                Visual::ContinuedFraction(alignment) => {
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::Fraction));
+                    self.open_tag("mfrac", None, false)?;
+                    match alignment {
+                        BoxAlignment::Left => self.writer.write_all(b" numalign=\"left\">"),
+                        BoxAlignment::Center => self.writer.write_all(b">"),
+                        BoxAlignment::Right => self.writer.write_all(b" numalign=\"right\">"),
+                    }
+                }
                 Visual::SquareRoot => {
                     self.env_stack.push(Environment::new(EnvironmentType::Sqrt));
                     self.open_tag("msqrt", None, true)
@@ -303,13 +475,81 @@ where
                     self.env_stack.push(Environment::new(EnvironmentType::Root));
                     self.open_tag("mroot", None, true)
                 }
+                Visual::Accent => {
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::Accent));
+                    self.open_tag("mover", None, false)?;
+                    self.writer.write_all(b" accent=\"true\">")
+                }
+                Visual::Boxed => {
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::Boxed));
+                    self.open_tag("menclose", None, false)?;
+                    self.writer.write_all(b" notation=\"box\">")
+                }
+                Visual::Framed { width, alignment } => {
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::Boxed));
+                    match width {
+                        Some(width) => {
+                            let style = format!(
+                                "display: inline-block; width: {}em; text-align: {}",
+                                tex_to_css_em(width),
+                                match alignment {
+                                    BoxAlignment::Left => "left",
+                                    BoxAlignment::Center => "center",
+                                    BoxAlignment::Right => "right",
+                                }
+                            );
+                            self.open_tag("menclose", Some(&style), false)?;
+                        }
+                        None => self.open_tag("menclose", None, false)?,
+                    }
+                    self.writer.write_all(b" notation=\"box\">")
+                }
+                Visual::Lap(direction) => {
+                    self.env_stack.push(Environment::new(EnvironmentType::Lap));
+                    self.open_tag("mpadded", None, false)?;
+                    self.writer.write_all(match direction {
+                        LapDirection::Left => b" width=\"0\" lspace=\"-100%\">",
+                        LapDirection::Right => b" width=\"0\">",
+                        LapDirection::Center => b" width=\"0\" lspace=\"-50%\">",
+                    })
+                }
+                Visual::Raise(dim) => {
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::Raise));
+                    self.open_tag("mpadded", None, false)?;
+                    write!(self.writer, " voffset=\"{}em\">", tex_to_css_em(dim))
+                }
+                Visual::VCenter => {
+                    // Tables are vertically centered on the math axis.
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::VCenter));
+                    self.open_tag("mtable", None, true)?;
+                    self.writer.write_all(b"<mtr><mtd>")
+                }
+                Visual::Strike(kind) => {
+                    self.env_stack
+                        .push(Environment::new(EnvironmentType::Boxed));
+                    self.open_tag("menclose", None, false)?;
+                    self.writer.write_all(match kind {
+                        StrikeKind::UpDiagonal => b" notation=\"updiagonalstrike\">",
+                        StrikeKind::DownDiagonal => b" notation=\"downdiagonalstrike\">",
+                        StrikeKind::Cross => b" notation=\"updiagonalstrike downdiagonalstrike\">",
+                        StrikeKind::Horizontal => b" notation=\"horizontalstrike\">",
+                    })
+                }
                 Visual::Negation => {
                     self.env_stack
                         .push(Environment::new(EnvironmentType::Negate));
+                    let Some(next) = self.input.peek() else {
+                        return Err(RenderError::Unsupported(
+                            "a negation at the end of the events",
+                        ));
+                    };
                     if !matches!(
-                        self.input
-                            .peek()
-                            .expect("need to be a next event after negation"),
+                        next,
                         Ok(Event::Content(Content::Operator(_)))
                             | Ok(Event::Content(Content::Identifier(Identifier::Char(_))))
                     ) {
@@ -320,21 +560,77 @@ where
                 }
             },
 
+            Ok(Event::Script {
+                ty,
+                position: ScriptPosition::Prescript,
+            }) => {
+                let env = EnvironmentType::Prescript(ty);
+                self.env_stack.push(Environment::new(env));
+                self.open_tag(env.tag(), None, true)
+            }
+            Ok(Event::Script {
+                ty,
+                position: ScriptPosition::Tensor,
+            }) => {
+                // The indices of the tensor, along with the scripts of its innermost base, are
+                // written as pairs of subscript and superscript following the same base.
+                let mut indices = vec![ty];
+                let mut children = 1 + script_count(ty);
+                while let Some(Ok(Event::Script {
+                    ty,
+                    position: position @ (ScriptPosition::Tensor | ScriptPosition::Right),
+                })) = self.input.peek()
+                {
+                    if children + script_count(*ty) > 32 {
+                        break;
+                    }
+                    indices.push(*ty);
+                    children += script_count(*ty);
+                    let innermost = *position == ScriptPosition::Right;
+                    self.input.next();
+                    if innermost {
+                        break;
+                    }
+                }
+                let (mut none_before, mut none_after) = (0, 0);
+                let mut child = 1;
+                for ty in indices.into_iter().rev() {
+                    match ty {
+                        ScriptType::Subscript => none_after |= 1 << child,
+                        ScriptType::Superscript => none_before |= 1 << child,
+                        ScriptType::SubSuperscript => {}
+                    }
+                    child += script_count(ty);
+                }
+                let env = EnvironmentType::Tensor {
+                    children,
+                    none_before,
+                    none_after,
+                };
+                self.env_stack.push(Environment::new(env));
+                self.open_tag(env.tag(), None, true)
+            }
             Ok(Event::Script { ty, position }) => {
                 let state = self.state();
                 let above_below = match position {
                     ScriptPosition::Right => false,
                     ScriptPosition::AboveBelow => true,
                     ScriptPosition::Movable => {
-                        state.style == Some(Style::Display)
+                        state.adjust_limits
+                            || state.style == Some(Style::Display)
                             || (state.style.is_none()
                                 && self.config.display_mode == DisplayMode::Block)
                     }
+                    ScriptPosition::Prescript | ScriptPosition::Tensor => {
+                        return Err(RenderError::Unsupported(
+                            "prescripts or tensor indices rendered as scripts",
+                        ))
+                    }
                 };
                 let env = EnvironmentType::Script {
                     ty,
                     above_below,
-                    append: "",
+                    function_application: false,
                 };
                 self.env_stack.push(Environment::new(env));
                 self.open_tag(env.tag(), None, true)
@@ -373,30 +669,167 @@ where
                         ColorTarget::Background => state.background_color = Some(color),
                     },
                     StateChange::Style(style) => state.style = Some(style),
+                    StateChange::Cramped => state.cramped = true,
+                    StateChange::TextFont(font) => state.text_font.set(font),
                 }
                 Ok(())
             }
-            // TODO: handle math environments
-            Ok(_) => Ok(()),
-            Err(e) => {
-                let error_color = self.config.error_color;
-                write!(
-                    self.writer,
-                    "<merror style=\"border-color: #{:x}{:x}{:x}\"><mtext>",
-                    error_color.0, error_color.1, error_color.2
-                )?;
-                self.writer.write_all(e.to_string().as_bytes())?;
-                self.writer.write_all(b"</mtext></merror>")
+            Ok(Event::Alignment) => {
+                if let Some(Environment {
+                    env: EnvironmentType::Table { column, .. },
+                    ..
+                }) = self.env_stack.last_mut()
+                {
+                    *column += 1;
+                }
+                self.writer.write_all(b"</mtd><mtd>")
+            }
+            // A line break ending the last row of the environment does not start a new row.
+            Ok(Event::NewLine) => match self.input.peek() {
+                Some(Ok(Event::End)) => Ok(()),
+                _ => {
+                    if let Some(Environment {
+                        env:
+                            EnvironmentType::Table {
+                                column, columns, ..
+                            },
+                        ..
+                    }) = self.env_stack.last_mut()
+                    {
+                        let (row_columns, table_columns) = (*column, *columns);
+                        *columns = table_columns.max(row_columns + 1);
+                        *column = 0;
+                        self.pad_row(row_columns, table_columns)?;
+                    }
+                    self.writer.write_all(b"</mtd></mtr><mtr><mtd>")
+                }
+            },
+            Ok(Event::UnknownCommand(name)) => self.write_error(&format!("\\{name}")),
+            Ok(Event::Annotation { kind, value }) => {
+                self.env_stack
+                    .push(Environment::new(EnvironmentType::Annotation));
+                let value = escape_attribute(value);
+                match kind {
+                    AnnotationKind::Style => self.open_tag("mrow", Some(&value), true),
+                    AnnotationKind::Class | AnnotationKind::Id => {
+                        self.open_tag("mrow", None, false)?;
+                        let name = if kind == AnnotationKind::Class {
+                            "class"
+                        } else {
+                            "id"
+                        };
+                        write!(self.writer, " {name}=\"{value}\">")
+                    }
+                }
+            }
+            Ok(Event::Link { url }) => {
+                self.env_stack
+                    .push(Environment::new(EnvironmentType::Annotation));
+                self.open_tag("mrow", None, false)?;
+                write!(self.writer, " href=\"{}\">", escape_attribute(url))
+            }
+            Err(e) => self.write_error(&e.to_string()),
+        }?)
+    }
+
+    /// Open the `mtable` of an `array` environment, with the alignment of its columns and the
+    /// vertical lines between them, and return its number of columns.
+    fn write_array_table(&mut self, spec: &[ArrayColumn]) -> io::Result<usize> {
+        let (first, last) = (spec.first(), spec.last());
+        let border = match (first, last) {
+            (Some(ArrayColumn::VerticalLine), Some(ArrayColumn::VerticalLine))
+                if spec.len() > 1 =>
+            {
+                Some("border-left: 0.06em solid; border-right: 0.06em solid")
+            }
+            (Some(ArrayColumn::VerticalLine), _) => Some("border-left: 0.06em solid"),
+            (_, Some(ArrayColumn::VerticalLine)) => Some("border-right: 0.06em solid"),
+            _ => None,
+        };
+        self.open_tag("mtable", border, false)?;
+
+        let mut alignments = Vec::new();
+        let mut lines = Vec::new();
+        let mut line = false;
+        for column in spec {
+            match column {
+                ArrayColumn::Column(alignment) => {
+                    if !alignments.is_empty() {
+                        lines.push(if line { "solid" } else { "none" });
+                    }
+                    alignments.push(match alignment {
+                        ColumnAlignment::Left => "left",
+                        ColumnAlignment::Center => "center",
+                        ColumnAlignment::Right => "right",
+                    });
+                    line = false;
+                }
+                ArrayColumn::VerticalLine => line = true,
+            }
+        }
+        if !alignments.is_empty() {
+            write!(self.writer, " columnalign=\"{}\"", alignments.join(" "))?;
+        }
+        if lines.contains(&"solid") {
+            write!(self.writer, " columnlines=\"{}\"", lines.join(" "))?;
+        }
+        self.writer.write_all(b">")?;
+        Ok(alignments.len())
+    }
+
+    /// Write the empty cells padding a row with `column + 1` cells to the given number of
+    /// columns.
+    fn pad_row(&mut self, column: usize, columns: usize) -> io::Result<()> {
+        for _ in column + 1..columns {
+            self.writer.write_all(b"</mtd><mtd>")?;
+        }
+        Ok(())
+    }
+
+    fn write_error(&mut self, message: &str) -> io::Result<()> {
+        let error_color = self.config.error_color;
+        write!(
+            self.writer,
+            "<merror style=\"border-color: #{:x}{:x}{:x}\"><mtext>",
+            error_color.0, error_color.1, error_color.2
+        )?;
+        self.writer.write_all(escape_text(message).as_bytes())?;
+        self.writer.write_all(b"</mtext></merror>")
+    }
+
+    /// The reason why the event cannot be rendered where it occurs, if it cannot.
+    ///
+    /// The events produced by the parser are always supported, but other iterators of events may
+    /// not form a valid tree of elements.
+    fn unsupported(&self, event: &Event<'a>) -> Option<&'static str> {
+        match event {
+            Event::Alignment | Event::NewLine
+                if !self
+                    .env_stack
+                    .iter()
+                    .any(|env| matches!(env.env, EnvironmentType::Table { .. })) =>
+            {
+                Some("an alignment or a line break outside of an environment")
             }
+            _ => None,
         }
     }
 
-    fn write(mut self) -> io::Result<()> {
+    fn write(mut self) -> Result<(), RenderError> {
         // Safety: this function must only write valid utf-8 to the writer.
         // How is the writer used?:
         // - using `write_all` with a utf-8 string.
         // - using `write!` with a utf-8 string, and the parameters must all be valid utf-8 since
         //      they are formatted using the `Display` trait.
+        self.write_start()?;
+        while let Some(event) = self.input.next() {
+            self.write_next(event)?;
+        }
+        self.write_end()
+    }
+
+    /// Open the `math` element.
+    fn write_start(&mut self) -> io::Result<()> {
         write!(
             self.writer,
             "<math display=\"{}\"",
@@ -406,34 +839,121 @@ where
             self.writer
                 .write_all(b" xmlns=\"http://www.w3.org/1998/Math/MathML\"")?;
         }
+        for (name, value) in [("id", self.config.id), ("class", self.config.class)] {
+            if let Some(value) = value {
+                write!(self.writer, " {name}=\"{}\"", escape_attribute(value))?;
+            }
+        }
         self.writer.write_all(b">")?;
         if self.config.annotation.is_some() {
             self.writer.write_all(b"<semantics>")?;
         }
+        Ok(())
+    }
 
-        while let Some(event) = self.input.next() {
-            self.write_event(event)?;
+    /// Write the given event, and close the elements it completes.
+    fn write_next(&mut self, event: Result<Event<'a>, E>) -> Result<(), RenderError> {
+        if let Some(reason) = event
+            .as_ref()
+            .ok()
+            .and_then(|event| self.unsupported(event))
+        {
+            return Err(RenderError::Unsupported(reason));
+        }
+        self.write_event(event)?;
 
-            while let Some(Environment { env, count }) = self.env_stack.last_mut() {
-                if *count == Some(0) {
-                    self.writer.write_all(b"</")?;
-                    self.writer.write_all(env.tag().as_bytes())?;
-                    self.writer.write_all(b">")?;
-                    if let EnvironmentType::Script { append, .. } = env {
-                        self.writer.write_all(append.as_bytes())?;
+        while let Some(Environment { env, count }) = self.env_stack.last_mut() {
+            if *count == Some(0) {
+                let env = *env;
+                if env == EnvironmentType::VCenter {
+                    self.writer.write_all(b"</mtd></mtr>")?;
+                }
+                if env == EnvironmentType::Prescript(ScriptType::Subscript) {
+                    self.writer.write_all(b"<none />")?;
+                }
+                if let EnvironmentType::Tensor {
+                    children,
+                    none_after,
+                    ..
+                } = env
+                {
+                    if none_after & (1 << (children - 1)) != 0 {
+                        self.writer.write_all(b"<none />")?;
                     }
-                    self.env_stack.pop();
-                    continue;
                 }
-                if let Some(count) = count {
-                    *count -= 1;
+                self.writer.write_all(b"</")?;
+                self.writer.write_all(env.tag().as_bytes())?;
+                self.writer.write_all(b">")?;
+                self.env_stack.pop();
+                if let EnvironmentType::Script {
+                    function_application: true,
+                    ..
+                } = env
+                {
+                    match self.env_stack.last_mut() {
+                        // The script is the base of another script, e.g., `\varliminf_n`, so
+                        // the function application must come after the outer script.
+                        Some(Environment {
+                            env:
+                                outer @ EnvironmentType::Script {
+                                    function_application: false,
+                                    ..
+                                },
+                            count,
+                        }) if count.map(|count| count + 1) == Environment::new(*outer).count => {
+                            if let EnvironmentType::Script {
+                                function_application,
+                                ..
+                            } = outer
+                            {
+                                *function_application = true;
+                            }
+                        }
+                        _ => self.write_function_application()?,
+                    }
                 }
-                break;
+                continue;
+            }
+            if let Some(count) = count {
+                *count -= 1;
             }
+            // The prescripts follow the base, and are written as pairs of subscript and
+            // superscript.
+            if let EnvironmentType::Prescript(ty) = *env {
+                if count.map(|count| count + 2) == Environment::new(*env).count {
+                    self.writer.write_all(b"<mprescripts />")?;
+                    if ty == ScriptType::Superscript {
+                        self.writer.write_all(b"<none />")?;
+                    }
+                }
+            }
+            // The empty scripts of the tensor indices are written around the others.
+            if let EnvironmentType::Tensor {
+                children,
+                none_before,
+                none_after,
+            } = *env
+            {
+                if let Some(child) = count.and_then(|count| (children - 2).checked_sub(count)) {
+                    if none_after & (1 << child) != 0 {
+                        self.writer.write_all(b"<none />")?;
+                    }
+                    if none_before & (1 << (child + 1)) != 0 {
+                        self.writer.write_all(b"<none />")?;
+                    }
+                }
+            }
+            break;
         }
+        Ok(())
+    }
 
+    /// Close the `math` element, once all the events are written.
+    fn write_end(&mut self) -> Result<(), RenderError> {
         if !self.env_stack.is_empty() || self.state_stack.len() != 1 {
-            panic!("unbalanced environment stack or state stack");
+            return Err(RenderError::Unsupported(
+                "the events end inside of a group or an element",
+            ));
         }
 
         if let Some(annotation) = self.config.annotation {
@@ -444,7 +964,29 @@ where
             )?;
             self.writer.write_all(b"</semantics>")?;
         }
-        self.writer.write_all(b"</math>")
+        self.writer.write_all(b"</math>")?;
+        Ok(())
+    }
+}
+
+/// An error encountered while rendering events to MathML.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RenderError {
+    /// The writer failed to write the MathML.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The event cannot be rendered where it occurs, e.g., an [`Event::End`] which does not close
+    /// a group, or the events end before an element is complete.
+    #[error("unsupported event: {0}")]
+    Unsupported(&'static str),
+}
+
+/// The number of scripts of the given type.
+fn script_count(ty: ScriptType) -> u8 {
+    match ty {
+        ScriptType::SubSuperscript => 2,
+        ScriptType::Subscript | ScriptType::Superscript => 1,
     }
 }
 
@@ -459,11 +1001,18 @@ impl Environment {
         Self {
             env,
             count: match env {
-                EnvironmentType::Group => None,
+                EnvironmentType::Group(_) | EnvironmentType::LeftRight => None,
+                EnvironmentType::Table { .. } => None,
                 EnvironmentType::Fraction => Some(2),
                 EnvironmentType::Root => Some(2),
                 EnvironmentType::Sqrt => Some(1),
                 EnvironmentType::Negate => Some(1),
+                EnvironmentType::Accent => Some(2),
+                EnvironmentType::Boxed => Some(1),
+                EnvironmentType::Lap => Some(1),
+                EnvironmentType::Raise => Some(1),
+                EnvironmentType::VCenter => Some(1),
+                EnvironmentType::Annotation => Some(1),
                 EnvironmentType::Script {
                     ty: ScriptType::Subscript,
                     ..
@@ -476,6 +1025,9 @@ impl Environment {
                     ty: ScriptType::SubSuperscript,
                     ..
                 } => Some(3),
+                EnvironmentType::Prescript(ScriptType::SubSuperscript) => Some(3),
+                EnvironmentType::Prescript(_) => Some(2),
+                EnvironmentType::Tensor { children, .. } => Some(children),
             },
         }
     }
@@ -483,28 +1035,69 @@ impl Environment {
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum EnvironmentType {
-    Group,
+    /// A group, which may form an atom of a given class (e.g., `\mathbin{...}`).
+    Group(Option<AtomClass>),
+    /// A `\left ... \right` group, whose delimiters are stretched.
+    LeftRight,
+    /// A mathematical environment (e.g., `matrix`), whose content is laid out in a table.
+    ///
+    /// `column` is the index of the current cell in its row, and `columns` is the number of
+    /// columns of the table known so far, to which shorter rows are padded.
+    Table {
+        grouping: Grouping,
+        column: usize,
+        columns: usize,
+    },
     Fraction,
     Root,
     Sqrt,
     Negate,
+    Accent,
+    Boxed,
+    Lap,
+    Raise,
+    VCenter,
+    /// An element annotated with an HTML attribute, or a link, which is set on the `mrow`
+    /// wrapping it.
+    Annotation,
     Script {
         ty: ScriptType,
         above_below: bool,
         // TODO: This is a hotfix to append things after groupings for the commands stuff.
         // Shoud probably be refactored into it's own type of things to do, a bit like `Instruction`.
-        append: &'static str,
+        /// Whether a function application should be written after the script, when its base is
+        /// a function name (e.g., `\sin^2 x`).
+        function_application: bool,
+    },
+    /// Scripts placed before their base, which is the first element of the environment.
+    Prescript(ScriptType),
+    /// The indices of a tensor following their base, which is the first element of the
+    /// environment.
+    ///
+    /// The bits of `none_before` and `none_after` are set for the children (by index) that must
+    /// be preceded or followed by an empty script, since each index is a pair of scripts.
+    Tensor {
+        children: u8,
+        none_before: u32,
+        none_after: u32,
     },
 }
 
 impl EnvironmentType {
     fn tag(&self) -> &'static str {
         match self {
-            EnvironmentType::Group => "mrow",
+            EnvironmentType::Group(_) | EnvironmentType::LeftRight => "mrow",
+            EnvironmentType::Table { .. } => "mtable",
             EnvironmentType::Fraction => "mfrac",
             EnvironmentType::Root => "mroot",
             EnvironmentType::Sqrt => "msqrt",
             EnvironmentType::Negate => "mrow",
+            EnvironmentType::Accent => "mover",
+            EnvironmentType::Boxed => "menclose",
+            EnvironmentType::Lap => "mpadded",
+            EnvironmentType::Raise => "mpadded",
+            EnvironmentType::VCenter => "mtable",
+            EnvironmentType::Annotation => "mrow",
             EnvironmentType::Script {
                 ty: ScriptType::Subscript,
                 above_below: false,
@@ -535,6 +1128,7 @@ impl EnvironmentType {
                 above_below: true,
                 ..
             } => "munderover",
+            EnvironmentType::Prescript(_) | EnvironmentType::Tensor { .. } => "mmultiscripts",
         }
     }
 }
@@ -546,34 +1140,865 @@ struct State<'a> {
     border_color: Option<&'a str>,
     background_color: Option<&'a str>,
     style: Option<Style>,
+    cramped: bool,
+    /// Whether movable limits should be placed above and below their operator, as is the case
+    /// inside of `\adjustlimits`.
+    adjust_limits: bool,
+    text_font: TextFonts,
+}
+
+/// The text fonts in effect, which only apply to text content.
+#[derive(Debug, Clone, Copy, Default)]
+struct TextFonts {
+    bold: bool,
+    shape: Option<TextFont>,
+    family: Option<TextFont>,
+}
+
+impl TextFonts {
+    /// Compose the text font with the ones in effect, replacing the font changing the same
+    /// attribute.
+    fn set(&mut self, font: TextFont) {
+        match font {
+            TextFont::Bold => self.bold = true,
+            TextFont::Italic | TextFont::SmallCaps => self.shape = Some(font),
+            TextFont::Emphasis => {
+                self.shape = (self.shape != Some(TextFont::Italic)).then_some(TextFont::Italic)
+            }
+            TextFont::Roman | TextFont::SansSerif | TextFont::Monospace => self.family = Some(font),
+        }
+    }
+
+    /// The CSS style of text in these fonts, if they are not the default ones.
+    fn style(&self) -> Option<String> {
+        let mut declarations = Vec::new();
+        if self.bold {
+            declarations.push("font-weight: bold");
+        }
+        match self.shape {
+            Some(TextFont::Italic) => declarations.push("font-style: italic"),
+            Some(TextFont::SmallCaps) => declarations.push("font-variant: small-caps"),
+            _ => {}
+        }
+        match self.family {
+            Some(TextFont::Roman) => declarations.push("font-family: serif"),
+            Some(TextFont::SansSerif) => declarations.push("font-family: sans-serif"),
+            Some(TextFont::Monospace) => declarations.push("font-family: monospace"),
+            _ => {}
+        }
+        (!declarations.is_empty()).then(|| declarations.join("; "))
+    }
+}
+
+/// Escape the characters of the given string which are special in an attribute value.
+fn escape_attribute(value: &str) -> Cow<'_, str> {
+    escape(value, &['&', '<', '"'])
+}
+
+/// Escape the characters of the given string which are special in the content of an element.
+fn escape_text(value: &str) -> Cow<'_, str> {
+    escape(value, &['&', '<'])
+}
+
+fn escape<'s>(value: &'s str, special: &[char]) -> Cow<'s, str> {
+    if !value.contains(special) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            _ if !special.contains(&c) => escaped.push(c),
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// An adapter writing the output of the MathML renderer, or of another writer producing utf-8,
+/// into a [`fmt::Write`].
+pub(crate) struct FmtWriter<W>(pub(crate) W);
+
+impl<W: fmt::Write> io::Write for FmtWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The writers only write valid utf-8, one string at a time.
+        let str = std::str::from_utf8(buf)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        self.0.write_str(str).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Takes a [`Parser`], or any `Iterator<Item = Result<Event<'_>, E>>` as input, and renders a
-/// string of MathML into the input string.
+/// string of MathML into the given [`fmt::Write`], such as a `&mut String`.
+///
+/// The MathML is written as the events are consumed, without building an intermediate
+/// representation of the whole output.
+///
+/// Errors in the events are rendered as `merror` elements, and a [`RenderError`] is only returned
+/// when the events do not form valid elements (see [`RenderError::Unsupported`]).
 ///
 /// [`Parser`]: crate::parser::Parser
-pub fn push_mathml<'a, I, E>(
-    string: &mut String,
+pub fn push_mathml<'a, W, I, E>(
+    writer: W,
     parser: I,
     config: RenderConfig<'a>,
-) -> io::Result<()>
+) -> Result<(), RenderError>
 where
+    W: fmt::Write,
     I: Iterator<Item = Result<Event<'a>, E>>,
     E: std::error::Error,
 {
-    // SAFETY: The MathmlWriter guarantees that all writes to the writer are valid utf-8.
-    MathmlWriter::new(parser, unsafe { string.as_mut_vec() }, config).write()
+    Renderer::new(parser, FmtWriter(writer), config).write()
 }
 
 /// Takes a [`Parser`], or any `Iterator<Item = Result<Event<'_>, E>>`, as input and renders the
 /// MathML into the given writer.
 ///
 /// [`Parser`]: crate::parser::Parser
-pub fn write_mathml<'a, I, W, E>(writer: W, parser: I, config: RenderConfig<'a>) -> io::Result<()>
+pub fn write_mathml<'a, I, W, E>(
+    writer: W,
+    parser: I,
+    config: RenderConfig<'a>,
+) -> Result<(), RenderError>
 where
     I: Iterator<Item = Result<Event<'a>, E>>,
     W: io::Write,
     E: std::error::Error,
 {
-    MathmlWriter::new(parser, writer, config).write()
+    Renderer::new(parser, writer, config).write()
+}
+
+/// A MathML renderer to which the events are pushed one at a time, instead of being pulled from
+/// an iterator as in [`write_mathml`].
+///
+/// The MathML of an event is written into the writer once the events following it are pushed,
+/// since its rendering may depend on them. Only the last events pushed are kept until then, and
+/// the remaining ones are written when the writer is [finished](MathmlWriter::finish).
+///
+/// ```rust
+/// use pulldown_latex::{mathml::MathmlWriter, Parser, RenderConfig};
+///
+/// let mut writer = MathmlWriter::new(Vec::new(), RenderConfig::default());
+/// for event in Parser::new(r"\frac{1}{2}") {
+///     writer.push_event(event.unwrap()).unwrap();
+/// }
+/// let mathml = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// assert_eq!(
+///     mathml,
+///     "<math display=\"inline\"><mfrac><mrow><mn>1</mn></mrow><mrow><mn>2</mn></mrow></mfrac>\
+///      </math>"
+/// );
+/// ```
+pub struct MathmlWriter<'a, W> {
+    renderer: Renderer<'a, std::vec::IntoIter<Result<Event<'a>, Infallible>>, W>,
+    /// Whether the `math` element is opened.
+    started: bool,
+    /// The number of groups opened by the events, and not yet closed.
+    depth: usize,
+}
+
+impl<'a, W: io::Write> MathmlWriter<'a, W> {
+    /// Create a writer rendering the events into the given writer, using the provided
+    /// [`RenderConfig`].
+    pub fn new(writer: W, config: RenderConfig<'a>) -> Self {
+        Self {
+            renderer: Renderer::new(Vec::new().into_iter(), writer, config),
+            started: false,
+            depth: 0,
+        }
+    }
+
+    /// Push the next event to render, and write the events preceding it which no longer depend on
+    /// the events following them.
+    ///
+    /// Unsupported events are reported as soon as they are written, so an [`Event::End`] which
+    /// does not close a group is reported immediately, and the events ending before an element is
+    /// complete are reported by [`MathmlWriter::finish`].
+    pub fn push_event(&mut self, event: Event<'a>) -> Result<(), RenderError> {
+        match event {
+            Event::Begin(_) => self.depth += 1,
+            Event::End => {
+                self.depth = self.depth.checked_sub(1).ok_or(RenderError::Unsupported(
+                    "an `End` event which does not close a group",
+                ))?;
+            }
+            _ => {}
+        }
+        let mut events: Vec<_> = self.renderer.input.by_ref().collect();
+        events.push(Ok(event));
+        let pending = pending_events(&events);
+        self.renderer.input = events.into_iter().peekable();
+        self.write_events(pending)
+    }
+
+    /// Render the remaining events, and return the underlying writer.
+    pub fn finish(mut self) -> Result<W, RenderError> {
+        self.write_events(0)?;
+        self.renderer.write_end()?;
+        Ok(self.renderer.writer)
+    }
+
+    /// Write the events pushed so far, except for the given number of last events.
+    fn write_events(&mut self, pending: usize) -> Result<(), RenderError> {
+        if !self.started {
+            self.renderer.write_start()?;
+            self.started = true;
+        }
+        while self.renderer.input.len() > pending {
+            let Some(event) = self.renderer.input.next() else {
+                break;
+            };
+            self.renderer.write_next(event)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for MathmlWriter<'_, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MathmlWriter")
+            .field("writer", &self.renderer.writer)
+            .field("config", &self.renderer.config)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The number of events at the end of the pushed events whose rendering may depend on the events
+/// that will follow them.
+///
+/// Rendering an event looks at the event following it, and consumes the runs of events forming a
+/// single element with it, such as consecutive `Text` events, the state changes at the start of a
+/// group, or the scripts of a tensor. The last event is thus kept, along with the run it ends.
+fn pending_events(events: &[Result<Event<'_>, Infallible>]) -> usize {
+    let run = events
+        .windows(2)
+        .rev()
+        .take_while(|pair| {
+            matches!(
+                pair,
+                [
+                    Ok(Event::Content(Content::Text(_))),
+                    Ok(Event::Content(Content::Text(_)))
+                ] | [
+                    Ok(Event::Begin(_) | Event::StateChange(_)),
+                    Ok(Event::StateChange(_))
+                ] | [Ok(Event::Script { .. }), Ok(Event::Script { .. })]
+            )
+        })
+        .count();
+    (run + 1).min(events.len())
+}
+
+/// The space to the left and to the right of an atom of the given class.
+///
+/// These are the spaces TeX inserts between an ordinary atom and an atom of the given class.
+fn atom_spacing(class: AtomClass) -> (&'static str, &'static str) {
+    match class {
+        AtomClass::Ordinary | AtomClass::Opening | AtomClass::Closing => ("", ""),
+        AtomClass::Operator | AtomClass::Inner => ("0.1667em", "0.1667em"),
+        AtomClass::Binary => ("0.2222em", "0.2222em"),
+        AtomClass::Relation => ("0.2778em", "0.2778em"),
+        AtomClass::Punctuation => ("", "0.1667em"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{ErrorPolicy, ParserConfig},
+        parser::Parser,
+    };
+
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    fn render(input: &str) -> String {
+        let mut output = String::new();
+        push_mathml(&mut output, Parser::new(input), RenderConfig::default()).unwrap();
+        output
+    }
+
+    #[test]
+    fn function_application() {
+        assert_eq!(
+            render(r"\sin x"),
+            "<math display=\"inline\"><mi>sin</mi><mo>\u{2061}</mo><mspace width=\"0.1667em\" \
+             /><mi>x</mi></math>"
+        );
+        assert_eq!(
+            render(r"\sin(x)"),
+            "<math display=\"inline\"><mi>sin</mi><mo>\u{2061}</mo><mo \
+             stretchy=\"false\">(</mo><mi>x</mi><mo stretchy=\"false\">)</mo></math>"
+        );
+        assert_eq!(
+            render(r"\sin^2 x"),
+            "<math display=\"inline\"><msup><mi>sin</mi><mn>2</mn></msup><mo>\u{2061}</mo><mspace \
+             width=\"0.1667em\" /><mi>x</mi></math>"
+        );
+    }
+
+    #[test]
+    fn atom_class_spacing() {
+        assert_eq!(
+            render(r"a \mathbin{!} b"),
+            "<math display=\"inline\"><mi>a</mi><mrow><mspace width=\"0.2222em\" \
+             /><mo>!</mo><mspace width=\"0.2222em\" /></mrow><mi>b</mi></math>"
+        );
+    }
+
+    #[test]
+    fn display_mode_limits() {
+        let config = ParserConfig {
+            display_mode: DisplayMode::Block,
+            ..Default::default()
+        };
+        let mut output = String::new();
+        push_mathml(
+            &mut output,
+            Parser::with_config(r"\sum_i \int_a", config),
+            RenderConfig {
+                display_mode: DisplayMode::Block,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // The limits keep their script level.
+        assert_eq!(
+            output,
+            "<math display=\"block\"><munder><mo movablelimits=\"false\">∑</mo><mi>i</mi>\
+             </munder><msub><mo>∫</mo><mi>a</mi></msub></math>"
+        );
+    }
+
+    #[test]
+    fn vcenter() {
+        assert_eq!(
+            render(r"\left( \vcenter{\frac{a}{b}} \right)"),
+            "<math display=\"inline\"><mrow><mo stretchy=\"true\" fence=\"true\">(</mo><mtable>\
+             <mtr><mtd><mrow><mfrac><mrow><mi>a</mi></mrow><mrow><mi>b</mi></mrow></mfrac></mrow>\
+             </mtd></mtr></mtable><mo stretchy=\"true\" fence=\"true\">)</mo></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn small_caps_slanted_and_double_struck_italic() {
+        assert_eq!(
+            render(r"\sc aQ"),
+            "<math display=\"inline\"><mi mathvariant=\"normal\">ᴀ</mi><mi \
+             mathvariant=\"normal\">Q</mi></math>"
+        );
+        assert_eq!(
+            render(r"\sl a"),
+            "<math display=\"inline\"><mi style=\"font-style: oblique\" \
+             mathvariant=\"normal\">a</mi></math>"
+        );
+        assert_eq!(
+            render(r"\mathbbit{dx}"),
+            "<math display=\"inline\"><mrow><mi>ⅆ</mi><mi>𝕩</mi></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn root_attributes() {
+        let render_with = |config| {
+            let mut output = String::new();
+            push_mathml(&mut output, Parser::new("x"), config).unwrap();
+            output
+        };
+        assert_eq!(
+            render_with(RenderConfig {
+                xml: true,
+                ..Default::default()
+            }),
+            "<math display=\"inline\" xmlns=\"http://www.w3.org/1998/Math/MathML\"><mi>x</mi>\
+             </math>"
+        );
+        assert_eq!(
+            render_with(RenderConfig {
+                id: Some("eq-1"),
+                class: Some("a \"b\" & c"),
+                ..Default::default()
+            }),
+            "<math display=\"inline\" id=\"eq-1\" class=\"a &quot;b&quot; &amp; c\"><mi>x</mi>\
+             </math>"
+        );
+        assert_eq!(render_with(RenderConfig::default()), render("x"));
+    }
+
+    #[test]
+    fn single_limit() {
+        let config = RenderConfig {
+            display_mode: DisplayMode::Block,
+            ..Default::default()
+        };
+        let mut output = String::new();
+        push_mathml(&mut output, Parser::new(r"\sum_{i=0} \sum^{n}"), config).unwrap();
+        assert_eq!(
+            output,
+            "<math display=\"block\"><munder><mo movablelimits=\"false\">∑</mo><mrow><mi>i</\
+             mi><mo>=</mo><mn>0</mn></mrow></munder><mover><mo \
+             movablelimits=\"false\">∑</mo><mrow><mi>n</mi></mrow></mover></math>"
+        );
+    }
+
+    #[test]
+    fn environments() {
+        assert_eq!(
+            render(r"\begin{pmatrix} a & b \\ c & d \\ \end{pmatrix}"),
+            "<math display=\"inline\"><mrow><mo stretchy=\"true\" fence=\"true\">(</mo><mtable>\
+             <mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr><mtr><mtd><mi>c</mi></mtd><mtd>\
+             <mi>d</mi></mtd></mtr></mtable><mo stretchy=\"true\" fence=\"true\">)</mo></mrow>\
+             </math>"
+        );
+        assert_eq!(
+            render(r"\begin{cases} 1 & x \\ 0 & y \end{cases}"),
+            "<math display=\"inline\"><mrow><mo>{</mo><mtable \
+             columnalign=\"left\"><mtr><mtd><mn>1</mn></mtd><mtd><mi>x</mi></mtd></mtr><mtr><mtd><mn>0</\
+             mn></mtd><mtd><mi>y</mi></mtd></mtr></mtable></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn negation() {
+        assert_eq!(
+            render(r"\not< \not\equiv \not a"),
+            "<math display=\"inline\"><mo>≮</mo><mo>≢</mo><mi>a̸</mi></math>"
+        );
+    }
+
+    #[test]
+    fn primes() {
+        assert_eq!(
+            render(r"f''' + g^{''} + h'''''"),
+            "<math display=\"inline\"><msup><mi>f</mi><mo>‴</mo></msup><mo>+</mo><msup><mi>g</mi>\
+             <mrow><mo>″</mo></mrow></msup><mo>+</mo><msup><mi>h</mi><mrow><mo>⁗</mo><mo>′</mo>\
+             </mrow></msup></math>"
+        );
+    }
+
+    #[test]
+    fn array_columns() {
+        assert_eq!(
+            render(r"\begin{array}{|l|cr} a & b & c \\ d \end{array}"),
+            "<math display=\"inline\"><mtable style=\"border-left: 0.06em solid\" \
+             columnalign=\"left center right\" columnlines=\"solid none\"><mtr><mtd><mi>a</mi>\
+             </mtd><mtd><mi>b</mi></mtd><mtd><mi>c</mi></mtd></mtr><mtr><mtd><mi>d</mi></mtd>\
+             <mtd></mtd><mtd></mtd></mtr></mtable></math>"
+        );
+        // Rows are padded to the longest row preceding them.
+        assert_eq!(
+            render(r"\begin{matrix} a & b \\ c \end{matrix}"),
+            "<math display=\"inline\"><mtable><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd>\
+             </mtr><mtr><mtd><mi>c</mi></mtd><mtd></mtd></mtr></mtable></math>"
+        );
+        // The number of columns is not limited.
+        let output = render(&format!(
+            r"\begin{{matrix}} {} \\ c \end{{matrix}}",
+            "a&".repeat(300)
+        ));
+        assert_eq!(output.matches("<mtd>").count(), 2 * 301);
+    }
+
+    #[test]
+    fn unknown_command() {
+        let config = ParserConfig {
+            on_unknown_command: ErrorPolicy::EmitPlaceholder,
+            ..Default::default()
+        };
+        let mut output = String::new();
+        push_mathml(
+            &mut output,
+            Parser::with_config(r"\foo + 1 \<", config),
+            RenderConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "<math display=\"inline\"><merror style=\"border-color: #b22222\"><mtext>\\foo</mtext>\
+             </merror><mo>+</mo><mn>1</mn><merror style=\"border-color: #b22222\"><mtext>\\&lt;\
+             </mtext></merror></math>"
+        );
+    }
+
+    #[test]
+    fn html_annotations() {
+        let config = ParserConfig {
+            trust: true,
+            ..Default::default()
+        };
+        let mut output = String::new();
+        push_mathml(
+            &mut output,
+            Parser::with_config(
+                r"\htmlId{eq1}{x} \htmlClass{a&b}y^2 \color{red}\htmlStyle{font-weight: bold}z",
+                config,
+            ),
+            RenderConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "<math display=\"inline\"><mrow id=\"eq1\"><mrow><mi>x</mi></mrow></mrow><msup><mrow \
+             class=\"a&amp;b\"><mi>y</mi></mrow><mn>2</mn></msup><mrow style=\"color: red; \
+             font-weight: bold\"><mi style=\"color: red\">z</mi></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            render(r#"\href{https://x.org/?a=1&b="2"}{y+1} \href{#eq}z"#),
+            "<math display=\"inline\"><mrow href=\"https://x.org/?a=1&amp;b=&quot;2&quot;\"><mrow>\
+             <mi>y</mi><mo>+</mo><mn>1</mn></mrow></mrow><mrow href=\"#eq\"><mi>z</mi></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn fmt_writers() {
+        /// A writer keeping each string written to it.
+        struct Chunks(Vec<String>);
+
+        impl fmt::Write for Chunks {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0.push(s.to_owned());
+                Ok(())
+            }
+        }
+
+        let input = r"\frac{a}{\sqrt{b}} + \text{c}";
+        let mut chunks = Chunks(Vec::new());
+        push_mathml(&mut chunks, Parser::new(input), RenderConfig::default()).unwrap();
+        // The output is written as the events are rendered.
+        assert!(chunks.0.len() > 1);
+        assert_eq!(chunks.0.concat(), render(input));
+
+        struct Failing;
+
+        impl fmt::Write for Failing {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        let result = push_mathml(Failing, Parser::new(input), RenderConfig::default());
+        assert!(matches!(result, Err(RenderError::Io(_))));
+    }
+
+    #[test]
+    fn unsupported_events() {
+        let x = Event::Content(Content::Identifier(Identifier::Char('x')));
+        for events in [
+            // A fraction missing its denominator.
+            vec![Event::Visual(Visual::Fraction(None)), x],
+            vec![Event::Begin(Grouping::Normal), x],
+            vec![x, Event::End],
+            vec![Event::Visual(Visual::SquareRoot), Event::End],
+            vec![x, Event::Alignment, x],
+            // A negation with nothing to negate.
+            vec![x, Event::Visual(Visual::Negation)],
+            vec![Event::Visual(Visual::Negation), Event::End],
+        ] {
+            let mut output = String::new();
+            let result = push_mathml(
+                &mut output,
+                events.into_iter().map(Ok::<_, io::Error>),
+                RenderConfig::default(),
+            );
+            assert!(
+                matches!(result, Err(RenderError::Unsupported(_))),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn pushed_events() {
+        for input in [
+            r"\left( \frac{a}{b} \right)",
+            r"\sqrt{x} \sqrt[3]{y} a_1^2 \sum_{i=0}^n",
+            r"\overset{!}{=} \underset{a}{b} \overbrace{x+y}^2",
+            r"\begin{matrix} a & b \\ c & d \end{matrix}",
+            // Events rendered along with the events following them.
+            r"\text{a  b \'e} \sin x \not= \not a {\color{red}\bf x} T^a{}_b{}^c",
+        ] {
+            let mut writer = MathmlWriter::new(Vec::new(), RenderConfig::default());
+            for event in Parser::new(input) {
+                writer.push_event(event.unwrap()).unwrap();
+            }
+            let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+            assert_eq!(output, render(input), "{input}");
+        }
+
+        // The base of `\overset` and `\underset` is the first child of `mover` and `munder`.
+        let mut writer = MathmlWriter::new(Vec::new(), RenderConfig::default());
+        for event in Parser::new(r"\overset{!}{=}") {
+            writer.push_event(event.unwrap()).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(writer.finish().unwrap()).unwrap(),
+            "<math display=\"inline\"><mover><mrow><mo>=</mo></mrow><mrow><mo>!</mo></mrow>\
+             </mover></math>"
+        );
+    }
+
+    #[test]
+    fn pushed_events_written_early() {
+        struct Shared(Rc<RefCell<Vec<u8>>>);
+
+        impl io::Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let mut writer = MathmlWriter::new(Shared(output.clone()), RenderConfig::default());
+        for event in Parser::new(r"\frac{1}{2} + x") {
+            writer.push_event(event.unwrap()).unwrap();
+        }
+        // Only the last event is kept until the writer is finished.
+        assert_eq!(
+            String::from_utf8(output.borrow().clone()).unwrap(),
+            "<math display=\"inline\"><mfrac><mrow><mn>1</mn></mrow><mrow><mn>2</mn></mrow></mfrac>\
+             <mo>+</mo>"
+        );
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(output.borrow().clone()).unwrap(),
+            render(r"\frac{1}{2} + x")
+        );
+    }
+
+    #[test]
+    fn pushed_unsupported_events() {
+        let x = Event::Content(Content::Identifier(Identifier::Char('x')));
+        let mut writer = MathmlWriter::new(Vec::new(), RenderConfig::default());
+        writer.push_event(x).unwrap();
+        assert!(matches!(
+            writer.push_event(Event::End),
+            Err(RenderError::Unsupported(_))
+        ));
+
+        let mut writer = MathmlWriter::new(Vec::new(), RenderConfig::default());
+        writer
+            .push_event(Event::Visual(Visual::Fraction(None)))
+            .unwrap();
+        writer.push_event(x).unwrap();
+        assert!(matches!(writer.finish(), Err(RenderError::Unsupported(_))));
+    }
+
+    #[test]
+    fn adjusted_limits() {
+        // The limits are placed under the operators even in inline mode, so that they are aligned.
+        assert_eq!(
+            render(r"\adjustlimits\lim_{n}\sup_k x"),
+            "<math display=\"inline\"><mrow><munder><mi>lim</mi><mrow><mi>n</mi></mrow></munder>\
+             <mo>\u{2061}</mo><mspace width=\"0.1667em\" /><munder><mi>sup</mi><mi>k</mi></munder>\
+             <mo>\u{2061}</mo></mrow><mi>x</mi></math>"
+        );
+        assert_eq!(
+            render(r"\adjustlimits\operatorname{lim}\limits_{n}\max_m a"),
+            "<math display=\"inline\"><mrow><munder><mi>lim</mi><mrow><mi>n</mi></mrow></munder>\
+             <mo>\u{2061}</mo><mspace width=\"0.1667em\" /><munder><mi>max</mi><mi>m</mi></munder>\
+             <mo>\u{2061}</mo></mrow><mi>a</mi></math>"
+        );
+    }
+
+    #[test]
+    fn laps() {
+        // MathML Core lengths cannot refer to the width of the content, but percentages do.
+        assert_eq!(
+            render(r"\llap{a}\rlap{b}\clap{c}"),
+            "<math display=\"inline\"><mpadded width=\"0\" lspace=\"-100%\"><mtext>a</mtext>\
+             </mpadded><mpadded width=\"0\"><mtext>b</mtext></mpadded><mpadded width=\"0\" \
+             lspace=\"-50%\"><mtext>c</mtext></mpadded></math>"
+        );
+    }
+
+    #[test]
+    fn split_fraction() {
+        assert_eq!(
+            render(r"\frac{\splitfrac{x+y}{+z}}{w}"),
+            "<math display=\"inline\"><mfrac><mrow><mrow displaystyle=\"false\" scriptlevel=\"0\">\
+             <mfrac linethickness=\"0\" numalign=\"left\" denomalign=\"right\"><mrow><mi>x</mi>\
+             <mo>+</mo><mi>y</mi></mrow><mrow><mo>+</mo><mi>z</mi></mrow></mfrac></mrow></mrow>\
+             <mrow><mi>w</mi></mrow></mfrac></math>"
+        );
+    }
+
+    #[test]
+    fn continued_fraction() {
+        assert_eq!(
+            render(r"\cfrac[l]{1}{1+\cfrac[r]{1}{x}}"),
+            "<math display=\"inline\"><mrow displaystyle=\"true\" scriptlevel=\"0\">\
+             <mfrac numalign=\"left\"><mrow><mn>1</mn></mrow><mrow><mn>1</mn><mo>+</mo>\
+             <mrow displaystyle=\"true\" scriptlevel=\"0\"><mfrac numalign=\"right\"><mrow>\
+             <mn>1</mn></mrow><mrow><mi>x</mi></mrow></mfrac></mrow></mrow></mfrac></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn delimiters() {
+        assert_eq!(
+            render(r"\left(\frac{a}{b}\right)"),
+            "<math display=\"inline\"><mrow><mo stretchy=\"true\" fence=\"true\">(</mo><mfrac>\
+             <mrow><mi>a</mi></mrow><mrow><mi>b</mi></mrow></mfrac><mo stretchy=\"true\" \
+             fence=\"true\">)</mo></mrow></math>"
+        );
+        assert_eq!(
+            render(r"\bigl( x \Bigr]"),
+            "<math display=\"inline\"><mrow><mo minsize=\"1.2em\" maxsize=\"1.2em\">(</mo></mrow>\
+             <mi>x</mi><mrow><mo minsize=\"1.8em\" maxsize=\"1.8em\">]</mo></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn middle_delimiter() {
+        assert_eq!(
+            render(r"\left( \frac{a}{b} \middle| c \right)"),
+            "<math display=\"inline\"><mrow><mo stretchy=\"true\" fence=\"true\">(</mo><mfrac>\
+             <mrow><mi>a</mi></mrow><mrow><mi>b</mi></mrow></mfrac><mo stretchy=\"true\">|</mo><mi>\
+             c</mi><mo stretchy=\"true\" fence=\"true\">)</mo></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn prescripts() {
+        assert_eq!(
+            render(r"{}^{14}_{6}C"),
+            "<math display=\"inline\"><mmultiscripts><mi>C</mi><mprescripts \
+             /><mrow><mn>6</mn></mrow><mrow><mn>14</mn></mrow></mmultiscripts></math>"
+        );
+        assert_eq!(
+            render(r"{}_a x {}^b y"),
+            "<math display=\"inline\"><mmultiscripts><mi>x</mi><mprescripts /><mi>a</mi><none \
+             /></mmultiscripts><mmultiscripts><mi>y</mi><mprescripts /><none \
+             /><mi>b</mi></mmultiscripts></math>"
+        );
+        // The scripts following `\prescript` apply to the whole construct.
+        assert_eq!(
+            render(r"\prescript{a}{}{X}^2"),
+            "<math display=\"inline\"><msup><mmultiscripts><mrow><mi>X</mi></mrow><mprescripts \
+             /><none /><mrow><mi>a</mi></mrow></mmultiscripts><mn>2</mn></msup></math>"
+        );
+    }
+
+    #[test]
+    fn tensor() {
+        assert_eq!(
+            render(r"T^a{}_b{}^c"),
+            "<math display=\"inline\"><mmultiscripts><mi>T</mi><none /><mi>a</mi><mi>b</mi><none \
+             /><none /><mi>c</mi></mmultiscripts></math>"
+        );
+        assert_eq!(
+            render(r"R_a^b{}_{cd}"),
+            "<math display=\"inline\"><mmultiscripts><mi>R</mi><mi>a</mi><mi>b</mi><mrow><mi>c</mi>\
+             <mi>d</mi></mrow><none /></mmultiscripts></math>"
+        );
+    }
+
+    #[test]
+    fn scripts_and_radicals() {
+        assert_eq!(
+            render(r"\sqrt{x} \sqrt[3]{y} a_1^2 \overset{!}{=} \underset{a}{b}"),
+            "<math display=\"inline\"><msqrt><mrow><mi>x</mi></mrow></msqrt><mroot><mrow><mi>y</mi>\
+             </mrow><mn>3</mn></mroot><msubsup><mi>a</mi><mn>1</mn><mn>2</mn></msubsup><mover><mrow>\
+             <mo>=</mo></mrow><mrow><mo>!</mo></mrow></mover><munder><mrow><mi>b</mi></mrow><mrow>\
+             <mi>a</mi></mrow></munder></math>"
+        );
+    }
+
+    #[test]
+    fn dot_accents() {
+        assert_eq!(
+            render(r"\dddot{x} \ddddot q^2"),
+            "<math display=\"inline\"><mover accent=\"true\"><mrow><mi>x</mi></mrow><mo>\u{20DB}\
+             </mo></mover><msup><mover accent=\"true\"><mi>q</mi><mo>\u{20DC}</mo></mover><mn>2\
+             </mn></msup></math>"
+        );
+    }
+
+    #[test]
+    fn braces() {
+        assert_eq!(
+            render(r"\overbrace{x}^{n} \underbrace{y}_k"),
+            "<math display=\"inline\"><mover><mover><mrow><mi>x</mi></mrow><mo \
+             stretchy=\"true\">⏞</mo></mover><mrow><mi>n</mi></mrow></mover><munder><munder><mrow>\
+             <mi>y</mi></mrow><mo stretchy=\"true\">⏟</mo></munder><mi>k</mi></munder></math>"
+        );
+    }
+
+    #[test]
+    fn strikes() {
+        assert_eq!(
+            render(r"\cancel{x} \xcancel{y} \sout{z}"),
+            "<math display=\"inline\"><menclose notation=\"updiagonalstrike\"><mrow><mi>x</mi>\
+             </mrow></menclose><menclose notation=\"updiagonalstrike downdiagonalstrike\"><mrow>\
+             <mi>y</mi></mrow></menclose><menclose notation=\"horizontalstrike\"><mrow><mi>z</mi>\
+             </mrow></menclose></math>"
+        );
+    }
+
+    #[test]
+    fn text_fonts() {
+        assert_eq!(
+            render(r"\textbf{\textit{a}\textsc{b}\textsf{$x$}}"),
+            "<math display=\"inline\"><mrow><mrow><mtext style=\"font-weight: bold; font-style: \
+             italic\">a</mtext></mrow><mrow><mtext style=\"font-weight: bold; font-variant: \
+             small-caps\">b</mtext></mrow><mrow><mrow><mi>x</mi></mrow></mrow></mrow></math>"
+        );
+        // Emphasized text is upright inside italic text.
+        assert_eq!(
+            render(r"\text{a \emph{b \emph{c}}}"),
+            "<math display=\"inline\"><mrow><mtext>a </mtext><mrow><mtext style=\"font-style: \
+             italic\">b </mtext><mrow><mtext>c</mtext></mrow></mrow></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn corners() {
+        // Corners are fences, which are not surrounded by any space.
+        assert_eq!(
+            render(r"\left\ulcorner x \right\urcorner \llcorner y \lrcorner"),
+            "<math display=\"inline\"><mrow><mo stretchy=\"true\" fence=\"true\">⌜</mo><mi>x</mi>\
+             <mo stretchy=\"true\" fence=\"true\">⌝</mo></mrow><mo stretchy=\"false\">⌞</mo><mi>y\
+             </mi><mo stretchy=\"false\">⌟</mo></math>"
+        );
+    }
+
+    #[test]
+    fn sized_delimiter_classes() {
+        assert_eq!(
+            render(r"a \bigm| b"),
+            "<math display=\"inline\"><mi>a</mi><mrow><mspace width=\"0.2778em\" /><mo \
+             minsize=\"1.2em\" maxsize=\"1.2em\">|</mo><mspace width=\"0.2778em\" /></mrow><mi>\
+             b</mi></math>"
+        );
+        assert_eq!(
+            render(r"a \bigl| b"),
+            "<math display=\"inline\"><mi>a</mi><mrow><mo minsize=\"1.2em\" \
+             maxsize=\"1.2em\">|</mo></mrow><mi>b</mi></math>"
+        );
+    }
+
+    #[test]
+    fn text_mode() {
+        assert_eq!(
+            render("\\text{if   a\n  % comment\n  then~b}"),
+            "<math display=\"inline\"><mrow><mtext>if a then\u{a0}b</mtext></mrow></math>"
+        );
+    }
 }
@@ -9,15 +9,26 @@ mod lex;
 mod macros;
 mod primitives;
 mod state;
+mod storage;
+pub(crate) mod symbols;
 mod tables;
 
-use std::fmt::Display;
+use std::{fmt::Display, ops::Range};
 
 use thiserror::Error;
 
-use crate::event::{Event, Grouping, ScriptPosition, ScriptType};
+use crate::{
+    config::{DisplayMode, ParserConfig},
+    event::{
+        AtomClass, Content, Event, Grouping, Operator, ScriptPosition, ScriptType, StateChange,
+        Style, Visual,
+    },
+    latex::{push_latex, PrintError},
+};
 
-use self::state::ParserState;
+use self::{macros::MacroContext, state::ParserState};
+pub use self::storage::Storage;
+pub(crate) use self::tables::is_delimiter;
 
 /// The parser completes the task of transforming the input `LaTeX` into a symbolic representation,
 /// namely a stream of [`Event`]s.
@@ -52,26 +63,187 @@ pub struct Parser<'a> {
 
     /// The current state of the parser
     state: ParserState,
+
+    /// The configuration of the parser.
+    config: ParserConfig,
+
+    /// The next event, if it was parsed ahead of time by [`Parser::peek`].
+    peeked: Option<Result<Event<'a>, ParserError<'a>>>,
+
+    /// The byte offset, in the initial input, of the token currently being parsed.
+    ///
+    /// This is used to report the span of an error.
+    token_start: usize,
+
+    /// The number of groups in the instruction stack, which are being parsed.
+    depth: usize,
+
+    /// The control sequences assigned by `\let` and `\futurelet`, and the macros defined by
+    /// `\def` and `\edef`.
+    macro_context: MacroContext<'a>,
+
+    /// The storage of the expansions of macros, if the parser was created with one.
+    storage: Option<&'a Storage>,
 }
 
-// TODO: When using macros, one should truly just prepend the extended macro to the start of the
-// current string.
+// TODO: The expansion of a macro is parsed on its own, like the branches of a conditional. One
+// should truly prepend the expansion to the start of the current string, so that it can take
+// arguments from the input following it.
 // We should thus never call `current_string` repeatedly, the string
 // outputed by current string is always fully formed.
 impl<'a> Parser<'a> {
+    /// Create a new parser for the given input, using the default [`ParserConfig`].
+    ///
+    /// The macros whose expansion is not a contiguous part of the input cannot be expanded
+    /// without a [`Storage`], see [`Parser::with_storage`].
     pub fn new(input: &'a str) -> Self {
-        let mut instruction_stack = Vec::with_capacity(64);
-        instruction_stack.push(Instruction::SubGroup {
-            content: input,
-            allows_alignment: false,
-        });
+        Self::with_config(input, ParserConfig::default())
+    }
+
+    /// Create a new parser for the given input, using the provided [`ParserConfig`].
+    pub fn with_config(input: &'a str, config: ParserConfig) -> Self {
+        let instruction_stack = Vec::with_capacity(64);
         let buffer = Vec::with_capacity(16);
-        Self {
+        let mut parser = Self {
             input,
             instruction_stack,
             buffer,
             state: ParserState::default(),
+            config,
+            peeked: None,
+            token_start: 0,
+            depth: 0,
+            macro_context: MacroContext::new(),
+            storage: None,
+        };
+        // The input is parsed in `\displaystyle`, but the display mode is left to the renderer,
+        // e.g., `<math display="block">`, so that the scripts keep their own style.
+        if config.display_mode == DisplayMode::Block {
+            parser.state.argument_style = Some((0, Style::Display));
+        }
+        parser.group_content(input, false);
+        parser.state.argument_style = None;
+        parser
+            .instruction_stack
+            .extend(parser.buffer.drain(..).rev());
+        parser.depth = parser
+            .instruction_stack
+            .iter()
+            .filter(|instruction| matches!(instruction, Instruction::SubGroup { .. }))
+            .count();
+        parser
+    }
+
+    /// Create a new parser for the given input, using the provided [`ParserConfig`], which
+    /// expands the macros defined in the input into the given [`Storage`].
+    ///
+    /// Without a storage, only the macros whose expansion is a contiguous part of the input can
+    /// be expanded.
+    pub fn with_storage(input: &'a str, config: ParserConfig, storage: &'a Storage) -> Self {
+        Self {
+            storage: Some(storage),
+            ..Self::with_config(input, config)
+        }
+    }
+
+    /// Parse the input, expanding the macros it defines, and print the events as `LaTeX`, using
+    /// [`push_latex`].
+    ///
+    /// ```rust
+    /// use pulldown_latex::Parser;
+    ///
+    /// let expanded = Parser::expand_to_string(r"\def\R{\mathbb{R}} \edef\S{\R^2} x \in \S");
+    /// assert_eq!(expanded.unwrap(), r"x\in\mathbb{R}^2");
+    /// ```
+    ///
+    /// [`push_latex`]: crate::latex::push_latex
+    pub fn expand_to_string(input: &'a str) -> Result<String, PrintError<ParserError<'a>>> {
+        let storage = Storage::new();
+        let mut output = String::new();
+        push_latex(
+            &mut output,
+            Parser::with_storage(input, ParserConfig::default(), &storage),
+        )
+        .map(|()| output)
+        .map_err(|error| match error {
+            // The context of an error is always part of the input, and never of the storage.
+            PrintError::Event(error) => PrintError::Event(ParserError {
+                context: error.context.and_then(|(context, column)| {
+                    let start = Self::input_offset(input, context)?;
+                    Some((&input[start..start + context.len()], column))
+                }),
+                span: error.span,
+                position: error.position,
+                error: error.error,
+            }),
+            PrintError::Unsupported(element) => PrintError::Unsupported(element),
+        })
+    }
+
+    /// Join the pieces of the expansion of a macro, or of a span of text, as a single string.
+    ///
+    /// The pieces are used as is when they follow each other in the input, and are otherwise
+    /// stored in the [`Storage`] of the parser.
+    fn join_pieces(&self, pieces: &[&'a str]) -> InnerResult<&'a str> {
+        match pieces {
+            [] => Ok(""),
+            [piece] => Ok(piece),
+            [first, ..] => {
+                let start = Self::input_offset(self.input, first);
+                let mut end = start;
+                for piece in pieces {
+                    end = end
+                        .filter(|end| Self::input_offset(self.input, piece) == Some(*end))
+                        .map(|end| end + piece.len());
+                }
+                if let (Some(start), Some(end)) = (start, end) {
+                    return Ok(&self.input[start..end]);
+                }
+                let storage = self.storage.ok_or(ErrorKind::MacroStorage)?;
+                Ok(storage.store(pieces.concat()))
+            }
+        }
+    }
+
+    /// Return the next event without consuming it.
+    ///
+    /// The event is parsed once, and returned by the following call to `next`.
+    pub fn peek(&mut self) -> Option<Result<&Event<'a>, &ParserError<'a>>> {
+        if self.peeked.is_none() {
+            self.peeked = self.next();
+        }
+        self.peeked.as_ref().map(Result::as_ref)
+    }
+
+    /// Parse the whole input, collecting every error instead of stopping at the first one.
+    ///
+    /// When an error occurs, the erroneous token is skipped and parsing resumes after it. Parsing
+    /// stops at the first fatal error (see [`ParserError::is_fatal`]), since the events that
+    /// follow it would not reflect the structure of the input.
+    ///
+    /// ```rust
+    /// use pulldown_latex::Parser;
+    ///
+    /// let (events, errors) = Parser::new(r"\foo + \baz x").collect_errors();
+    /// assert_eq!(events.len(), 2);
+    /// assert_eq!(errors.len(), 2);
+    /// ```
+    pub fn collect_errors(self) -> (Vec<Event<'a>>, Vec<ParserError<'a>>) {
+        let mut events = Vec::new();
+        let mut errors = Vec::new();
+        for result in self {
+            match result {
+                Ok(event) => events.push(event),
+                Err(error) => {
+                    let fatal = error.is_fatal();
+                    errors.push(error);
+                    if fatal {
+                        break;
+                    }
+                }
+            }
         }
+        (events, errors)
     }
 
     /// Get the current string we are parsing.
@@ -101,6 +273,61 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Whether the group currently being parsed is the content of a `\left ... \right` pair, in
+    /// which case `\middle` can be used.
+    fn allows_middle(&self) -> bool {
+        match self
+            .instruction_stack
+            .last()
+            .expect("there is something in the stack")
+        {
+            Instruction::SubGroup { allows_middle, .. } => *allows_middle,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The style of the group currently being parsed.
+    fn current_style(&self) -> Style {
+        match self.instruction_stack.last() {
+            Some(Instruction::SubGroup { style, .. }) => *style,
+            _ => Style::Text,
+        }
+    }
+
+    /// The style in effect after the content currently in the buffer.
+    fn buffered_style(&self) -> Style {
+        let (start, style) = self
+            .state
+            .argument_style
+            .unwrap_or_else(|| (0, self.current_style()));
+        let mut depth = 0usize;
+        for instruction in self.buffer[start..].iter().rev() {
+            match instruction {
+                Instruction::Event(Event::End) => depth += 1,
+                Instruction::Event(Event::Begin(_)) => depth = depth.saturating_sub(1),
+                Instruction::Event(Event::StateChange(StateChange::Style(style))) if depth == 0 => {
+                    return *style
+                }
+                _ => (),
+            }
+        }
+        style
+    }
+
+    /// Set the style of the groups pushed to the buffer from `start`, e.g., when they are the
+    /// arguments of a script.
+    fn set_buffered_style(&mut self, start: usize, new_style: Style) {
+        let mut depth = 0usize;
+        for instruction in &mut self.buffer[start..] {
+            match instruction {
+                Instruction::Event(Event::Begin(_)) => depth += 1,
+                Instruction::Event(Event::End) => depth -= 1,
+                Instruction::SubGroup { style, .. } if depth <= 1 => *style = new_style,
+                _ => (),
+            }
+        }
+    }
+
     /// Handles the superscript and/or subscript following what was parsed previously.
     ///
     /// Follows the design decisions described in [`design/suffixes.md`].
@@ -109,7 +336,9 @@ impl<'a> Parser<'a> {
             return Ok(None);
         }
 
-        let mut script_position = if self.state.above_below_suffix_default {
+        let mut script_position = if self.state.limits {
+            ScriptPosition::AboveBelow
+        } else if self.state.above_below_suffix_default {
             ScriptPosition::Movable
         } else {
             ScriptPosition::Right
@@ -124,39 +353,257 @@ impl<'a> Parser<'a> {
                 }
             }
         }
+        let stack_len = self.instruction_stack.len();
         let str = self.current_string();
         *str = str.trim_start();
 
         let Some(next_char) = str.chars().next() else {
             return Ok(None);
         };
-        let subscript_first = match next_char {
-            '^' => false,
-            '_' => true,
+        let ty = match next_char {
+            // A character written in `^^` notation is not a superscript.
+            '^' if lex::caret_notation(str).is_some() => return Ok(None),
+            '\'' => {
+                let primes = lex::primes(str);
+                self.prime_suffixes(primes)?
+            }
+            '^' | '_' => {
+                *str = &str[1..];
+                self.rhs_suffixes(next_char == '_')?
+            }
             _ => return Ok(None),
         };
-        *str = &str[1..];
+        let suffix = Event::Script {
+            ty,
+            position: script_position,
+        };
+        if script_position == ScriptPosition::Right {
+            return self.tensor_suffixes(suffix, stack_len).map(Some);
+        }
+        Ok(Some(suffix))
+    }
+
+    /// Handle the scripts following empty groups after the scripts of an element as the indices
+    /// of a tensor, as in `T^a{}_b{}^c`, where each index is staggered after the previous ones.
+    ///
+    /// The scripts of each index take the previous scripted element as their base, and the
+    /// returned suffix is the one of the last index.
+    fn tensor_suffixes(
+        &mut self,
+        mut suffix: Event<'a>,
+        stack_len: usize,
+    ) -> InnerResult<Event<'a>> {
+        let mut previous_scripts = Vec::new();
+        loop {
+            // The scripts are moved out of the way to parse what follows them.
+            previous_scripts.push(self.instruction_stack.split_off(stack_len));
+            let str = self.current_string();
+            let Some(rest) = str
+                .trim_start()
+                .strip_prefix('{')
+                .and_then(|rest| rest.trim_start().strip_prefix('}'))
+                .map(|rest| rest.trim_start())
+            else {
+                break;
+            };
+            let subscript_first = match rest.chars().next() {
+                Some('^') => false,
+                Some('_') => true,
+                _ => break,
+            };
+            *str = &rest[1..];
+
+            // The previous suffix precedes the base, which becomes the base of the new suffix.
+            self.buffer
+                .insert(self.state.suffix_base, Instruction::Event(suffix));
+            let ty = self.rhs_suffixes(subscript_first)?;
+            suffix = Event::Script {
+                ty,
+                position: ScriptPosition::Tensor,
+            };
+        }
+        // The scripts of the previous indices come first.
+        for scripts in previous_scripts.into_iter().rev() {
+            self.instruction_stack.extend(scripts);
+        }
+        Ok(suffix)
+    }
+
+    /// Handle the scripts following an empty group as the prescripts of the element following
+    /// them, as in `{}^{14}_6\mathrm{C}`.
+    ///
+    /// Returns whether the scripts were handled, which is not the case when no element follows
+    /// them, in which case the scripts are attached to the empty group.
+    fn prescripts(&mut self) -> InnerResult<bool> {
+        let str = self.current_string();
+        let mut rest = str.trim_start();
+        let subscript_first = match rest.chars().next() {
+            Some('^') => false,
+            Some('_') => true,
+            _ => return Ok(false),
+        };
+        let has_base = lex::scripts(&mut rest).is_ok()
+            && match lex::peek_token(rest) {
+                Ok(Token::Character(c)) => !matches!(c.into(), '^' | '_' | '&' | '}' | '\''),
+                Ok(Token::ControlSequence(cs)) => !matches!(
+                    cs,
+                    "right" | "middle" | "end" | "endgroup" | "\\" | "cr" | "limits" | "nolimits"
+                ),
+                Err(_) => false,
+            };
+        if !has_base {
+            return Ok(false);
+        }
+        *str = &str.trim_start()[1..];
 
+        // The scripts are moved after the base.
+        let stack_len = self.instruction_stack.len();
         let ty = self.rhs_suffixes(subscript_first)?;
-        Ok(Some(Event::Script {
+        let scripts: Vec<_> = self.instruction_stack.drain(stack_len..).rev().collect();
+        self.buffer.push(Instruction::Event(Event::Script {
             ty,
-            position: script_position,
-        }))
+            position: ScriptPosition::Prescript,
+        }));
+        let base = lex::argument(self.current_string())?;
+        self.handle_argument(base)?;
+        self.buffer.extend(scripts);
+        Ok(true)
+    }
+
+    /// Parse the argument of a subscript or superscript.
+    ///
+    /// An empty group is a valid argument, but the argument cannot be missing, nor be another
+    /// script character as in `x_^2`.
+    fn script_argument(&mut self, subscript: bool) -> InnerResult<Argument<'a>> {
+        let missing = if subscript {
+            ErrorKind::EmptySubscript
+        } else {
+            ErrorKind::EmptySuperscript
+        };
+        match lex::argument(self.current_string()) {
+            Ok(Argument::Token(Token::Character(c))) if matches!(c.into(), '^' | '_') => {
+                Err(missing)
+            }
+            Err(ErrorKind::EndOfInput) => Err(missing),
+            argument => argument,
+        }
+    }
+
+    /// Handle the scripts of an element followed by primes, as in `f''` or `f'^2_n`.
+    ///
+    /// The primes are a shorthand for a superscript, which also contains the superscript
+    /// following them, if any (TeXbook p. 130).
+    fn prime_suffixes(&mut self, primes: usize) -> InnerResult<ScriptType> {
+        let style = script_style(self.buffered_style());
+        let start = self.buffer.len();
+        let previous_style = self.state.argument_style.replace((start, style));
+        let mut subscript = None;
+        let mut superscript = None;
+        loop {
+            let str = self.current_string();
+            *str = str.trim_start();
+            let is_subscript = match str.chars().next() {
+                Some('^') if lex::caret_notation(str).is_none() => false,
+                Some('_') => true,
+                _ => break,
+            };
+            let script = if is_subscript {
+                &mut subscript
+            } else {
+                &mut superscript
+            };
+            if script.is_some() {
+                return Err(if is_subscript {
+                    ErrorKind::DoubleSubscript
+                } else {
+                    ErrorKind::DoubleSuperscript
+                });
+            }
+            *str = &str[1..];
+            let script_start = self.buffer.len();
+            let arg = self.script_argument(is_subscript)?;
+            self.handle_argument(arg)?;
+            self.set_buffered_style(script_start, style);
+            *script = Some(script_start - start..self.buffer.len() - start);
+        }
+        self.state.argument_style = previous_style;
+
+        let scripts = self.buffer.split_off(start);
+        if let Some(range) = &subscript {
+            self.buffer.extend_from_slice(&scripts[range.clone()]);
+        }
+        if let Some(range) = superscript {
+            self.buffer.push(Instruction::Event(Event::Begin(Grouping::Normal)));
+            self.push_primes(primes);
+            self.buffer.extend_from_slice(&scripts[range]);
+            self.buffer.push(Instruction::Event(Event::End));
+        } else {
+            self.push_primes(primes);
+        }
+        let suffixes = self.buffer.drain(start..);
+        self.instruction_stack.extend(suffixes.rev());
+        Ok(if subscript.is_some() {
+            ScriptType::SubSuperscript
+        } else {
+            ScriptType::Superscript
+        })
     }
 
     fn rhs_suffixes(&mut self, subscript_first: bool) -> InnerResult<ScriptType> {
+        let style = script_style(self.buffered_style());
         let first_suffix_start = self.buffer.len();
-        let arg = lex::argument(self.current_string())?;
+        let previous_style = self
+            .state
+            .argument_style
+            .replace((first_suffix_start, style));
+        let arg = self.script_argument(subscript_first)?;
         self.handle_argument(arg)?;
+        self.set_buffered_style(first_suffix_start, style);
         let second_suffix_start = self.buffer.len();
         let str = self.current_string();
+        *str = str.trim_start();
         let next_char = str.chars().next();
-        if (next_char == Some('_') && !subscript_first)
+        if next_char == Some('\'') && subscript_first {
+            // Primes following a subscript start the superscript, as in `f_1'` or `f_1'^2`.
+            let primes = lex::primes(str);
+            let str = self.current_string();
+            *str = str.trim_start();
+            if str.starts_with('^') && lex::caret_notation(str).is_none() {
+                *str = &str[1..];
+                self.buffer.push(Instruction::Event(Event::Begin(Grouping::Normal)));
+                self.push_primes(primes);
+                let superscript_start = self.buffer.len();
+                let arg = self.script_argument(false)?;
+                self.handle_argument(arg)?;
+                self.set_buffered_style(superscript_start, style);
+                self.buffer.push(Instruction::Event(Event::End));
+            } else {
+                self.push_primes(primes);
+            }
+
+            let str = self.current_string();
+            *str = str.trim_start();
+            match str.chars().next() {
+                Some('^' | '\'') => return Err(ErrorKind::DoubleSuperscript),
+                Some('_') => return Err(ErrorKind::DoubleSubscript),
+                _ => (),
+            }
+        } else if (next_char == Some('_') && !subscript_first)
             || (next_char == Some('^') && subscript_first)
         {
             *str = &str[1..];
-            let arg = lex::argument(str)?;
+            let arg = self.script_argument(!subscript_first)?;
             self.handle_argument(arg)?;
+            self.set_buffered_style(second_suffix_start, style);
+
+            // Both scripts are already present, so any other script is a double script.
+            let str = self.current_string();
+            *str = str.trim_start();
+            match str.chars().next() {
+                Some('^') => return Err(ErrorKind::DoubleSuperscript),
+                Some('_') => return Err(ErrorKind::DoubleSubscript),
+                _ => (),
+            }
         } else if next_char == Some('_') || next_char == Some('^') {
             return Err(if subscript_first {
                 ErrorKind::DoubleSubscript
@@ -164,6 +611,7 @@ impl<'a> Parser<'a> {
                 ErrorKind::DoubleSuperscript
             });
         }
+        self.state.argument_style = previous_style;
         let second_suffix_end = self.buffer.len();
 
         Ok(
@@ -199,6 +647,11 @@ impl<'a> Parser<'a> {
     /// Otherwise, it pushes the argument to the stack ungrouped.
     fn handle_argument(&mut self, argument: Argument<'a>) -> InnerResult<()> {
         match argument {
+            // The arguments of a script character would be parsed after the element taking it as
+            // an argument, as in `\hat^x`.
+            Argument::Token(Token::Character(c)) if matches!(c.into(), '^' | '_') => {
+                return Err(ErrorKind::Argument);
+            }
             Argument::Token(token) => {
                 self.state.invalidate_relax = true;
                 match token {
@@ -207,64 +660,166 @@ impl<'a> Parser<'a> {
                 };
             }
             Argument::Group(group) => {
-                self.buffer.extend([
-                    Instruction::Event(Event::Begin(Grouping::Normal)),
-                    Instruction::SubGroup {
-                        content: group,
-                        allows_alignment: false,
-                    },
-                    Instruction::Event(Event::End),
-                ]);
+                self.buffer
+                    .push(Instruction::Event(Event::Begin(Grouping::Normal)));
+                self.group_content(group, false);
+                self.buffer.push(Instruction::Event(Event::End));
             }
         };
         Ok(())
     }
 
+    /// Push the content of a group to the buffer.
+    ///
+    /// If the content contains an infix command (e.g., `\over` or `\atopwithdelims`) at its top
+    /// level, the content is split into the numerator and denominator of a fraction.
+    fn group_content(&mut self, content: &'a str, allows_middle: bool) {
+        if !self.infix_fraction(content, allows_middle) {
+            self.buffer.push(Instruction::SubGroup {
+                content,
+                allows_alignment: false,
+                allows_middle,
+                style: self.buffered_style(),
+            });
+        }
+    }
+
+    /// Push the fraction formed by the content if it contains an infix command (e.g., `\over`)
+    /// at its top level, and return whether it does.
+    fn infix_fraction(&mut self, content: &'a str, allows_middle: bool) -> bool {
+        let infix = lex::infix_fraction(content).and_then(|(numerator, infix, mut denominator)| {
+            // If the arguments of the infix command are invalid, the error is reported once the
+            // parser reaches the command.
+            let arguments = lex::infix_arguments(infix, &mut denominator).ok()?;
+            Some((numerator, arguments, denominator))
+        });
+        let Some((numerator, (left, right, bar_size), denominator)) = infix else {
+            return false;
+        };
+
+        let delimited = left.is_some() || right.is_some();
+        if delimited {
+            self.buffer
+                .push(Instruction::Event(Event::Begin(Grouping::LeftRight)));
+        }
+        if let Some(left) = left {
+            self.buffer
+                .push(Instruction::Event(Event::Content(Content::Operator(
+                    Operator {
+                        content: left,
+                        ..Default::default()
+                    },
+                ))));
+        }
+        self.buffer.push(Instruction::Event(Event::Visual(Visual::Fraction(bar_size))));
+        for content in [numerator, denominator] {
+            self.buffer.extend([
+                Instruction::Event(Event::Begin(Grouping::Internal)),
+                Instruction::SubGroup {
+                    content,
+                    allows_alignment: false,
+                    allows_middle,
+                    style: fraction_style(self.buffered_style()),
+                },
+                Instruction::Event(Event::End),
+            ]);
+        }
+        if let Some(right) = right {
+            self.buffer
+                .push(Instruction::Event(Event::Content(Content::Operator(
+                    Operator {
+                        content: right,
+                        ..Default::default()
+                    },
+                ))));
+        }
+        if delimited {
+            self.buffer.push(Instruction::Event(Event::End));
+        }
+        true
+    }
+
+    /// Push the fraction formed by the environment cell starting the current string, if it
+    /// contains an infix command, and skip over the cell.
+    fn cell_content(&mut self) {
+        let content = *self.current_string();
+        if !lex::contains_infix(content) {
+            return;
+        }
+        let cell = lex::cell(content);
+        if self.infix_fraction(cell, false) {
+            *self.current_string() = &content[cell.len()..];
+        }
+    }
+
+    /// Return the error reported along with its context, and discard what was parsed of the
+    /// erroneous token, such that parsing can resume after it.
+    ///
+    /// `stack_len` is the length of the instruction stack before the token was parsed.
+    fn recover(&mut self, kind: ErrorKind, stack_len: usize) -> ParserError<'a> {
+        self.instruction_stack.truncate(stack_len);
+        let error = self.error_with_context(kind);
+        self.buffer.clear();
+        self.state = ParserState::default();
+        error
+    }
+
     /// Return the context surrounding the error reported.
     fn error_with_context(&mut self, kind: ErrorKind) -> ParserError<'a> {
-        let Some(curr_ptr) = self.instruction_stack.last().and_then(|i| match i {
+        // Errors inside of the expansion of a macro stored outside of the input have no context.
+        let Some(distance) = self.instruction_stack.last().and_then(|i| match i {
             Instruction::Event(_) => None,
-            // TODO: Here we should check whether the pointer is currently inside a macro definition or inside
-            // of the inputed string, when macros are supported.
-            Instruction::SubGroup { content: s, .. } => Some(s.as_ptr()),
+            Instruction::SubGroup { content: s, .. } => Self::input_offset(self.input, s),
         }) else {
             return ParserError {
                 context: None,
+                span: None,
+                position: None,
                 error: kind,
             };
         };
-        let initial_byte_ptr = self.input.as_ptr();
-        // Safety:
-        // * Both `self` and `origin` must be either in bounds or one
-        //   byte past the end of the same [allocated object].
-        //   => this is true, as self never changes the allocation of the `input`.
-        //
-        // * Both pointers must be *derived from* a pointer to the same object.
-        //   (See below for an example.)
-        //   => this is true, as `initial_byte_ptr` is derived from `input.as_ptr()`, and
-        //   `curr_ptr` is derived from `s.as_ptr()`, which points to `input`.
-        // * The distance between the pointers, in bytes, must be an exact multiple
-        //   of the size of `T`.
-        //   => this is true, as both pointers are `u8` pointers.
-        // * The distance between the pointers, **in bytes**, cannot overflow an `isize`.
-        //   => this is obvious as the size of a string should not overflow an `isize`.
-        // * The distance being in bounds cannot rely on "wrapping around" the address space.
-        //   => this is true, a `str` does not rely on this behavior either.
-        let distance = unsafe { curr_ptr.offset_from(initial_byte_ptr) } as usize;
         let start = floor_char_boundary(self.input, distance.saturating_sub(15));
         let end = floor_char_boundary(self.input, distance + 15);
 
+        // Whitespace consumed after the token is not part of the span.
+        let span_start = self.token_start.min(distance);
+        let span_end = span_start + self.input[span_start..distance].trim_end().len();
+
+        let preceding = &self.input[..distance];
+        let line = preceding.matches('\n').count() + 1;
+        let line_start = preceding.rfind('\n').map_or(0, |index| index + 1);
+        let column = preceding[line_start..].chars().count() + 1;
+
+        // The caret is placed under the character, which may be more than one byte long.
+        let context_column = self.input[start..distance].chars().count();
+
         ParserError {
-            context: Some((&self.input[start..end], distance - start)),
+            context: Some((&self.input[start..end], context_column)),
+            span: Some(span_start..span_end),
+            position: Some((line, column)),
             error: kind,
         }
     }
+
+    /// Return the byte offset of the given string in the initial input.
+    ///
+    /// Returns `None` if the string does not start inside of the initial input, or one byte past
+    /// its end, which is the case of the expansion of a macro kept in a [`Storage`].
+    fn input_offset(input: &str, string: &str) -> Option<usize> {
+        (string.as_ptr() as usize)
+            .checked_sub(input.as_ptr() as usize)
+            .filter(|offset| *offset <= input.len())
+    }
 }
 
 impl<'a> Iterator for Parser<'a> {
     type Item = Result<Event<'a>, ParserError<'a>>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(peeked) = self.peeked.take() {
+            return Some(peeked);
+        }
+        let stack_len = self.instruction_stack.len();
         match self.instruction_stack.last_mut() {
             Some(Instruction::Event(_)) => {
                 let event = self
@@ -281,6 +836,7 @@ impl<'a> Iterator for Parser<'a> {
                 ..
             }) if content.trim_start().is_empty() => {
                 self.instruction_stack.pop();
+                self.depth -= 1;
                 self.next()
             }
             Some(Instruction::SubGroup {
@@ -289,9 +845,19 @@ impl<'a> Iterator for Parser<'a> {
             }) => {
                 // 1. Parse the next token and output everything to the staging stack.
                 // TODO: when try blocks hit stable, we should use them. (Please be stable soon!)
+                let token_content = content.trim_start();
+                if let Some(offset) = Self::input_offset(self.input, token_content) {
+                    self.token_start = offset;
+                }
                 let token = match lex::token(content) {
                     Ok(token) => token,
-                    Err(err) => return Some(Err(self.error_with_context(err))),
+                    // The rest of the group is a comment.
+                    Err(ErrorKind::EndOfInput) => {
+                        self.instruction_stack.pop();
+                        self.depth -= 1;
+                        return self.next();
+                    }
+                    Err(err) => return Some(Err(self.recover(err, stack_len))),
                 };
                 let maybe_err = match token {
                     // TODO: when expanding a user defined macro, we do not want to check for
@@ -300,20 +866,33 @@ impl<'a> Iterator for Parser<'a> {
                     Token::Character(c) => self.handle_char_token(c),
                 };
                 if let Err(err) = maybe_err {
-                    return Some(Err(self.error_with_context(err)));
+                    return Some(Err(self.recover(err, stack_len)));
                 }
 
                 // 2. Check for suffixes, to complete the atom.
                 let suffix = match self.handle_suffixes() {
-                    Err(err) => return Some(Err(self.error_with_context(err))),
+                    Err(err) => return Some(Err(self.recover(err, stack_len))),
                     Ok(suffix) => suffix,
                 };
 
                 // 3. Drain the staging stack to the instruction stack.
-                self.instruction_stack.extend(self.buffer.drain(..).rev());
+                let suffix_base = self.state.suffix_base;
+                self.instruction_stack
+                    .extend(self.buffer.drain(suffix_base..).rev());
                 if let Some(suffix) = suffix {
                     self.instruction_stack.push(Instruction::Event(suffix));
                 }
+                self.instruction_stack.extend(self.buffer.drain(..).rev());
+
+                // 4. Check that the groups of the token do not nest too deeply.
+                let groups = self.instruction_stack[stack_len..]
+                    .iter()
+                    .filter(|instruction| matches!(instruction, Instruction::SubGroup { .. }))
+                    .count();
+                if self.depth + groups > self.config.max_nesting_depth {
+                    return Some(Err(self.recover(ErrorKind::NestingTooDeep, stack_len)));
+                }
+                self.depth += groups;
 
                 self.state = ParserState::default();
                 self.next()
@@ -323,6 +902,23 @@ impl<'a> Iterator for Parser<'a> {
     }
 }
 
+/// The style of the scripts of content typeset in the given style (TeXbook p. 141).
+fn script_style(style: Style) -> Style {
+    match style {
+        Style::Display | Style::Text => Style::Script,
+        Style::Script | Style::ScriptScript => Style::ScriptScript,
+    }
+}
+
+/// The style of the numerator and denominator of a fraction typeset in the given style (TeXbook
+/// p. 141).
+fn fraction_style(style: Style) -> Style {
+    match style {
+        Style::Display => Style::Text,
+        style => script_style(style),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Token<'a> {
     ControlSequence(&'a str),
@@ -361,6 +957,15 @@ enum Argument<'a> {
     Group(&'a str),
 }
 
+/// What ends a span of text in a text mode argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextBreak<'a> {
+    /// An inline formula, with the content between its math shifts.
+    Math(&'a str),
+    /// A control sequence handled in text mode, e.g., `\textbf`.
+    ControlSequence(&'a str),
+}
+
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum Instruction<'a> {
     /// Send the event
@@ -369,6 +974,9 @@ pub(crate) enum Instruction<'a> {
     SubGroup {
         content: &'a str,
         allows_alignment: bool,
+        allows_middle: bool,
+        /// The style in which the content is typeset, which is tracked for `\mathchoice`.
+        style: Style,
     },
 }
 
@@ -380,21 +988,84 @@ pub(crate) enum Instruction<'a> {
 #[derive(Debug, Error)]
 pub struct ParserError<'a> {
     context: Option<(&'a str, usize)>,
+    span: Option<Range<usize>>,
+    position: Option<(usize, usize)>,
     #[source]
     error: ErrorKind,
 }
 
+impl ParserError<'_> {
+    /// The kind of error that occured.
+    ///
+    /// See [`ErrorKind`] for an example.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.error
+    }
+
+    /// The byte range of the error in the input given to the [`Parser`], if it is known.
+    ///
+    /// The range starts at the token being parsed when the error occured, and ends where the
+    /// parser stopped reading the input.
+    ///
+    /// ```rust
+    /// use pulldown_latex::Parser;
+    ///
+    /// let input = r"x + \foo y";
+    /// let error = Parser::new(input).find_map(Result::err).unwrap();
+    /// assert_eq!(error.span(), Some(4..8));
+    /// assert_eq!(&input[error.span().unwrap()], r"\foo");
+    /// ```
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+
+    /// The line (starting at 1) where the parser stopped reading the input, if it is known.
+    ///
+    /// ```rust
+    /// use pulldown_latex::Parser;
+    ///
+    /// let error = Parser::new("x +\n  \\foo").find_map(Result::err).unwrap();
+    /// assert_eq!(error.line(), Some(2));
+    /// assert_eq!(error.column(), Some(7));
+    /// ```
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|(line, _)| line)
+    }
+
+    /// Whether the error breaks the structure of the input, such that the events following it
+    /// would be meaningless.
+    ///
+    /// This is the case of unbalanced groups (e.g., a `\left` without a `\right`). Other errors,
+    /// such as unknown commands or missing arguments, only affect the erroneous command, and
+    /// parsing can resume after it.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self.error, ErrorKind::UnbalancedGroup(_))
+    }
+
+    /// The column (starting at 1, and counted in characters) where the parser stopped reading
+    /// the input, if it is known.
+    ///
+    /// See [`ParserError::line`] for an example.
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|(_, column)| column)
+    }
+}
+
 impl Display for ParserError<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Error while parsing: ")?;
+        f.write_str("Error while parsing")?;
+        if let Some((line, column)) = self.position {
+            write!(f, " at {line}:{column}")?;
+        }
+        f.write_str(": ")?;
         self.error.fmt(f)?;
-        if let Some((context, char_position)) = self.context {
+        if let Some((context, context_column)) = self.context {
             let context = context.replace(['\n', '\t'], " ");
             f.write_str("\n --> Context: ")?;
             const PREFIX_LEN: usize = 14;
             f.write_str(&context)?;
             f.write_str("\n")?;
-            f.write_fmt(format_args!("{:>1$}", "^", char_position + PREFIX_LEN))?;
+            f.write_fmt(format_args!("{:>1$}", "^", context_column + PREFIX_LEN))?;
         }
         Ok(())
     }
@@ -402,16 +1073,33 @@ impl Display for ParserError<'_> {
 
 pub(crate) type InnerResult<T> = std::result::Result<T, ErrorKind>;
 
+/// The kind of error that occured while parsing, as returned by [`ParserError::kind`].
+///
+/// New kinds of errors may be added in the future, hence the enum is not exhaustive.
+///
+/// ```rust
+/// use pulldown_latex::{parser::ErrorKind, Parser};
+///
+/// let error = Parser::new(r"x^a^b").find_map(Result::err).unwrap();
+/// assert!(matches!(error.kind(), ErrorKind::DoubleSuperscript));
+/// ```
 #[derive(Debug, Error)]
-pub(crate) enum ErrorKind {
+#[non_exhaustive]
+pub enum ErrorKind {
     #[error("unbalanced group found, expected {:?}", .0)]
     UnbalancedGroup(Option<Grouping>),
     #[error("unkown mathematical environment found")]
     Environment,
+    #[error("expected the column specification of an array, made of at most 24 `l`, `c`, `r`, and `|`")]
+    ArrayColumns,
     #[error(
         "unexpected math `$` (math shift) character - this character is currently unsupported"
     )]
     MathShift,
+    #[error(
+        "unbalanced inline formula delimiters - expected a `$` or `\\)` closing the formula"
+    )]
+    UnbalancedMathShift,
     #[error(
         "unexpected hash sign `#` character - this character can only be used in macro definitions"
     )]
@@ -428,6 +1116,8 @@ pub(crate) enum ErrorKind {
     MathUnit,
     #[error("expected a delimiter token")]
     Delimiter,
+    #[error("expected a delimiter size (`\\big`, `\\Big`, `\\bigg`, or `\\Bigg`)")]
+    DelimiterSize,
     #[error("expected a control sequence")]
     ControlSequence,
     #[error("expected a number")]
@@ -440,6 +1130,12 @@ pub(crate) enum ErrorKind {
     DoubleSubscript,
     #[error("trying to add a superscript twice to the same element")]
     DoubleSuperscript,
+    #[error("groups are nested too deeply, see `ParserConfig::max_nesting_depth`")]
+    NestingTooDeep,
+    #[error("expected a subscript after `_`")]
+    EmptySubscript,
+    #[error("expected a superscript after `^`")]
+    EmptySuperscript,
     #[error("unknown primitive command found")]
     UnknownPrimitive,
     #[error("control sequence found as argument to a command that does not support them")]
@@ -452,6 +1148,102 @@ pub(crate) enum ErrorKind {
     InvalidCharNumber,
     #[error("cannot use the `\\relax` command in this context")]
     Relax,
+    #[error("the `\\middle` command can only be used between `\\left` and `\\right`")]
+    MiddleOutsideLeftRight,
+    #[error("ambiguous use of an infix command, only one infix command (e.g., `\\over`) can be used in a group")]
+    AmbiguousInfix,
+    #[error("limit controls (`\\limits` and `\\nolimits`) must follow a math operator")]
+    LimitControl,
+    #[error("expected a `\\fi` closing the conditional")]
+    UnterminatedConditional,
+    #[error("`\\else`, `\\or`, and `\\fi` must close a conditional, and a conditional can only contain one `\\else`")]
+    ConditionalEnd,
+    #[error("expected a relation (`<`, `=`, or `>`)")]
+    Relation,
+    #[error("`\\csname` must be followed by characters and closed by `\\endcsname`")]
+    ControlSequenceName,
+    #[error("the command is only allowed when `ParserConfig::trust` is enabled")]
+    Untrusted,
+    #[error("the protocol of the URL is only allowed when `ParserConfig::trust` is enabled")]
+    UntrustedUrl,
+    #[error("invalid parameter text in macro definition, parameters must be numbered `#1` to `#9` in order")]
+    MacroParameters,
+    #[error("the arguments of the macro do not match its parameter text")]
+    MacroArguments,
+    #[error("expanding this macro requires a `Storage`, see `Parser::with_storage`")]
+    MacroStorage,
+}
+
+/// Whether the given control sequence name (without the leading backslash) is a command known to
+/// the parser.
+///
+/// ```rust
+/// use pulldown_latex::parser::is_known_primitive;
+///
+/// assert!(is_known_primitive("frac"));
+/// assert!(!is_known_primitive("foo"));
+/// ```
+pub fn is_known_primitive(name: &str) -> bool {
+    // The command is parsed against an empty input, so commands that expect arguments fail with an
+    // error other than `UnknownPrimitive`.
+    let mut parser = Parser::new("");
+    !name.is_empty()
+        && !matches!(
+            parser.handle_primitive(name),
+            Err(ErrorKind::UnknownPrimitive)
+        )
+}
+
+/// The character produced by the given control sequence name (without the leading backslash),
+/// along with its atom class, if the command produces a single symbol.
+///
+/// Delimiters are classified as opening or closing delimiters, except for fences (e.g., `\vert`)
+/// which are ordinary atoms.
+///
+/// ```rust
+/// use pulldown_latex::{event::AtomClass, parser::primitive_to_char};
+///
+/// assert_eq!(primitive_to_char("alpha"), Some(('α', AtomClass::Ordinary)));
+/// assert_eq!(primitive_to_char("leq"), Some(('≤', AtomClass::Relation)));
+/// assert_eq!(primitive_to_char("lgroup"), Some(('⟮', AtomClass::Opening)));
+/// assert_eq!(primitive_to_char("rvert"), Some(('|', AtomClass::Closing)));
+/// assert_eq!(primitive_to_char("sum"), Some(('∑', AtomClass::Operator)));
+/// assert_eq!(primitive_to_char("int"), Some(('∫', AtomClass::Operator)));
+/// assert_eq!(primitive_to_char("frac"), None);
+/// ```
+pub fn primitive_to_char(name: &str) -> Option<(char, AtomClass)> {
+    symbols::identifier(name)
+        .map(|c| (c, AtomClass::Ordinary))
+        .or_else(|| symbols::operator(name))
+        .or_else(|| symbols::large_operator(name).map(|(c, _)| (c, AtomClass::Operator)))
+        .or_else(|| {
+            let delimiter = tables::control_sequence_delimiter_map(name)?;
+            let class =
+                tables::control_sequence_delimiter_class(name).unwrap_or(AtomClass::Ordinary);
+            Some((delimiter, class))
+        })
+}
+
+/// The name of the command (without the leading backslash) producing the given character, if a
+/// command of the parser produces it as a single symbol.
+///
+/// When more than one command produces the character, a canonical name is returned. The name is
+/// looked up in the same tables as [`primitive_to_char`], which maps it back to the character.
+///
+/// ```rust
+/// use pulldown_latex::parser::char_to_primitive;
+///
+/// assert_eq!(char_to_primitive('α'), Some("alpha"));
+/// assert_eq!(char_to_primitive('≤'), Some("leq"));
+/// assert_eq!(char_to_primitive('⟨'), Some("langle"));
+/// assert_eq!(char_to_primitive('a'), None);
+/// ```
+pub fn char_to_primitive(c: char) -> Option<&'static str> {
+    symbols::name(c).or_else(|| {
+        tables::CONTROL_SEQUENCE_DELIMITERS
+            .iter()
+            .find_map(|&(name, delimiter)| (delimiter == c).then_some(name))
+    })
 }
 
 fn floor_char_boundary(str: &str, index: usize) -> usize {
@@ -471,7 +1263,12 @@ fn floor_char_boundary(str: &str, index: usize) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use crate::event::{Content, Identifier, Operator, Visual};
+    use crate::attribute::DimensionUnit;
+    use crate::config::ErrorPolicy;
+    use crate::event::{
+        AnnotationKind, AtomClass, BoxAlignment, ColorChange, ColorTarget, Content, Identifier, LapDirection, Operator, OwnedContent,
+        OwnedEvent, OwnedStateChange, StateChange, StrikeKind, Style, TextFont, Visual,
+    };
 
     use super::*;
 
@@ -485,10 +1282,7 @@ mod tests {
         assert_eq!(
             events,
             vec![
-                Event::Script {
-                    ty: ScriptType::Superscript,
-                    position: ScriptPosition::AboveBelow
-                },
+                Event::Visual(Visual::Accent),
                 Event::Begin(Grouping::Normal),
                 Event::Content(Content::Identifier(Identifier::Char('y'))),
                 Event::End,
@@ -662,53 +1456,2463 @@ mod tests {
         );
     }
 
-    // For mir
     #[test]
-    fn multidigit_number() {
-        let parser = Parser::new("123");
+    fn overbracket_label() {
+        let parser = Parser::new(r"\overbracket[1pt][0.5em]{x}^n");
         let events = parser
             .collect::<Result<Vec<_>, ParserError<'static>>>()
             .unwrap();
 
-        assert_eq!(events, vec![Event::Content(Content::Number("123"))]);
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Visual(Visual::Accent),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: '⎴',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('n'))),
+            ]
+        );
     }
-}
 
-// Token parsing procedure, as per TeXbook p. 46-47.
-//
-// This is roughly what the lexer implementation will look like for text mode.
-//
-// 1. Trim any trailing whitespace from a line.
-//
-// 2. If '\' (escape character) is encountered, parse the next token.
-//  '\n' => _The name is empty_???
-//  'is_ascii_alphabetic' => parse until an non ASCII alphabetic, and the name is the token
-//  'otherwise' => parse next character, and the name is the symbol.
-//
-//  Go to SkipBlanks mode if the token is a word or a space symbol.
-//  Otherwise, go to MidLine mode.
-//
-// 3. If `^^` is found:
-//  - If the following are two characters of type ASCII lowercase letter or digit,
-//  then `^^__` is converted to the correspoding ascii value.
-//  - If the following is a single ASCII character, then `^^_` is converted to the corresponding ASCII
-//  value with the formula: if `c` is the character, then `c + 64` if `c` if the character has code
-//  between 0 and 63, and `c - 64` if the character has code between 64 and 127.
-//
-//  __Note__: This rule takes precedence over escape character parsing. If such a sequence is found
-//  in an escape sequence, it is converted to the corresponding ASCII value.
-//
-// 4. If the token is a single character, go to MidLine mode.
-//
-// 5. If the token is an end of line, go to the next line. If nothing was on the line (were in NewLine state), then the
-//  `par` token is emitted, meaning that a new paragraph should be started.
-//  If the state was MidLine, then the newline is transformed into a space.
-//  If the state was SkipBlanks, then the newline is ignored.
-//
-// 6. Ignore characters from the `Ignore` category.
-//
-// 7. If the token is a space and the mode is MidLine, the space is transformed into a space token.
-//
-// 8. If the token is a comment, ignore the rest of the line, and go to the next line.
-//
-// 9. Go to newlines on the next line.
+    #[test]
+    fn adjustlimits() {
+        let parser = Parser::new(r"\adjustlimits\lim_{n}\sup_k x");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::AdjustLimits),
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Movable
+                },
+                Event::Content(Content::Identifier(Identifier::Str("lim"))),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('n'))),
+                Event::End,
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Movable
+                },
+                Event::Content(Content::Identifier(Identifier::Str("sup"))),
+                Event::Content(Content::Identifier(Identifier::Char('k'))),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+            ]
+        );
+
+        // Operators taking an argument are part of the adjusted group.
+        let events = Parser::new(r"\adjustlimits\operatorname{lim}\limits_n\mathop{x}_k y")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(events.first(), Some(&Event::Begin(Grouping::AdjustLimits)));
+        assert_eq!(
+            events[events.len() - 2..],
+            [
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn genfrac_vertical_bars() {
+        let parser = Parser::new(r"\genfrac{|}{|}{}{}{a}{b}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        // Delimiters inside of a `LeftRight` group are left with the default stretchiness, as is
+        // the case for `\left| ... \right|`.
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::LeftRight),
+                Event::Content(Content::Operator(Operator {
+                    content: '|',
+                    ..Default::default()
+                })),
+                Event::Visual(Visual::Fraction(None)),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: '|',
+                    ..Default::default()
+                })),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn boxed() {
+        let parser = Parser::new(r"\boxed{x}\boxed y");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Visual(Visual::Boxed),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Visual(Visual::Boxed),
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+            ]
+        );
+
+        // The frame of `\fcolorbox` is the border of the box.
+        let events = Parser::new(r"\fcolorbox{red}{blue}{y}")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(
+            events[..3],
+            [
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::Color(ColorChange {
+                    color: "red",
+                    target: ColorTarget::Border,
+                })),
+                Event::StateChange(StateChange::Color(ColorChange {
+                    color: "blue",
+                    target: ColorTarget::Background,
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn left_right_superscript() {
+        let parser = Parser::new(r"\left(x+1\right)^2");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Begin(Grouping::LeftRight),
+                Event::Content(Content::Operator(Operator {
+                    content: '(',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Content(Content::Operator(Operator {
+                    content: '+',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Number("1")),
+                Event::Content(Content::Operator(Operator {
+                    content: ')',
+                    ..Default::default()
+                })),
+                Event::End,
+                Event::Content(Content::Number("2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn middle() {
+        let parser = Parser::new(r"\left( a \middle| b \right)");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::LeftRight),
+                Event::Content(Content::Operator(Operator {
+                    content: '(',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::Content(Content::Operator(Operator {
+                    content: '|',
+                    stretchy: Some(true),
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::Content(Content::Operator(Operator {
+                    content: ')',
+                    ..Default::default()
+                })),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn middle_outside_left_right() {
+        for input in [r"a \middle| b", r"\left( {a \middle| b} \right)"] {
+            let err = Parser::new(input)
+                .collect::<Result<Vec<_>, ParserError<'static>>>()
+                .unwrap_err();
+            assert!(matches!(err.error, ErrorKind::MiddleOutsideLeftRight));
+        }
+    }
+
+    #[test]
+    fn greek_codepoints() {
+        let parser = Parser::new(r"\mu\phi\varphi");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Identifier(Identifier::Char('\u{03BC}'))),
+                Event::Content(Content::Identifier(Identifier::Char('\u{03D5}'))),
+                Event::Content(Content::Identifier(Identifier::Char('\u{03C6}'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn cramped() {
+        let parser = Parser::new(r"\cramped[\scriptstyle]{x}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::Style(Style::Script)),
+                Event::StateChange(StateChange::Cramped),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn sum_followed_by_subscript() {
+        let parser = Parser::new(r"\sum_{i=1}^n a_i");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::SubSuperscript,
+                    position: ScriptPosition::Movable
+                },
+                Event::Content(Content::Operator(Operator {
+                    content: '∑',
+                    deny_movable_limits: true,
+                    ..Default::default()
+                })),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('i'))),
+                Event::Content(Content::Operator(Operator {
+                    content: '=',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Number("1")),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('n'))),
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::Content(Content::Identifier(Identifier::Char('i'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn splitfrac() {
+        let parser = Parser::new(r"\splitdfrac{a}{b}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Internal),
+                Event::StateChange(StateChange::Style(Style::Display)),
+                Event::Visual(Visual::SplitFraction),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::End,
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn continued_fractions() {
+        let parser = Parser::new(r"\cfrac[l]{1}{\cfrac[r]a\mathchoice{d}{t}{s}{s}}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Internal),
+                Event::StateChange(StateChange::Style(Style::Display)),
+                Event::Visual(Visual::ContinuedFraction(BoxAlignment::Left)),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Number("1")),
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::Begin(Grouping::Internal),
+                Event::StateChange(StateChange::Style(Style::Display)),
+                Event::Visual(Visual::ContinuedFraction(BoxAlignment::Right)),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                // The denominator is in display style.
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('d'))),
+                Event::End,
+                Event::End,
+                Event::End,
+                Event::End,
+            ]
+        );
+        assert!(Parser::new(r"\cfrac[x]{1}{2}").any(|event| event.is_err()));
+    }
+
+    #[test]
+    fn big_operator_limit_defaults() {
+        let above_below = [
+            "sum", "prod", "coprod", "bigvee", "bigwedge", "bigcup", "bigcap", "biguplus",
+            "bigoplus", "bigotimes", "bigodot", "bigsqcup", "bigsqcap", "bigtimes", "intop",
+        ];
+        let beside = [
+            "int", "iint", "iiint", "smallint", "iiiint", "intcap", "intcup", "oint", "oiint",
+            "oiiint", "sqint", "fint",
+        ];
+        for (operators, position) in [
+            (&above_below[..], ScriptPosition::Movable),
+            (&beside[..], ScriptPosition::Right),
+        ] {
+            for op in operators {
+                let input = format!(r"\{op}_a^b");
+                let events = Parser::new(&input)
+                    .collect::<Result<Vec<_>, ParserError>>()
+                    .unwrap();
+                assert_eq!(
+                    events[0],
+                    Event::Script {
+                        ty: ScriptType::SubSuperscript,
+                        position
+                    },
+                    "unexpected limit placement for `\\{op}`"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lap_under_big_operator() {
+        let parser = Parser::new(r"\sum_{\mathclap{i}} \rlap{x}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Movable
+                },
+                Event::Content(Content::Operator(Operator {
+                    content: '∑',
+                    deny_movable_limits: true,
+                    ..Default::default()
+                })),
+                Event::Begin(Grouping::Normal),
+                Event::Visual(Visual::Lap(LapDirection::Center)),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('i'))),
+                Event::End,
+                Event::End,
+                Event::Visual(Visual::Lap(LapDirection::Right)),
+                Event::Content(Content::Text("x")),
+            ]
+        );
+    }
+
+    #[test]
+    fn dddot_with_scripts() {
+        let parser = Parser::new(r"\dddot{f}^2 \ddddot f");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Visual(Visual::Accent),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('f'))),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: '\u{20DB}',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Number("2")),
+                Event::Visual(Visual::Accent),
+                Event::Content(Content::Identifier(Identifier::Char('f'))),
+                Event::Content(Content::Operator(Operator {
+                    content: '\u{20DC}',
+                    ..Default::default()
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn raise_and_lower() {
+        let parser = Parser::new(r"\raisebox{-1pt}{a} \lower2pt x \raise 0.5em {y}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Visual(Visual::Raise((-1., DimensionUnit::Pt))),
+                Event::Content(Content::Text("a")),
+                Event::Visual(Visual::Raise((-2., DimensionUnit::Pt))),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Visual(Visual::Raise((0.5, DimensionUnit::Em))),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn infix_commands() {
+        let parser = Parser::new(r"{a \over b} n \choose k");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::LeftRight),
+                Event::Content(Content::Operator(Operator {
+                    content: '(',
+                    ..Default::default()
+                })),
+                Event::Visual(Visual::Fraction(Some((0., DimensionUnit::Em)))),
+                Event::Begin(Grouping::Internal),
+                Event::Begin(Grouping::Normal),
+                Event::Visual(Visual::Fraction(None)),
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::End,
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::End,
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('n'))),
+                Event::End,
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Identifier(Identifier::Char('k'))),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: ')',
+                    ..Default::default()
+                })),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn ambiguous_infix() {
+        let err = Parser::new(r"a \over b \atop c")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap_err();
+        assert!(matches!(err.error, ErrorKind::AmbiguousInfix));
+
+        // Each cell of an environment can contain an infix command.
+        let events = Parser::new(r"\begin{matrix} \overline{a} \over b & c \\ d \atop e \end{matrix}")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, Event::Visual(Visual::Fraction(_))))
+                .count(),
+            2
+        );
+        assert!(events.contains(&Event::Alignment));
+        assert!(events.contains(&Event::NewLine));
+    }
+
+    #[test]
+    fn smallint() {
+        let parser = Parser::new(r"\smallint_0^1 \int");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::SubSuperscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Operator(Operator {
+                    content: '∫',
+                    size: Some((0.7, DimensionUnit::Em)),
+                    ..Default::default()
+                })),
+                Event::Content(Content::Number("0")),
+                Event::Content(Content::Number("1")),
+                Event::Content(Content::Operator(Operator {
+                    content: '∫',
+                    ..Default::default()
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_integrals() {
+        let parser = Parser::new(r"\iint_D \iiint_V");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Operator(Operator {
+                    content: '∬',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('D'))),
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Operator(Operator {
+                    content: '∭',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('V'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn infix_commands_with_arguments() {
+        let parser = Parser::new(r"a \abovewithdelims [ . 1pt b");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::LeftRight),
+                Event::Content(Content::Operator(Operator {
+                    content: '[',
+                    ..Default::default()
+                })),
+                Event::Visual(Visual::Fraction(Some((1., DimensionUnit::Pt)))),
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::End,
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::End,
+                Event::End,
+            ]
+        );
+
+        let err = Parser::new(r"a \above x b")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap_err();
+        assert!(matches!(err.error, ErrorKind::DimensionArgument));
+        let err = Parser::new(r"a \overwithdelims x ) b")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap_err();
+        assert!(matches!(err.error, ErrorKind::Delimiter));
+    }
+
+    #[test]
+    fn stacked_accents() {
+        let parser = Parser::new(r"\dot{\hat{q}}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Visual(Visual::Accent),
+                Event::Begin(Grouping::Normal),
+                Event::Visual(Visual::Accent),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('q'))),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: '^',
+                    stretchy: Some(false),
+                    ..Default::default()
+                })),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: '˙',
+                    ..Default::default()
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn varinjlim() {
+        let parser = Parser::new(r"\varinjlim_n \projlim");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Movable
+                },
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::AboveBelow
+                },
+                Event::Content(Content::Identifier(Identifier::Str("lim"))),
+                Event::Content(Content::Operator(Operator {
+                    content: '→',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('n'))),
+                Event::Content(Content::Identifier(Identifier::Str("proj lim"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn boxed_with_suffix_and_color() {
+        let parser = Parser::new(r"\boxed{\color{red} x}_1");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Visual(Visual::Boxed),
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::Color(ColorChange {
+                    color: "red",
+                    target: ColorTarget::Text,
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Content(Content::Number("1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_on_integrals() {
+        for op in ["iint", "iiint", "smallint"] {
+            let input = format!(r"\{op}\limits_D");
+            let events = Parser::new(&input)
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap();
+            assert_eq!(
+                events[0],
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::AboveBelow
+                },
+                "unexpected limit placement for `\\{op}\\limits`"
+            );
+        }
+    }
+
+    #[test]
+    fn strikes() {
+        let events = Parser::new(r"\cancel x \bcancel{y} \xcancel 1 \sout{z}")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Visual(Visual::Strike(StrikeKind::UpDiagonal)),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Visual(Visual::Strike(StrikeKind::DownDiagonal)),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+                Event::End,
+                Event::Visual(Visual::Strike(StrikeKind::Cross)),
+                Event::Content(Content::Number("1")),
+                Event::Visual(Visual::Strike(StrikeKind::Horizontal)),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('z'))),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn framebox() {
+        let parser = Parser::new(r"\framebox[3cm][r]{hi} \fbox{$x$}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Visual(Visual::Framed {
+                    width: Some((3., DimensionUnit::Cm)),
+                    alignment: BoxAlignment::Right,
+                }),
+                Event::Content(Content::Text("hi")),
+                Event::Visual(Visual::Framed {
+                    width: None,
+                    alignment: BoxAlignment::Center,
+                }),
+                Event::Begin(Grouping::Normal),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_math_in_text() {
+        let parser = Parser::new(r"\text{if $x^2$ then \$1 $y$}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Text("if ")),
+                Event::Begin(Grouping::Normal),
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Content(Content::Number("2")),
+                Event::End,
+                Event::Content(Content::Text(" then ")),
+                Event::Content(Content::Text("$1 ")),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+                Event::End,
+                Event::End,
+            ]
+        );
+
+        let events = Parser::new(r"\text{a \(x\) $b \text{ if $c$}$}")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Text("a ")),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Content(Content::Text(" ")),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Text(" if ")),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('c'))),
+                Event::End,
+                Event::End,
+                Event::End,
+                Event::End,
+            ]
+        );
+
+        // The error starts at the unbalanced delimiter, inside of the argument.
+        for (input, span) in [
+            (r"x + \text{a $b}", 12..15),
+            (r"\text{a \(b}", 8..12),
+            (r"\text{a \) b}", 8..13),
+        ] {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            assert!(matches!(error.kind(), ErrorKind::UnbalancedMathShift));
+            assert_eq!(error.span(), Some(span), "{input}");
+        }
+    }
+
+    #[test]
+    fn text_fonts() {
+        let parser = Parser::new(r"\text{a \textbf{b \textit c}}\texttt{$x$}");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Text("a ")),
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::TextFont(TextFont::Bold)),
+                Event::Content(Content::Text("b ")),
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::TextFont(TextFont::Italic)),
+                Event::Content(Content::Text("c")),
+                Event::End,
+                Event::End,
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::TextFont(TextFont::Monospace)),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn caret_notation() {
+        let events = Parser::new(r"^^5cbeta^^Ix^^32^2")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Identifier(Identifier::Char('β'))),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Number("2")),
+                Event::Content(Content::Number("2")),
+            ]
+        );
+
+        // Carets which do not start a character in `^^` notation are superscripts.
+        let error = Parser::new(r"x^^").find_map(Result::err).unwrap();
+        assert!(matches!(error.kind(), ErrorKind::EmptySuperscript));
+    }
+
+    #[test]
+    fn negated_relations() {
+        let events = Parser::new(r"\not= \not\in \not\perp")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Operator(Operator {
+                    content: '≠',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Operator(Operator {
+                    content: '∉',
+                    ..Default::default()
+                })),
+                Event::Visual(Visual::Negation),
+                Event::Content(Content::Operator(Operator {
+                    content: '⟂',
+                    ..Default::default()
+                })),
+            ]
+        );
+
+        // The argument of `\not` must produce something to negate.
+        for input in [r"\not\iffalse x\fi", r"\not \def\a{x}", r"\not\let\c=\alpha"] {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            assert!(matches!(error.kind(), ErrorKind::Argument), "{input}");
+        }
+    }
+
+    #[test]
+    fn primes() {
+        let events = Parser::new(r"f'' g'^2_n")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('f'))),
+                Event::Content(Content::Operator(Operator {
+                    content: '″',
+                    ..Default::default()
+                })),
+                Event::Script {
+                    ty: ScriptType::SubSuperscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('g'))),
+                Event::Content(Content::Identifier(Identifier::Char('n'))),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Operator(Operator {
+                    content: '′',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Number("2")),
+                Event::End,
+            ]
+        );
+
+        let error = Parser::new(r"f'^2^3").find_map(Result::err).unwrap();
+        assert!(matches!(error.kind(), ErrorKind::DoubleSuperscript));
+
+        // Primes following a subscript start the superscript.
+        let events = Parser::new(r"f_1' g_n'^2")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::SubSuperscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('f'))),
+                Event::Content(Content::Number("1")),
+                Event::Content(Content::Operator(Operator {
+                    content: '′',
+                    ..Default::default()
+                })),
+                Event::Script {
+                    ty: ScriptType::SubSuperscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('g'))),
+                Event::Content(Content::Identifier(Identifier::Char('n'))),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Operator(Operator {
+                    content: '′',
+                    ..Default::default()
+                })),
+                Event::Content(Content::Number("2")),
+                Event::End,
+            ]
+        );
+
+        let error = Parser::new(r"f_1'^2'").find_map(Result::err).unwrap();
+        assert!(matches!(error.kind(), ErrorKind::DoubleSuperscript));
+    }
+
+    #[test]
+    fn display_mode_config() {
+        let config = ParserConfig {
+            display_mode: DisplayMode::Block,
+            ..Default::default()
+        };
+        let events = Parser::with_config(r"\sum_i", config)
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Movable
+                },
+                Event::Content(Content::Operator(Operator {
+                    content: '∑',
+                    deny_movable_limits: true,
+                    ..Default::default()
+                })),
+                Event::Content(Content::Identifier(Identifier::Char('i'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_commands() {
+        assert!(Parser::new(r"\foo").collect::<Result<Vec<_>, _>>().is_err());
+
+        let parse = |on_unknown_command| {
+            let config = ParserConfig {
+                on_unknown_command,
+                ..Default::default()
+            };
+            Parser::with_config(r"\foo{x} + 1", config)
+                .collect::<Result<Vec<_>, ParserError<'static>>>()
+                .unwrap()
+        };
+        let rest = [
+            Event::Begin(Grouping::Normal),
+            Event::Content(Content::Identifier(Identifier::Char('x'))),
+            Event::End,
+            Event::Content(Content::Operator(Operator {
+                content: '+',
+                ..Default::default()
+            })),
+            Event::Content(Content::Number("1")),
+        ];
+
+        assert_eq!(parse(ErrorPolicy::Skip), rest);
+        assert_eq!(parse(ErrorPolicy::EmitPlaceholder)[0], Event::UnknownCommand("foo"));
+        assert_eq!(parse(ErrorPolicy::EmitPlaceholder)[1..], rest);
+        assert_eq!(
+            parse(ErrorPolicy::EmitIdentifier)[0],
+            Event::Content(Content::Identifier(Identifier::Str("foo")))
+        );
+        assert_eq!(parse(ErrorPolicy::EmitIdentifier)[1..], rest);
+    }
+
+    #[test]
+    fn atom_classes() {
+        let events = Parser::new(r"a \mathbin{!} b \mathop{T}\limits_a")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::Begin(Grouping::AtomClass(AtomClass::Binary)),
+                Event::Content(Content::Operator(Operator {
+                    content: '!',
+                    ..Default::default()
+                })),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::AboveBelow
+                },
+                Event::Begin(Grouping::AtomClass(AtomClass::Operator)),
+                Event::Content(Content::Identifier(Identifier::Char('T'))),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn atom_class_commands() {
+        let classes = [
+            ("mathord", AtomClass::Ordinary),
+            ("mathop", AtomClass::Operator),
+            ("mathbin", AtomClass::Binary),
+            ("mathrel", AtomClass::Relation),
+            ("mathopen", AtomClass::Opening),
+            ("mathclose", AtomClass::Closing),
+            ("mathpunct", AtomClass::Punctuation),
+            ("mathinner", AtomClass::Inner),
+        ];
+        for (name, class) in classes {
+            let expected = vec![
+                Event::Begin(Grouping::AtomClass(class)),
+                Event::Content(Content::Identifier(Identifier::Char('#'))),
+                Event::End,
+            ];
+            for input in [format!("\\{name}{{\\#}}"), format!("\\{name}\\#")] {
+                let events = Parser::new(&input)
+                    .collect::<Result<Vec<_>, ParserError>>()
+                    .unwrap();
+                assert_eq!(events, expected, "{input}");
+            }
+        }
+
+        let events = Parser::new(r"\mathop{T}\nolimits^2")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(
+            events[..2],
+            [
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Begin(Grouping::AtomClass(AtomClass::Operator)),
+            ]
+        );
+    }
+
+    #[test]
+    fn vcenter() {
+        let events = Parser::new(r"\left( \vcenter{x} \right)")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::LeftRight),
+                Event::Content(Content::Operator(Operator {
+                    content: '(',
+                    ..Default::default()
+                })),
+                Event::Visual(Visual::VCenter),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: ')',
+                    ..Default::default()
+                })),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn function_scripts() {
+        let events = Parser::new(r"\sin^2 x \log_2 x")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Str("sin"))),
+                Event::Content(Content::Number("2")),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Str("log"))),
+                Event::Content(Content::Number("2")),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn modulo() {
+        let space = |width: f32| Event::Space {
+            width: Some((width / 18., DimensionUnit::Em)),
+            height: None,
+            depth: None,
+        };
+        let a = Event::Content(Content::Identifier(Identifier::Char('a')));
+        let b = Event::Content(Content::Identifier(Identifier::Char('b')));
+        let mod_ = Event::Content(Content::Identifier(Identifier::Str("mod")));
+        let paren = |content| {
+            Event::Content(Content::Operator(Operator {
+                content,
+                ..Default::default()
+            }))
+        };
+        let parse = |input| {
+            Parser::new(input)
+                .collect::<Result<Vec<_>, ParserError<'static>>>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            parse(r"a \bmod b"),
+            vec![
+                a,
+                Event::Begin(Grouping::AtomClass(AtomClass::Binary)),
+                mod_,
+                Event::End,
+                b
+            ]
+        );
+        assert_eq!(
+            parse(r"a \pmod b"),
+            vec![
+                a,
+                space(8.),
+                Event::Begin(Grouping::Internal),
+                paren('('),
+                mod_,
+                b,
+                paren(')'),
+                Event::End
+            ]
+        );
+        assert_eq!(parse(r"a \mod b"), vec![a, space(12.), mod_, b]);
+        assert_eq!(
+            parse(r"a \pod b"),
+            vec![
+                a,
+                space(8.),
+                Event::Begin(Grouping::Internal),
+                paren('('),
+                b,
+                paren(')'),
+                Event::End
+            ]
+        );
+
+        let subscript = Event::Script {
+            ty: ScriptType::Subscript,
+            position: ScriptPosition::Right,
+        };
+        let one = Event::Content(Content::Number("1"));
+        assert_eq!(
+            parse(r"\mod b_1"),
+            vec![space(12.), mod_, subscript, b, one]
+        );
+        assert_eq!(
+            parse(r"\pod b_1"),
+            vec![
+                space(8.),
+                subscript,
+                Event::Begin(Grouping::Internal),
+                paren('('),
+                b,
+                paren(')'),
+                Event::End,
+                one
+            ]
+        );
+    }
+
+    #[test]
+    fn peek() {
+        let mut parser = Parser::new(r"a^2");
+        let script = Event::Script {
+            ty: ScriptType::Superscript,
+            position: ScriptPosition::Right,
+        };
+
+        assert_eq!(parser.peek().unwrap().unwrap(), &script);
+        assert_eq!(parser.peek().unwrap().unwrap(), &script);
+        assert_eq!(parser.next().unwrap().unwrap(), script);
+        assert_eq!(
+            parser.next().unwrap().unwrap(),
+            Event::Content(Content::Identifier(Identifier::Char('a')))
+        );
+        assert_eq!(
+            parser.peek().unwrap().unwrap(),
+            &Event::Content(Content::Number("2"))
+        );
+        assert_eq!(
+            parser.next().unwrap().unwrap(),
+            Event::Content(Content::Number("2"))
+        );
+        assert!(parser.peek().is_none());
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn text_mode() {
+        let text = |input| {
+            Parser::new(input)
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap()
+        };
+        assert_eq!(
+            text("\\text{a   b\n  c~d}"),
+            vec![
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Text("a ")),
+                Event::Content(Content::Text("b")),
+                Event::Content(Content::Text(" ")),
+                Event::Content(Content::Text("c")),
+                Event::Content(Content::Text("\u{a0}")),
+                Event::Content(Content::Text("d")),
+                Event::End,
+            ]
+        );
+        assert_eq!(
+            text(r"\text{50\%}"),
+            vec![
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Text("50")),
+                Event::Content(Content::Text("%")),
+                Event::End,
+            ]
+        );
+        assert_eq!(text(r"\text{a b}"), vec![Event::Content(Content::Text("a b"))]);
+
+        // With a storage, the text is given as a single event.
+        let storage = Storage::new();
+        let events = Parser::with_storage(
+            "\\text{a   b\n  c~d} \\text{50\\%}",
+            ParserConfig::default(),
+            &storage,
+        )
+        .collect::<Result<Vec<_>, ParserError>>()
+        .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Text("a b c\u{a0}d")),
+                Event::Content(Content::Text("50%")),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_accents() {
+        let events = Parser::new(r"\text{caf\'e}")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Text("caf")),
+                Event::Content(Content::Text("é")),
+                Event::End,
+            ]
+        );
+
+        let storage = Storage::new();
+        let events = Parser::with_storage(
+            r#"\text{caf\'e} \textbf{na\"{\i}ve $x$ \v{s}\k a} \text{\r{}}"#,
+            ParserConfig::default(),
+            &storage,
+        )
+        .collect::<Result<Vec<_>, ParserError>>()
+        .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Text("café")),
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::TextFont(TextFont::Bold)),
+                Event::Content(Content::Text("naïve ")),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Content(Content::Text(" šą")),
+                Event::End,
+                Event::Content(Content::Text("\u{a0}\u{30a}")),
+            ]
+        );
+    }
+
+    #[test]
+    fn mbox() {
+        let events = Parser::new(r"x_{\mbox{if {a} b}} \hbox{c}")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Begin(Grouping::Normal),
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::Style(Style::Text)),
+                Event::Begin(Grouping::Internal),
+                Event::Content(Content::Text("if ")),
+                Event::Content(Content::Text("a")),
+                Event::Content(Content::Text(" b")),
+                Event::End,
+                Event::End,
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::StateChange(StateChange::Style(Style::Text)),
+                Event::Content(Content::Text("c")),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn owned_events() {
+        let input = String::from(r"\sin{\color{red} 2.5}\text{a}");
+        let events = Parser::new(&input)
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        let owned = events
+            .iter()
+            .map(|event| event.into_owned())
+            .collect::<Vec<_>>();
+        let borrowed = owned.iter().map(OwnedEvent::as_event).collect::<Vec<_>>();
+        assert_eq!(borrowed, events);
+        drop(borrowed);
+        drop(events);
+        drop(input);
+
+        assert_eq!(
+            owned[2],
+            OwnedEvent::StateChange(OwnedStateChange::Color {
+                color: String::from("red"),
+                target: ColorTarget::Text,
+            })
+        );
+        assert_eq!(owned[3], OwnedEvent::Content(OwnedContent::Number(String::from("2.5"))));
+    }
+
+    #[test]
+    fn mathchoice() {
+        let choice = r"\mathchoice{d}{t}{s}{u}";
+        let input = format!(
+            r"{choice} \displaystyle {choice} x^{{{choice}_{choice}}} \frac{{{choice}}}{{1}} \textstyle \frac{{{choice}}}{{1}} \dfrac1{choice}"
+        );
+        let events = Parser::new(&input)
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        let choices = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Content(Content::Identifier(Identifier::Char(c))) if *c != 'x' => Some(*c),
+                _ => None,
+            })
+            .collect::<String>();
+
+        assert_eq!(choices, "tdsutst");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let events = Parser::new(
+            r"\left( \sum_{i=0}^\infty \frac{\color{red} x^i}{\sqrt[3]{i!}} \right) \text{if} \mathbf{y} \kern 1em",
+        )
+        .collect::<Result<Vec<_>, ParserError>>()
+        .unwrap();
+
+        let json = serde_json::to_string(&events).unwrap();
+        let deserialized: Vec<Event> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, events);
+
+        let owned = events
+            .iter()
+            .map(|event| event.into_owned())
+            .collect::<Vec<_>>();
+        let json = serde_json::to_string(&owned).unwrap();
+        let deserialized: Vec<OwnedEvent> = serde_json::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(deserialized, owned);
+    }
+
+    #[test]
+    fn known_primitives() {
+        for name in [
+            "frac", "sqrt", "alpha", "sum", "left", "mathbf", "color", "begin", "limits", ",",
+            "{", "varinjlim", "boxed",
+        ] {
+            assert!(is_known_primitive(name), "{name}");
+        }
+        for name in ["foo", "Frac", "textcolour", "", "matrix"] {
+            assert!(!is_known_primitive(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn limit_controls() {
+        let position = |input| match Parser::new(input).next() {
+            Some(Ok(Event::Script { position, .. })) => position,
+            event => panic!("expected a script, found {event:?}"),
+        };
+
+        assert_eq!(position(r"\int\limits_0^1"), ScriptPosition::AboveBelow);
+        assert_eq!(position(r"\sum\nolimits_i"), ScriptPosition::Right);
+        assert_eq!(position(r"\lim\nolimits_{x\to 0}"), ScriptPosition::Right);
+        assert_eq!(position(r"\sum\nolimits\limits_i"), ScriptPosition::AboveBelow);
+
+        let errors = Parser::new(r"x\limits_0")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap_err();
+        assert!(matches!(errors.error, ErrorKind::LimitControl));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_static_events() {
+        const JSON: &str = r#"[
+            {"Begin": "Normal"},
+            {"StateChange": {"Color": {"color": "blue", "target": "Text"}}},
+            {"Begin": "Internal"},
+            {"StateChange": {"Font": "DoubleStruck"}},
+            {"Content": {"Identifier": {"Char": "R"}}},
+            "End",
+            "End",
+            {"Content": {"Identifier": {"Str": "sin"}}},
+            {"Content": {"Number": "2"}}
+        ]"#;
+        let events: Vec<Event<'static>> = serde_json::from_str(JSON).unwrap();
+
+        assert_eq!(
+            events,
+            Parser::new(r"{\color{blue} \mathbb R} \sin 2")
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn symbol_lookup() {
+        let symbols = [
+            ("alpha", 'α', AtomClass::Ordinary),
+            ("aleph", 'ℵ', AtomClass::Ordinary),
+            ("cdots", '⋯', AtomClass::Inner),
+            ("cdotp", '·', AtomClass::Punctuation),
+            ("pm", '±', AtomClass::Binary),
+            ("leq", '≤', AtomClass::Relation),
+            ("rightarrow", '→', AtomClass::Relation),
+            ("forall", '∀', AtomClass::Ordinary),
+        ];
+        for (name, char, class) in symbols {
+            assert_eq!(primitive_to_char(name), Some((char, class)), "{name}");
+            let input = format!("\\{name}");
+            let events = Parser::new(&input)
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap();
+            let expected = if symbols::identifier(name).is_some() {
+                Event::Content(Content::Identifier(Identifier::Char(char)))
+            } else {
+                Event::Content(Content::Operator(Operator {
+                    content: char,
+                    ..Default::default()
+                }))
+            };
+            assert_eq!(events, vec![expected], "{name}");
+        }
+        for (name, char) in [("sum", '∑'), ("bigcup", '⋃'), ("int", '∫'), ("oint", '∮')] {
+            assert_eq!(
+                primitive_to_char(name),
+                Some((char, AtomClass::Operator)),
+                "{name}"
+            );
+        }
+        for name in ["frac", "dots", "smallsetminus", "foo", ""] {
+            assert_eq!(primitive_to_char(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn error_span() {
+        let cases = [
+            (r"\foo", r"\foo"),
+            (r"a^{b + \foo_c}", r"\foo"),
+            (r"x \sqrt[2]", r"\sqrt[2]"),
+            (r"\alpha^^", r"\alpha^^"),
+        ];
+        for (input, spanned) in cases {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            let span = error.span().expect("the error has a span");
+            assert_eq!(&input[span], spanned, "{input}");
+        }
+    }
+
+    #[test]
+    fn reverse_symbol_lookup() {
+        assert_eq!(char_to_primitive('α'), Some("alpha"));
+        assert_eq!(char_to_primitive('≤'), Some("leq"));
+        assert_eq!(char_to_primitive('→'), Some("rightarrow"));
+        assert_eq!(char_to_primitive('⋯'), Some("cdots"));
+        assert_eq!(char_to_primitive('x'), None);
+        assert_eq!(char_to_primitive('⟨'), Some("langle"));
+        assert_eq!(char_to_primitive('⌊'), Some("lfloor"));
+        let names = symbols::IDENTIFIERS
+            .iter()
+            .copied()
+            .chain(symbols::OPERATORS.iter().map(|&(name, (c, _))| (name, c)))
+            .chain(symbols::LARGE_OPERATORS.iter().map(|&(name, (c, _))| (name, c)))
+            .chain(tables::CONTROL_SEQUENCE_DELIMITERS.iter().copied());
+        for (name, c) in names {
+            assert_eq!(primitive_to_char(name).map(|(c, _)| c), Some(c), "{name}");
+            let canonical = char_to_primitive(c).unwrap();
+            assert_eq!(
+                primitive_to_char(canonical).map(|(c, _)| c),
+                Some(c),
+                "{canonical}"
+            );
+        }
+    }
+
+    #[test]
+    fn non_breaking_space() {
+        let parse = |nbsp_entity| {
+            let config = ParserConfig {
+                nbsp_entity,
+                ..Default::default()
+            };
+            Parser::with_config(r"a~b\ c\nobreakspace d", config)
+                .collect::<Result<Vec<_>, ParserError<'static>>>()
+                .unwrap()
+        };
+        for (nbsp_entity, space) in [(false, "\u{a0}"), (true, "&nbsp;")] {
+            assert_eq!(
+                parse(nbsp_entity),
+                vec![
+                    Event::Content(Content::Identifier(Identifier::Char('a'))),
+                    Event::Content(Content::Text(space)),
+                    Event::Content(Content::Identifier(Identifier::Char('b'))),
+                    Event::Content(Content::Text(space)),
+                    Event::Content(Content::Identifier(Identifier::Char('c'))),
+                    Event::Content(Content::Text(space)),
+                    Event::Content(Content::Identifier(Identifier::Char('d'))),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn error_position() {
+        let error = Parser::new("a +\nα + \\foo").find_map(Result::err).unwrap();
+        assert_eq!(error.line(), Some(2));
+        assert_eq!(error.column(), Some(9));
+        assert!(error
+            .to_string()
+            .starts_with("Error while parsing at 2:9: unknown primitive command found"));
+    }
+
+    #[test]
+    fn error_caret() {
+        let caret_line = |input| {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            let display = error.to_string();
+            let lines: Vec<_> = display.lines().collect();
+            let context = lines[1].chars().collect::<Vec<_>>();
+            let caret = lines[2].chars().count() - 1;
+            (lines[2].to_string(), context[caret])
+        };
+        let (ascii, under_ascii) = caret_line(r"a + b \foo");
+        let (unicode, under_unicode) = caret_line(r"α + β \foo");
+        assert_eq!(ascii, unicode);
+        assert_eq!(under_ascii, 'o');
+        assert_eq!(under_unicode, 'o');
+    }
+
+    #[test]
+    fn empty_groups() {
+        let events = Parser::new("a{}b")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::Begin(Grouping::Normal),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+            ]
+        );
+
+        let events = Parser::new("x^{}")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Begin(Grouping::Normal),
+                Event::End,
+            ]
+        );
+
+        let errors = Parser::new("x^").collect::<Result<Vec<_>, _>>();
+        assert!(errors.is_err());
+    }
+
+    #[test]
+    fn error_recovery() {
+        let plus = Event::Content(Content::Operator(Operator {
+            content: '+',
+            ..Default::default()
+        }));
+        // The scripts following an erroneous command are kept, and given an empty base.
+        let (events, errors) = Parser::new(r"a \foo^2 + \frac{b}").collect_errors();
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Begin(Grouping::Internal),
+                Event::End,
+                Event::Content(Content::Number("2")),
+                plus,
+            ]
+        );
+        let errors = errors.iter().map(|e| &e.error).collect::<Vec<_>>();
+        assert!(matches!(
+            errors[..],
+            [ErrorKind::UnknownPrimitive, ErrorKind::EndOfInput]
+        ));
+
+        let (events, errors) = Parser::new(r"a } b \foo").collect_errors();
+        assert_eq!(
+            events,
+            vec![Event::Content(Content::Identifier(Identifier::Char('a')))]
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].is_fatal());
+    }
+
+    #[test]
+    fn trailing_comment() {
+        let events = Parser::new("x % comment")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![Event::Content(Content::Identifier(Identifier::Char('x')))]
+        );
+    }
+
+    #[test]
+    fn sized_delimiter_classes() {
+        let events = Parser::new(r"\big( \Bigl( \biggr) \Biggm|")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        let delimiter = |content, size| {
+            Event::Content(Content::Operator(Operator {
+                content,
+                size: Some((size, DimensionUnit::Em)),
+                ..Default::default()
+            }))
+        };
+        assert_eq!(
+            events,
+            vec![
+                delimiter('(', 1.2),
+                Event::Begin(Grouping::AtomClass(AtomClass::Opening)),
+                delimiter('(', 1.8),
+                Event::End,
+                Event::Begin(Grouping::AtomClass(AtomClass::Closing)),
+                delimiter(')', 2.4),
+                Event::End,
+                Event::Begin(Grouping::AtomClass(AtomClass::Relation)),
+                delimiter('|', 3.),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn paired_delimiters() {
+        let events = Parser::new(
+            r"\DeclarePairedDelimiter\abs{\lvert}{\rvert} \abs{x} \abs*y \abs[\big]{z}",
+        )
+        .collect::<Result<Vec<_>, ParserError<'static>>>()
+        .unwrap();
+        let delimiter = |class, size: Option<f32>| {
+            [
+                Event::Begin(Grouping::AtomClass(class)),
+                Event::Content(Content::Operator(Operator {
+                    content: '|',
+                    stretchy: size.is_none().then_some(false),
+                    size: size.map(|size| (size, DimensionUnit::Em)),
+                    ..Default::default()
+                })),
+                Event::End,
+            ]
+        };
+        let fence = Event::Content(Content::Operator(Operator {
+            content: '|',
+            ..Default::default()
+        }));
+        let ident = |c| Event::Content(Content::Identifier(Identifier::Char(c)));
+
+        let mut expected = vec![Event::Begin(Grouping::Normal)];
+        expected.extend(delimiter(AtomClass::Opening, None));
+        expected.push(ident('x'));
+        expected.extend(delimiter(AtomClass::Closing, None));
+        expected.extend([
+            Event::End,
+            Event::Begin(Grouping::LeftRight),
+            fence,
+            ident('y'),
+            fence,
+            Event::End,
+            Event::Begin(Grouping::Normal),
+        ]);
+        expected.extend(delimiter(AtomClass::Opening, Some(1.2)));
+        expected.push(ident('z'));
+        expected.extend(delimiter(AtomClass::Closing, Some(1.2)));
+        expected.push(Event::End);
+        assert_eq!(events, expected);
+
+        let error = Parser::new(r"\DeclarePairedDelimiter\abs|| \abs[\large]{x}")
+            .find_map(Result::err)
+            .unwrap();
+        assert!(matches!(error.kind(), ErrorKind::DelimiterSize));
+        let error = Parser::new(r"\DeclarePairedDelimiter\abs|x")
+            .find_map(Result::err)
+            .unwrap();
+        assert!(matches!(error.kind(), ErrorKind::Delimiter));
+    }
+
+    #[test]
+    fn fence_classes() {
+        let events = Parser::new(r"\lvert x \rVert \vert")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        let fence = |content| {
+            Event::Content(Content::Operator(Operator {
+                content,
+                stretchy: Some(false),
+                ..Default::default()
+            }))
+        };
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::AtomClass(AtomClass::Opening)),
+                fence('|'),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::Begin(Grouping::AtomClass(AtomClass::Closing)),
+                fence('‖'),
+                Event::End,
+                fence('|'),
+            ]
+        );
+
+        let events = Parser::new(r"\left\lvert x \right\rVert")
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        assert!(matches!(
+            events[..],
+            [
+                Event::Begin(Grouping::LeftRight),
+                Event::Content(Content::Operator(Operator { content: '|', .. })),
+                _,
+                Event::Content(Content::Operator(Operator { content: '‖', .. })),
+                Event::End,
+            ]
+        ));
+    }
+
+    #[test]
+    fn tex_delimiters() {
+        let input = r"\left\lgroup x \right\rgroup \bigl\lmoustache \bigr\rmoustache \arrowvert \Arrowvert \bracevert";
+        let events = Parser::new(input)
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        let delimiter = |content, stretchy, size| {
+            Event::Content(Content::Operator(Operator {
+                content,
+                stretchy,
+                size,
+                ..Default::default()
+            }))
+        };
+        let big = Some((1.2, DimensionUnit::Em));
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::LeftRight),
+                delimiter('⟮', None, None),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                delimiter('⟯', None, None),
+                Event::End,
+                Event::Begin(Grouping::AtomClass(AtomClass::Opening)),
+                delimiter('⎰', None, big),
+                Event::End,
+                Event::Begin(Grouping::AtomClass(AtomClass::Closing)),
+                delimiter('⎱', None, big),
+                Event::End,
+                delimiter('⏐', Some(false), None),
+                delimiter('‖', Some(false), None),
+                delimiter('⎪', Some(false), None),
+            ]
+        );
+        assert_eq!(primitive_to_char("rmoustache"), Some(('⎱', AtomClass::Closing)));
+        assert_eq!(primitive_to_char("bracevert"), Some(('⎪', AtomClass::Ordinary)));
+    }
+
+    #[test]
+    fn stmaryrd_delimiters() {
+        let input = r"\left\llbracket \frac{a}{b} \right\rrbracket \llparenthesis x \rrparenthesis";
+        let events = Parser::new(input)
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+        let delimiter = |content, stretchy| {
+            Event::Content(Content::Operator(Operator {
+                content,
+                stretchy,
+                ..Default::default()
+            }))
+        };
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::LeftRight),
+                delimiter('⟦', None),
+                Event::Visual(Visual::Fraction(None)),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::End,
+                delimiter('⟧', None),
+                Event::End,
+                delimiter('⦇', Some(false)),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                delimiter('⦈', Some(false)),
+            ]
+        );
+        assert_eq!(primitive_to_char("llbracket"), Some(('⟦', AtomClass::Opening)));
+    }
+
+    #[test]
+    fn missing_scripts() {
+        for input in ["x_{}", "x^{}", "x_{}^{}", "{}^{}"] {
+            let events = Parser::new(input).collect::<Result<Vec<_>, ParserError>>();
+            assert!(events.is_ok(), "{input}");
+        }
+        for (input, subscript) in [
+            ("x^", false),
+            ("x_", true),
+            ("{x^}", false),
+            ("x_1^", false),
+            ("x_^2", true),
+            ("x^ % comment", false),
+        ] {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            if subscript {
+                assert!(matches!(error.error, ErrorKind::EmptySubscript), "{input}");
+            } else {
+                assert!(matches!(error.error, ErrorKind::EmptySuperscript), "{input}");
+            }
+        }
+        for input in [r"\hat^\bigr)", r"\frac_1 2", r"\sqrt^x"] {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            assert!(matches!(error.error, ErrorKind::Argument), "{input}");
+        }
+    }
+
+    #[test]
+    fn double_scripts() {
+        for (input, subscript, column) in [
+            ("x^a^b", false, 4),
+            ("x_a_b", true, 4),
+            ("x'^a^b", false, 5),
+            ("x_a^b^c", false, 6),
+            ("x^a_b_c", true, 6),
+            ("x^{a}  ^b", false, 8),
+            ("x_a ^b _c", true, 8),
+        ] {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            if subscript {
+                assert!(matches!(error.error, ErrorKind::DoubleSubscript), "{input}");
+            } else {
+                assert!(matches!(error.error, ErrorKind::DoubleSuperscript), "{input}");
+            }
+            assert_eq!(error.column(), Some(column), "{input}");
+        }
+        let events = Parser::new("x_a ^b + y").collect::<Result<Vec<_>, ParserError>>();
+        assert!(events.is_ok());
+    }
+
+    #[test]
+    fn html_annotations() {
+        let config = ParserConfig {
+            trust: true,
+            ..Default::default()
+        };
+        let events = Parser::with_config(r"\htmlId{eq1}{x} \htmlClass{a b}y^2", config)
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Annotation {
+                    kind: AnnotationKind::Id,
+                    value: "eq1"
+                },
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Annotation {
+                    kind: AnnotationKind::Class,
+                    value: "a b"
+                },
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+                Event::Content(Content::Number("2")),
+            ]
+        );
+
+        let error = Parser::new(r"\htmlStyle{color: red}{x}")
+            .find_map(Result::err)
+            .unwrap();
+        assert!(matches!(error.error, ErrorKind::Untrusted));
+        let error = Parser::with_config(r"\htmlId x", config)
+            .find_map(Result::err)
+            .unwrap();
+        assert!(matches!(error.error, ErrorKind::Argument));
+    }
+
+    #[test]
+    fn links() {
+        let events = Parser::new(r"\href{https://x}{y} \href{ #eq }z^2")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Link { url: "https://x" },
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+                Event::End,
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Link { url: "#eq" },
+                Event::Content(Content::Identifier(Identifier::Char('z'))),
+                Event::Content(Content::Number("2")),
+            ]
+        );
+
+        for url in ["javascript:alert(1)", " JavaScript:x", "java\tscript:x", "data:text/html,x"] {
+            let input = format!(r"\href{{{url}}}{{y}}");
+            let error = Parser::new(&input).find_map(Result::err).unwrap();
+            assert!(matches!(error.error, ErrorKind::UntrustedUrl), "{url}");
+
+            let config = ParserConfig {
+                trust: true,
+                ..Default::default()
+            };
+            let events = Parser::with_config(&input, config).collect::<Result<Vec<_>, _>>();
+            assert!(events.is_ok(), "{url}");
+        }
+        for url in ["mailto:a@b.c", "HTTPS://x", "a/b:c", "?q=a:b", "page.html"] {
+            let input = format!(r"\href{{{url}}}{{y}}");
+            let events = Parser::new(&input).collect::<Result<Vec<_>, _>>();
+            assert!(events.is_ok(), "{url}");
+        }
+        let error = Parser::new(r"\href x y").find_map(Result::err).unwrap();
+        assert!(matches!(error.error, ErrorKind::Argument));
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let nested = |depth| format!("{}x{}", "x_{".repeat(depth), "}".repeat(depth));
+        let input = nested(200);
+        let events = Parser::new(&input).collect::<Result<Vec<_>, ParserError>>();
+        assert!(events.is_ok());
+        let input = nested(1000);
+        let error = Parser::new(&input).find_map(Result::err).unwrap();
+        assert!(matches!(error.error, ErrorKind::NestingTooDeep));
+
+        let config = ParserConfig {
+            max_nesting_depth: 3,
+            ..Default::default()
+        };
+        let events = Parser::with_config("{{x}} y", config)
+            .collect::<Result<Vec<_>, ParserError>>();
+        assert!(events.is_ok());
+        let (events, errors) = Parser::with_config("{{{x}}} y", config).collect_errors();
+        assert!(matches!(errors[..], [ParserError { error: ErrorKind::NestingTooDeep, .. }]));
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Normal),
+                Event::Begin(Grouping::Normal),
+                Event::End,
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('y'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn let_assignments() {
+        let events = Parser::new(r"\let\foo=\alpha \let\bar\foo \let\alpha=x \foo \bar^2 \alpha")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Identifier(Identifier::Char('α'))),
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('α'))),
+                Event::Content(Content::Number("2")),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+            ]
+        );
+
+        let events = Parser::new(r"\let\bgroup={ \let\one 1 \bgroup\one2}")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Number("1")),
+                Event::Content(Content::Number("2")),
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn futurelet() {
+        let events = Parser::new(r"\futurelet\next\alpha\beta \next")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Content(Content::Identifier(Identifier::Char('α'))),
+                Event::Content(Content::Identifier(Identifier::Char('β'))),
+                Event::Content(Content::Identifier(Identifier::Char('β'))),
+            ]
+        );
+        let error = Parser::new(r"\futurelet\next x").find_map(Result::err).unwrap();
+        assert!(matches!(error.error, ErrorKind::EndOfInput));
+    }
+
+    #[test]
+    fn macro_definitions() {
+        let char_events = |chars: &str| {
+            chars
+                .chars()
+                .map(|c| Event::Content(Content::Identifier(Identifier::Char(c))))
+                .collect::<Vec<_>>()
+        };
+        let storage = Storage::new();
+        for (input, output) in [
+            (r"\edef\a{x}\def\x{\a}\x", "x"),
+            (r"\def\a{x}\edef\b{\a}\def\a{y}\b\a", "xy"),
+            (r"\def\a{\b}\def\b{y}\edef\c{\a}\def\b{z}\c", "y"),
+            (r"\def\f#1{#1#1}\f a\f{bc}", "aabcbc"),
+            (r"\def\swap#1#2{#2#1}\swap ab", "ba"),
+            (r"\def\p(#1,#2){#2#1}\p(a,{bc})", "bca"),
+            (r"\def\a{x}\let\b\a \def\a{y}\b\a", "xy"),
+            (r"\def\x{a}\csname x\endcsname", "a"),
+        ] {
+            let events = Parser::with_storage(input, ParserConfig::default(), &storage)
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap_or_else(|error| panic!("{input}: {error}"));
+            assert_eq!(events, char_events(output), "{input}");
+        }
+
+        // Expansions which are contiguous in the input do not need a storage.
+        let events = Parser::new(r"\def\f#1#2{#1#2}\f ab")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(events, char_events("ab"));
+
+        let error = Parser::new(r"\def\f#1#2{#2#1}\f ab")
+            .find_map(Result::err)
+            .unwrap();
+        assert!(matches!(error.error, ErrorKind::MacroStorage));
+        let error = Parser::new(r"\def\f#2{#2}").find_map(Result::err).unwrap();
+        assert!(matches!(error.error, ErrorKind::MacroParameters));
+        let error = Parser::new(r"\def\f(#1){#1}\f a").find_map(Result::err).unwrap();
+        assert!(matches!(error.error, ErrorKind::MacroArguments));
+        let error = Parser::new(r"\def\a{\a}\a").find_map(Result::err).unwrap();
+        assert!(matches!(error.error, ErrorKind::NestingTooDeep));
+        let error = Parser::new(r"\def\a{x\a}\edef\b{\a}").find_map(Result::err).unwrap();
+        assert!(matches!(error.error, ErrorKind::NestingTooDeep));
+
+        // The expansion of a macro can contain its own infix command.
+        for input in [r"\def\b{a\atop b}\b", r"\edef\b{a\atop b}\b"] {
+            let events = Parser::new(input)
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap_or_else(|error| panic!("{input}: {error}"));
+            assert_eq!(events[0], Event::Visual(Visual::Fraction(Some((0., DimensionUnit::Em)))));
+        }
+    }
+
+    #[test]
+    fn expand_to_string() {
+        assert_eq!(
+            Parser::expand_to_string(r"\def\pair#1#2{(#1, #2)} \pair{a}{\beta}").unwrap(),
+            r"(a,\beta)"
+        );
+        let Err(PrintError::Event(error)) = Parser::expand_to_string(r"\def\f#1{#1} \f") else {
+            panic!("expected a parser error");
+        };
+        assert!(matches!(error.kind(), ErrorKind::MacroArguments));
+        assert_eq!(error.span(), Some(13..15));
+        assert!(matches!(
+            Parser::expand_to_string(r"\hat\pmod{p}"),
+            Err(PrintError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn prescripts() {
+        let events = Parser::new(r"{}^{12}_6\text{C} + {}^a")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::SubSuperscript,
+                    position: ScriptPosition::Prescript
+                },
+                Event::Content(Content::Text("C")),
+                Event::Content(Content::Number("6")),
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Number("12")),
+                Event::End,
+                Event::Content(Content::Operator(Operator {
+                    content: '+',
+                    ..Default::default()
+                })),
+                // Without a base, the scripts are attached to the empty group.
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Begin(Grouping::Normal),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn tensor() {
+        let events = Parser::new(r"T^a{}_b{}^c")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Tensor
+                },
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Tensor
+                },
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('T'))),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+                Event::Content(Content::Identifier(Identifier::Char('c'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn tensor_command() {
+        let events = |input| {
+            Parser::new(input)
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap()
+        };
+        assert_eq!(events(r"\tensor T{^a _b^{c}}"), events(r"T^a{}_b{}^{c}"));
+        assert_eq!(events(r"\tensor{R}{_i}"), events(r"{R}_i"));
+        assert_eq!(events(r"\tensor x{}"), events("x"));
+        for input in [r"\tensor T{a}", r"\tensor T{^a_}", r"\tensor T{^_a}", r"\tensor Ta"] {
+            assert!(Parser::new(input).any(|event| event.is_err()), "{input}");
+        }
+    }
+
+    #[test]
+    fn prescript_command() {
+        let events = Parser::new(r"\prescript{14}{6}{\text{C}} \prescript{}{a}{x}^b")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::SubSuperscript,
+                    position: ScriptPosition::Prescript
+                },
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Text("C")),
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Number("6")),
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Number("14")),
+                Event::End,
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Script {
+                    ty: ScriptType::Subscript,
+                    position: ScriptPosition::Prescript
+                },
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+                Event::End,
+                Event::Begin(Grouping::Normal),
+                Event::Content(Content::Identifier(Identifier::Char('a'))),
+                Event::End,
+                Event::Content(Content::Identifier(Identifier::Char('b'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        let char_events = |chars: &str| {
+            chars
+                .chars()
+                .map(|c| Event::Content(Content::Identifier(Identifier::Char(c))))
+                .collect::<Vec<_>>()
+        };
+        for (input, output) in [
+            (r"\ifnum 1<2 a\else b\fi c", "ac"),
+            (r"\ifnum -3 > `a x\fi y", "y"),
+            (r"\ifdim 1em=10pt a\else b\fi", "a"),
+            (r"\ifdim 1pt>1in a\else b\fi", "b"),
+            (r"\ifdim 1in=72.27pt a\else b\fi", "a"),
+            (r"\ifdim 1pc<12.1pt a\else b\fi", "a"),
+            (r"\def\a{x}\def\b{x}\ifx\a\b a\else b\fi", "a"),
+            (r"\def\a{x}\edef\b{\a}\ifx\a\b a\else b\fi", "a"),
+            (r"\def\a{x}\let\b\a \ifx\a\b a\else b\fi", "a"),
+            (r"\def\a#1{x}\def\b{x}\ifx\a\b a\else b\fi", "b"),
+            (r"\def\a{x}\ifx\a x a\else b\fi", "b"),
+            (r"\let\foo=\alpha \ifx\foo\alpha a\fi \ifx\foo\beta b\else c\fi", "ac"),
+            (r"\if aa x\fi \if ab y\fi \ifx\alpha\beta z\fi", "x"),
+            (r"\iftrue \ifnum1=2 a\else b\fi \else c\fi d", "bd"),
+            (r"\iffalse \ifnum1=1 a\fi \fi d", "d"),
+        ] {
+            let events = Parser::new(input)
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap_or_else(|error| panic!("{input}: {error}"));
+            assert_eq!(events, char_events(output), "{input}");
+        }
+
+        let events = Parser::new(r"\begin{matrix} \iftrue a & b \fi \end{matrix}")
+            .collect::<Result<Vec<_>, ParserError>>()
+            .unwrap();
+        assert!(events.contains(&Event::Alignment));
+
+        for (input, error) in [
+            (r"a \fi", ErrorKind::ConditionalEnd),
+            (r"\iftrue a \else b \else c \fi", ErrorKind::ConditionalEnd),
+            (r"\iftrue a", ErrorKind::UnterminatedConditional),
+            (r"\ifnum 1 ! 2 a\fi", ErrorKind::Relation),
+        ] {
+            let found = Parser::new(input).find_map(Result::err).unwrap();
+            assert_eq!(
+                std::mem::discriminant(found.kind()),
+                std::mem::discriminant(&error),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn csname() {
+        let events =
+            Parser::new(r"\csname alpha\endcsname^2 \csname foo\endcsname \ifx\foo\relax x\fi")
+                .collect::<Result<Vec<_>, ParserError>>()
+                .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Script {
+                    ty: ScriptType::Superscript,
+                    position: ScriptPosition::Right
+                },
+                Event::Content(Content::Identifier(Identifier::Char('α'))),
+                Event::Content(Content::Number("2")),
+                Event::Content(Content::Identifier(Identifier::Char('x'))),
+            ]
+        );
+
+        for input in [r"\csname a\alpha\endcsname", r"\csname a", r"a \endcsname"] {
+            let error = Parser::new(input).find_map(Result::err).unwrap();
+            assert!(
+                matches!(error.kind(), ErrorKind::ControlSequenceName),
+                "{input}"
+            );
+        }
+    }
+
+    // For mir
+    #[test]
+    fn multidigit_number() {
+        let parser = Parser::new("123");
+        let events = parser
+            .collect::<Result<Vec<_>, ParserError<'static>>>()
+            .unwrap();
+
+        assert_eq!(events, vec![Event::Content(Content::Number("123"))]);
+    }
+}
@@ -1,7 +1,13 @@
-use crate::attribute::{Dimension, DimensionUnit, Glue};
+use std::{cmp::Ordering, sync::OnceLock};
+
+use crate::{
+    attribute::{Dimension, DimensionUnit, Glue},
+    event::{ArrayColumn, ArrayColumns, ColumnAlignment},
+};
 
 use super::{
-    tables::token_to_delim, Argument, CharToken, ErrorKind, InnerResult, Token,
+    tables::{precomposed, text_accent, token_to_delim},
+    Argument, CharToken, ErrorKind, InnerResult, TextBreak, Token,
 };
 
 /// Parse the right-hand side of a definition (TeXBook p. 271).
@@ -19,6 +25,42 @@ pub fn definition<'a>(input: &mut &'a str) -> InnerResult<(&'a str, &'a str, &'a
     Ok((control_sequence, parameter_text, replacement_text))
 }
 
+/// Parse the arguments of `\DeclarePairedDelimiter`, i.e., the declared control sequence and its
+/// left and right delimiters.
+pub fn paired_delimiter_declaration<'a>(
+    input: &mut &'a str,
+) -> InnerResult<(&'a str, char, char)> {
+    let name = match argument(input)? {
+        Argument::Token(Token::ControlSequence(name)) => name,
+        Argument::Group(mut group) => {
+            let name = control_sequence(&mut group)?;
+            if !group.trim().is_empty() {
+                return Err(ErrorKind::ControlSequence);
+            }
+            name
+        }
+        Argument::Token(Token::Character(_)) => return Err(ErrorKind::ControlSequence),
+    };
+    let left = delimiter_argument(input)?;
+    let right = delimiter_argument(input)?;
+    Ok((name, left, right))
+}
+
+/// Parse an argument made of a single delimiter, e.g., `|` or `{\langle}`.
+fn delimiter_argument(input: &mut &str) -> InnerResult<char> {
+    match argument(input)? {
+        Argument::Token(token) => token_to_delim(token).ok_or(ErrorKind::Delimiter),
+        Argument::Group(mut group) => {
+            let delimiter = delimiter(&mut group)?;
+            if group.trim().is_empty() {
+                Ok(delimiter)
+            } else {
+                Err(ErrorKind::Delimiter)
+            }
+        }
+    }
+}
+
 /// Parse an argument to a control sequence, and return it.
 pub fn argument<'a>(input: &mut &'a str) -> InnerResult<Argument<'a>> {
     if let Some(rest) = input.trim_start().strip_prefix('{') {
@@ -93,16 +135,202 @@ pub fn delimiter(input: &mut &str) -> InnerResult<char> {
     token_to_delim(maybe_delim).ok_or(ErrorKind::Delimiter)
 }
 
+/// Parse the branches of a conditional, up to and including its matching `\fi`, once its
+/// condition is parsed (TeXBook p. 207).
+///
+/// Returns the branch preceding `\else`, and the branch following it if there is one.
+pub fn conditional_branches<'a>(input: &mut &'a str) -> InnerResult<(&'a str, Option<&'a str>)> {
+    let content = *input;
+    let offset = |rest: &str| content.len() - rest.len();
+    let mut depth = 0usize;
+    let mut else_position = None;
+    loop {
+        let token_start = offset(input.trim_start());
+        let control_sequence = match token(input) {
+            Ok(Token::ControlSequence(cs)) => cs,
+            Ok(Token::Character(_)) => continue,
+            Err(ErrorKind::EndOfInput) => return Err(ErrorKind::UnterminatedConditional),
+            Err(err) => return Err(err),
+        };
+        match control_sequence {
+            "if" | "ifx" | "ifnum" | "ifdim" | "iftrue" | "iffalse" => depth += 1,
+            "fi" if depth > 0 => depth -= 1,
+            "else" if depth == 0 => {
+                if else_position.is_some() {
+                    return Err(ErrorKind::ConditionalEnd);
+                }
+                else_position = Some((token_start, offset(input)));
+            }
+            "fi" => {
+                return Ok(match else_position {
+                    Some((else_start, else_end)) => (
+                        &content[..else_start],
+                        Some(&content[else_end..token_start]),
+                    ),
+                    None => (&content[..token_start], None),
+                });
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Parse the content of a text mode argument up to the end of its first inline formula
+/// (`$...$` or `\(...\)`), or up to the first control sequence for which `is_command` holds.
+///
+/// Returns the text preceding them, and what ended the text if it is not the end of the input.
+/// Escaped characters (e.g., `\$`) and comments are skipped.
+///
+/// If the delimiters of an inline formula are unbalanced, the input is left at the delimiter
+/// which is not matched.
+pub fn text_span<'a>(
+    input: &mut &'a str,
+    is_command: impl Fn(&str) -> bool,
+) -> InnerResult<(&'a str, Option<TextBreak<'a>>)> {
+    let content = *input;
+    let mut rest = content;
+    while let Some(c) = rest.chars().next() {
+        let text = &content[..content.len() - rest.len()];
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '$' => {
+                let Some(end) = find_math_end(rest, "$") else {
+                    *input = &content[text.len()..];
+                    return Err(ErrorKind::UnbalancedMathShift);
+                };
+                *input = &rest[end + 1..];
+                return Ok((text, Some(TextBreak::Math(&rest[..end]))));
+            }
+            '\\' => {
+                if let Some(math) = rest.strip_prefix('(') {
+                    let Some(end) = find_math_end(math, r"\)") else {
+                        *input = &content[text.len()..];
+                        return Err(ErrorKind::UnbalancedMathShift);
+                    };
+                    *input = &math[end + 2..];
+                    return Ok((text, Some(TextBreak::Math(&math[..end]))));
+                }
+                if rest.starts_with(')') {
+                    *input = &content[text.len()..];
+                    return Err(ErrorKind::UnbalancedMathShift);
+                }
+                let len = rest
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    // A control symbol, e.g., `\$`.
+                    rest = &rest[rest.chars().next().map_or(0, char::len_utf8)..];
+                    continue;
+                }
+                let name;
+                (name, rest) = rest.split_at(len);
+                if is_command(name) {
+                    *input = rest;
+                    return Ok((text, Some(TextBreak::ControlSequence(name))));
+                }
+            }
+            '%' => {
+                rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+            }
+            _ => (),
+        }
+    }
+    *input = "";
+    Ok((content, None))
+}
+
+/// Find the position of the delimiter (`$` or `\)`) ending an inline formula, which is neither
+/// escaped, in a comment, nor in a group of the formula.
+///
+/// Skipping groups allows the formula to contain text arguments with their own inline formulas,
+/// e.g., `$a \text{ if $b$}$`.
+fn find_math_end(input: &str, delimiter: &str) -> Option<usize> {
+    let mut chars = input.char_indices();
+    let mut depth = 0usize;
+    while let Some((position, c)) = chars.next() {
+        if depth == 0 && input[position..].starts_with(delimiter) {
+            return Some(position);
+        }
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '\\' => {
+                chars.next();
+            }
+            '%' => {
+                chars.by_ref().find(|&(_, c)| c == '\n');
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Parse the characters forming a control sequence name, up to and including the `\endcsname`
+/// ending it, once the `\csname` is parsed (TeXBook p. 213).
+///
+/// The name cannot contain control sequences, since their expansion is not supported.
+pub fn control_sequence_name<'a>(input: &mut &'a str) -> InnerResult<&'a str> {
+    *input = input.trim_start();
+    let content = *input;
+    loop {
+        let name = &content[..content.len() - input.len()];
+        match token(input) {
+            Ok(Token::ControlSequence("endcsname")) => return Ok(name),
+            Ok(Token::Character(_)) => (),
+            Ok(Token::ControlSequence(_)) | Err(ErrorKind::EndOfInput) => {
+                return Err(ErrorKind::ControlSequenceName);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Parse the column specification of an `array` environment (e.g., `l|cr`).
+pub fn array_columns(spec: &str) -> InnerResult<ArrayColumns> {
+    let columns = spec
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            'l' => Ok(ArrayColumn::Column(ColumnAlignment::Left)),
+            'c' => Ok(ArrayColumn::Column(ColumnAlignment::Center)),
+            'r' => Ok(ArrayColumn::Column(ColumnAlignment::Right)),
+            '|' => Ok(ArrayColumn::VerticalLine),
+            _ => Err(ErrorKind::ArrayColumns),
+        })
+        .collect::<InnerResult<Vec<_>>>()?;
+    ArrayColumns::new(&columns).ok_or(ErrorKind::ArrayColumns)
+}
+
+/// Parse a relation used in the condition of `\ifnum` and `\ifdim` (TeXBook p. 209).
+///
+/// Returns the ordering of the left-hand side with respect to the right-hand side for which the
+/// condition holds.
+pub fn relation(input: &mut &str) -> InnerResult<Ordering> {
+    *input = input.trim_start();
+    let ordering = match input.chars().next() {
+        Some('<') => Ordering::Less,
+        Some('=') => Ordering::Equal,
+        Some('>') => Ordering::Greater,
+        Some(_) => return Err(ErrorKind::Relation),
+        None => return Err(ErrorKind::EndOfInput),
+    };
+    *input = &input[1..];
+    Ok(ordering)
+}
+
 /// Parse the right-hand side of a `futurelet` assignment (TeXBook p. 273).
 ///
-/// Returns the control sequence and both following tokens.
+/// Returns the control sequence and both following tokens. Only the control sequence is
+/// consumed, since both tokens are parsed after the assignment.
 pub fn futurelet_assignment<'a>(
     input: &mut &'a str,
 ) -> InnerResult<(&'a str, Token<'a>, Token<'a>)> {
     let control_sequence = control_sequence(input)?;
 
-    let token1 = token(input)?;
-    let token2 = token(input)?;
+    let mut rest = *input;
+    let token1 = token(&mut rest)?;
+    let token2 = peek_token(rest)?;
     Ok((control_sequence, token1, token2))
 }
 
@@ -152,6 +380,163 @@ pub fn limit_modifiers(input: &mut &str) -> Option<bool> {
     output
 }
 
+/// The infix commands producing a fraction from the content surrounding them.
+const INFIX_COMMANDS: [&str; 7] = [
+    "over",
+    "atop",
+    "above",
+    "choose",
+    "overwithdelims",
+    "atopwithdelims",
+    "abovewithdelims",
+];
+
+/// Whether one of the control words of the content is an infix command.
+///
+/// This is a cheap check done before tokenizing the content, where `\overline` is not `\over`.
+pub fn contains_infix(content: &str) -> bool {
+    let mut rest = content;
+    while let Some(index) = rest.find('\\') {
+        rest = &rest[index + 1..];
+        let name_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        if INFIX_COMMANDS.contains(&&rest[..name_len]) {
+            return true;
+        }
+        // A control symbol, such as `\\`, is skipped along with its character.
+        let len = match name_len {
+            0 => rest.chars().next().map_or(0, char::len_utf8),
+            len => len,
+        };
+        rest = &rest[len..];
+    }
+    false
+}
+
+/// Find the first infix command (e.g., `\over`) at the top level of the given group content.
+///
+/// Returns the content before the command, the name of the command, and the content after it.
+pub fn infix_fraction(content: &str) -> Option<(&str, &str, &str)> {
+    if !contains_infix(content) {
+        return None;
+    }
+
+    let mut rest = content;
+    let mut depth = 0usize;
+    loop {
+        let before = rest.trim_start();
+        match token(&mut rest).ok()? {
+            Token::Character(c) => match char::from(c) {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                _ => (),
+            },
+            Token::ControlSequence("left" | "begin") => depth += 1,
+            Token::ControlSequence("right" | "end") => depth = depth.saturating_sub(1),
+            Token::ControlSequence(cs) if depth == 0 && INFIX_COMMANDS.contains(&cs) => {
+                let numerator = &content[..content.len() - before.len()];
+                return Some((numerator, cs, rest));
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Return the content of the environment cell at the start of the input, which ends at the first
+/// `&`, `\\`, or `\cr` at the top level.
+pub fn cell(content: &str) -> &str {
+    let mut rest = content;
+    let mut depth = 0usize;
+    loop {
+        let before = rest;
+        match token(&mut rest) {
+            Ok(Token::Character(c)) => match char::from(c) {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                '&' if depth == 0 => return &content[..content.len() - before.len()],
+                _ => (),
+            },
+            Ok(Token::ControlSequence("left" | "begin")) => depth += 1,
+            Ok(Token::ControlSequence("right" | "end")) => depth = depth.saturating_sub(1),
+            Ok(Token::ControlSequence("\\" | "cr")) if depth == 0 => {
+                return &content[..content.len() - before.len()]
+            }
+            Ok(_) => (),
+            Err(_) => return content,
+        }
+    }
+}
+
+/// Parse the arguments following an infix command returned by [`infix_fraction`].
+///
+/// Returns the left and right delimiters surrounding the fraction, and the size of the fraction
+/// bar if it is not the default.
+pub fn infix_arguments(
+    infix: &str,
+    input: &mut &str,
+) -> InnerResult<(Option<char>, Option<char>, Option<Dimension>)> {
+    let no_bar = Some((0., DimensionUnit::Em));
+    let (left, right) = match infix {
+        "choose" => return Ok((Some('('), Some(')'), no_bar)),
+        "overwithdelims" | "atopwithdelims" | "abovewithdelims" => {
+            (null_delimiter(input)?, null_delimiter(input)?)
+        }
+        _ => (None, None),
+    };
+    let bar_size = match infix {
+        "above" | "abovewithdelims" => {
+            Some(dimension(input).map_err(|_| ErrorKind::DimensionArgument)?)
+        }
+        "atop" | "atopwithdelims" => no_bar,
+        _ => None,
+    };
+    Ok((left, right, bar_size))
+}
+
+/// Parse a delimiter, where `.` stands for the absence of a delimiter.
+fn null_delimiter(input: &mut &str) -> InnerResult<Option<char>> {
+    if let Some(rest) = input.trim_start().strip_prefix('.') {
+        *input = rest;
+        Ok(None)
+    } else {
+        delimiter(input).map(Some)
+    }
+}
+
+/// Skip over a token along with its limit modifiers and suffixes.
+///
+/// This is used by `\adjustlimits`, where the operators and their limits are parsed as a group.
+///
+/// Operators built from an argument, such as `\operatorname{lim}`, are skipped along with it.
+pub fn limited_operator(input: &mut &str) -> InnerResult<()> {
+    if let Token::ControlSequence("operatorname" | "mathop") = token(input)? {
+        argument(input)?;
+    }
+    limit_modifiers(input);
+    scripts(input)
+}
+
+/// Skip over the subscript and superscript at the start of the input, if any.
+pub fn scripts(input: &mut &str) -> InnerResult<()> {
+    for _ in 0..2 {
+        let Some(rest) = input.trim_start().strip_prefix(['^', '_']) else {
+            break;
+        };
+        *input = rest;
+        argument(input)?;
+    }
+    Ok(())
+}
+
+/// Parse the primes at the start of the input, returning how many there are.
+pub fn primes(input: &mut &str) -> usize {
+    let rest = input.trim_start_matches('\'');
+    let count = input.len() - rest.len();
+    *input = rest;
+    count
+}
+
 /// Parse the right side of a control sequence (`\` already being parsed).
 ///
 /// A control sequence can be of the form `\controlsequence`, or `\#` (control symbol).
@@ -306,7 +691,7 @@ pub fn floating_point(input: &mut &str) -> InnerResult<f32> {
         }
     });
 
-    if let Some(stripped_decimal_point) = input.strip_prefix(|c| c == '.' || c == ',') {
+    if let Some(stripped_decimal_point) = input.strip_prefix(['.', ',']) {
         let mut decimal = 0.;
         let mut decimal_divisor = 1.;
         *input = stripped_decimal_point.trim_start_matches(|c: char| {
@@ -378,26 +763,281 @@ pub fn token<'a>(input: &mut &'a str) -> InnerResult<Token<'a>> {
             Ok(Token::ControlSequence(rhs_control_sequence(input)?))
         }
         Some('%') => {
-            let (_, rest) = input.split_once('\n').ok_or(ErrorKind::EndOfInput)?;
-            *input = rest;
+            // A comment without a line break extends to the end of the input. The end of the input
+            // is kept, as errors are located using pointers into the input.
+            *input = match input.split_once('\n') {
+                Some((_, rest)) => rest,
+                None => &input[input.len()..],
+            };
             token(input)
         }
+        Some('^') if caret_notation(input).is_some() => {
+            let (c, rest) = caret_notation(input).expect("the input starts with `^^` notation");
+            *input = rest;
+            // The character is read as if it were in the input, so it can start a control
+            // sequence or a comment, or be skipped as a blank.
+            match c {
+                '\\' => Ok(Token::ControlSequence(rhs_control_sequence(input)?)),
+                '%' => {
+                    *input = match input.split_once('\n') {
+                        Some((_, rest)) => rest,
+                        None => &input[input.len()..],
+                    };
+                    token(input)
+                }
+                '\0' | '\t' | '\n' | '\r' | ' ' => token(input),
+                c => Ok(Token::Character(CharToken::from_str(latin_1_char(c)))),
+            }
+        }
         Some(c) => {
             let context = *input;
-            *input = &input.split_at(c.len_utf8()).1;
+            *input = input.split_at(c.len_utf8()).1;
             Ok(Token::Character(CharToken::from_str(context)))
         }
         None => Err(ErrorKind::EndOfInput),
     }
 }
 
+/// Decode the character written in `^^` notation at the start of the input (TeXbook p. 45), and
+/// return it along with the rest of the input.
+///
+/// `^^` followed by two lowercase hexadecimal digits stands for the character with that code
+/// (e.g., `^^41` is `A`), and `^^` followed by any other ASCII character stands for the character
+/// whose code differs from it by 64 (e.g., `^^I` is a tab, and `^^@` is a null character).
+///
+/// Returns `None` if the input does not start with `^^` followed by an ASCII character, in which
+/// case the carets stand for themselves.
+pub fn caret_notation(input: &str) -> Option<(char, &str)> {
+    let rest = input.strip_prefix("^^")?;
+    let is_hex_digit = |b: &u8| matches!(b, b'0'..=b'9' | b'a'..=b'f');
+    if rest.len() >= 2 && rest.as_bytes()[..2].iter().all(is_hex_digit) {
+        let code = u8::from_str_radix(&rest[..2], 16).expect("the digits are hexadecimal");
+        return Some((char::from(code), &rest[2..]));
+    }
+    let code = *rest.as_bytes().first().filter(|b| b.is_ascii())?;
+    let code = if code < 64 { code + 64 } else { code - 64 };
+    Some((char::from(code), &rest[1..]))
+}
+
+/// The given character, of code 0 to 255, as a string which outlives the input.
+///
+/// Characters written in `^^` notation do not appear as is in the input, so their tokens borrow
+/// these strings instead.
+fn latin_1_char(c: char) -> &'static str {
+    static CHARS: OnceLock<String> = OnceLock::new();
+    let chars = CHARS.get_or_init(|| (0..=u8::MAX).map(char::from).collect());
+    let start = chars
+        .char_indices()
+        .nth(c as usize)
+        .map(|(start, _)| start)
+        .expect("the character has a code below 256");
+    &chars[start..start + c.len_utf8()]
+}
+
+/// Return the next token in the input, without consuming it.
+pub fn peek_token(mut input: &str) -> InnerResult<Token<'_>> {
+    token(&mut input)
+}
+
+/// Split the content of a text mode argument (e.g., of `\text`) into the pieces of the text it
+/// stands for, following the rules of TeX's input processor (TeXBook p. 46-47).
+///
+/// - Comments are removed, along with the end of their line.
+/// - Runs of spaces and line breaks are collapsed into a single space. A blank line produces a
+///   `\par` token, which does nothing in restricted horizontal mode, so it only separates words.
+/// - `~` is a non-breaking space, and `\ ` is a space.
+/// - Escaped special characters (e.g., `\%`) stand for themselves, and braces are removed.
+/// - Accents (e.g., `\'e` or `\c{c}`) are composed with their argument, using the precomposed
+///   character if there is one (e.g., `é`).
+///
+/// Other control sequences are kept as is.
+///
+/// The pieces are either part of the content, or static strings (e.g., the non-breaking space of
+/// `~`), and the pieces following each other in the content are joined.
+pub fn text_pieces(content: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = content;
+    // Whether blanks are skipped, as in the "new line" and "skipping blanks" states of TeX.
+    let mut skip_blanks = false;
+    while let Some(c) = rest.chars().next() {
+        let current;
+        (current, rest) = rest.split_at(c.len_utf8());
+        match c {
+            '%' => {
+                rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+                skip_blanks = true;
+            }
+            c if c.is_ascii_whitespace() => {
+                if !skip_blanks {
+                    push_piece(content, &mut pieces, if c == ' ' { current } else { " " });
+                    skip_blanks = true;
+                }
+            }
+            '~' => {
+                push_piece(content, &mut pieces, "\u{a0}");
+                skip_blanks = false;
+            }
+            '{' | '}' => skip_blanks = false,
+            '\\' => {
+                skip_blanks = false;
+                match rest.chars().next() {
+                    Some(c @ (' ' | '#' | '$' | '%' | '&' | '_' | '{' | '}')) => {
+                        push_piece(content, &mut pieces, &rest[..1]);
+                        rest = &rest[1..];
+                        skip_blanks = c == ' ';
+                    }
+                    _ => match text_accent_command(rest) {
+                        Some((accent, after)) => {
+                            let argument;
+                            (argument, rest) = accent_argument(after);
+                            push_accented(content, &mut pieces, argument, accent);
+                        }
+                        None => push_piece(content, &mut pieces, current),
+                    },
+                }
+            }
+            _ => {
+                push_piece(content, &mut pieces, current);
+                skip_blanks = false;
+            }
+        }
+    }
+    pieces
+}
+
+/// Push the piece of text, joining it with the previous piece if both follow each other in the
+/// content.
+fn push_piece<'a>(content: &'a str, pieces: &mut Vec<&'a str>, piece: &'a str) {
+    if piece.is_empty() {
+        return;
+    }
+    let offset = |piece: &str| {
+        (piece.as_ptr() as usize)
+            .checked_sub(content.as_ptr() as usize)
+            .filter(|offset| offset + piece.len() <= content.len())
+    };
+    if let Some(last) = pieces.last_mut() {
+        if let (Some(start), Some(end)) = (offset(last), offset(piece)) {
+            if start + last.len() == end {
+                *last = &content[start..end + piece.len()];
+                return;
+            }
+        }
+    }
+    pieces.push(piece);
+}
+
+/// Parse the name of a text mode accent command (without the leading `\`), returning its combining
+/// character and the input following it.
+fn text_accent_command(input: &str) -> Option<(&'static str, &str)> {
+    let len = match input.find(|c: char| !c.is_ascii_alphabetic()) {
+        Some(0) => input.chars().next()?.len_utf8(),
+        Some(len) => len,
+        None => input.len(),
+    };
+    let (name, rest) = input.split_at(len);
+    text_accent(name).map(|accent| (accent, rest))
+}
+
+/// Parse the argument of a text mode accent, which is either a group, a control word (e.g., `\i`),
+/// or a single character, returning the argument and the input following it.
+fn accent_argument(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    let len = if let Some(rest) = input.strip_prefix('{') {
+        let mut depth = 0usize;
+        let end = rest.find(|c| {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => return true,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            false
+        });
+        return match end {
+            Some(end) => (&rest[..end], &rest[end + 1..]),
+            None => (rest, ""),
+        };
+    } else if let Some(rest) = input.strip_prefix('\\') {
+        1 + rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len())
+    } else {
+        input.chars().next().map_or(0, char::len_utf8)
+    };
+    input.split_at(len)
+}
+
+/// Push the pieces of the text of the argument of an accent, with the accent composed with its
+/// first character.
+///
+/// The dotless letters `\i` and `\j` are accented as `i` and `j`, and an empty argument leaves the
+/// accent on a non-breaking space.
+fn push_accented<'a>(
+    content: &'a str,
+    pieces: &mut Vec<&'a str>,
+    argument: &'a str,
+    accent: &'static str,
+) {
+    let argument_pieces = match argument.trim() {
+        r"\i" => vec!["i"],
+        r"\j" => vec!["j"],
+        argument => text_pieces(argument),
+    };
+    let mut argument_pieces = argument_pieces.into_iter();
+    let first = argument_pieces.next().unwrap_or("\u{a0}");
+    let letter = first.chars().next().expect("the pieces are not empty");
+    let (letter_piece, rest) = first.split_at(letter.len_utf8());
+    let accent_char = accent.chars().next().expect("the accent is a character");
+    match precomposed(letter, accent_char) {
+        Some(accented) => push_piece(content, pieces, accented),
+        None => {
+            push_piece(content, pieces, letter_piece);
+            push_piece(content, pieces, accent);
+        }
+    }
+    push_piece(content, pieces, rest);
+    for piece in argument_pieces {
+        push_piece(content, pieces, piece);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         attribute::DimensionUnit,
-        parser::{lex, Token},
+        event::{ArrayColumn, ArrayColumns, ColumnAlignment},
+        parser::{lex, CharToken, TextBreak, Token},
     };
 
+    #[test]
+    fn infix_fraction() {
+        assert_eq!(
+            lex::infix_fraction(r"a + {b \over c} \over \overline{d}"),
+            Some((r"a + {b \over c} ", "over", r"\overline{d}"))
+        );
+        assert_eq!(lex::infix_fraction(r"\left( a \atop b \right)"), None);
+        assert_eq!(lex::infix_fraction(r"\overline{a}"), None);
+    }
+
+    #[test]
+    fn contains_infix() {
+        assert!(lex::contains_infix(r"a \over b"));
+        assert!(lex::contains_infix(r"a\abovewithdelims()1pt b"));
+        assert!(!lex::contains_infix(r"\overline{a} \overset{b}{c} \overbrace{d}"));
+        assert!(!lex::contains_infix(r"a \\over b"));
+    }
+
+    #[test]
+    fn cell() {
+        assert_eq!(lex::cell(r"a {b & c} & d"), r"a {b & c}");
+        assert_eq!(
+            lex::cell(r"a \begin{matrix} b \\ c \end{matrix} \\ d"),
+            r"a \begin{matrix} b \\ c \end{matrix}"
+        );
+        assert_eq!(lex::cell(r"a"), "a");
+    }
+
     #[test]
     fn signs() {
         let mut input = "  +    +-   \\test";
@@ -454,7 +1094,124 @@ mod tests {
         assert_eq!(cs, "foo");
         assert_eq!(token1, Token::ControlSequence("bar"));
         assert_eq!(token2, Token::ControlSequence("baz"));
-        assert_eq!(input, "blah");
+        assert_eq!(input, r"\bar\baz blah");
+    }
+
+    #[test]
+    fn conditional_branches() {
+        let mut input = r"a \ifnum1<2 b\else c\fi \else d \fi e";
+        let branches = lex::conditional_branches(&mut input).unwrap();
+        assert_eq!(branches, (r"a \ifnum1<2 b\else c\fi ", Some("d ")));
+        assert_eq!(input, "e");
+
+        let mut input = r"a\fi";
+        assert_eq!(lex::conditional_branches(&mut input).unwrap(), ("a", None));
+        assert!(lex::conditional_branches(&mut r"a \else b").is_err());
+    }
+
+    #[test]
+    fn control_sequence_name() {
+        let mut input = r" foo bar\endcsname baz";
+        assert_eq!(lex::control_sequence_name(&mut input).unwrap(), "foo bar");
+        assert_eq!(input, "baz");
+        assert!(lex::control_sequence_name(&mut r"foo\relax\endcsname").is_err());
+        assert!(lex::control_sequence_name(&mut "foo").is_err());
+    }
+
+    #[test]
+    fn text_span() {
+        let is_command = |name: &str| name == "textbf";
+        let mut input = "if $x^2 = \\$1$ then \\textbf{y} \\textit{%$\n\\$} end";
+        assert_eq!(
+            lex::text_span(&mut input, is_command).unwrap(),
+            ("if ", Some(TextBreak::Math(r"x^2 = \$1")))
+        );
+        assert_eq!(
+            lex::text_span(&mut input, is_command).unwrap(),
+            (" then ", Some(TextBreak::ControlSequence("textbf")))
+        );
+        assert_eq!(input, "{y} \\textit{%$\n\\$} end");
+        input = &input[3..];
+        assert_eq!(
+            lex::text_span(&mut input, is_command).unwrap(),
+            (" \\textit{%$\n\\$} end", None)
+        );
+        assert_eq!(input, "");
+        let mut input = r"a \(x \text{$y$}\) b $c \text{\(d\)}$";
+        assert_eq!(
+            lex::text_span(&mut input, is_command).unwrap(),
+            ("a ", Some(TextBreak::Math(r"x \text{$y$}")))
+        );
+        assert_eq!(
+            lex::text_span(&mut input, is_command).unwrap(),
+            (" b ", Some(TextBreak::Math(r"c \text{\(d\)}")))
+        );
+        assert_eq!(input, "");
+        for unbalanced in ["a $b", r"a \(b $c$", r"a \) b", r"a $\text{$b}"] {
+            let mut input = unbalanced;
+            assert!(lex::text_span(&mut input, is_command).is_err());
+            assert_eq!(input, &unbalanced[2..]);
+        }
+    }
+
+    #[test]
+    fn caret_notation() {
+        assert_eq!(lex::caret_notation("^^Ix"), Some(('\t', "x")));
+        assert_eq!(lex::caret_notation("^^41^^@"), Some(('A', "^^@")));
+        assert_eq!(lex::caret_notation("^^e9"), Some(('é', "")));
+        assert_eq!(lex::caret_notation("^^E9"), Some(('\u{5}', "9")));
+        assert_eq!(lex::caret_notation("^^"), None);
+        assert_eq!(lex::caret_notation("^^α"), None);
+        assert_eq!(lex::caret_notation("^2"), None);
+
+        let mut input = r"^^5calpha ^^I^^25 comment
+            ^^3d";
+        assert_eq!(
+            lex::token(&mut input).unwrap(),
+            Token::ControlSequence("alpha")
+        );
+        assert_eq!(
+            lex::token(&mut input).unwrap(),
+            Token::Character(CharToken::from_str("="))
+        );
+    }
+
+    #[test]
+    fn array_columns() {
+        let columns = lex::array_columns("|l c|r").unwrap();
+        assert_eq!(
+            columns.as_slice(),
+            [
+                ArrayColumn::VerticalLine,
+                ArrayColumn::Column(ColumnAlignment::Left),
+                ArrayColumn::Column(ColumnAlignment::Center),
+                ArrayColumn::VerticalLine,
+                ArrayColumn::Column(ColumnAlignment::Right),
+            ]
+        );
+        assert!(lex::array_columns("lp").is_err());
+        assert!(lex::array_columns(&"c".repeat(ArrayColumns::CAPACITY + 1)).is_err());
+    }
+
+    #[test]
+    fn text_pieces() {
+        let text = |content| lex::text_pieces(content).concat();
+        assert_eq!(text("a   b\n  c"), "a b c");
+        assert_eq!(text(" if\n\n   then "), " if then ");
+        assert_eq!(text("a% comment\n   b"), "ab");
+        assert_eq!(text(r"a~b\ \ c"), "a\u{a0}b  c");
+        assert_eq!(text(r"50\% \{x\} {a}b \LaTeX"), r"50% {x} ab \LaTeX");
+        assert_eq!(
+            text(r#"caf\'e \"{u} \c c \v{s}a \'{\i} \'\j \^{} \H o \u{ab}"#),
+            "café ü ç ša í j\u{301} \u{a0}\u{302} ő ăb"
+        );
+        assert_eq!(text(r"\cc \dag"), r"\cc \dag");
+        assert_eq!(text("a\u{a0} b"), "a\u{a0} b");
+
+        // The pieces following each other in the content are joined.
+        assert_eq!(lex::text_pieces(r"a b \dag"), vec![r"a b \dag"]);
+        assert_eq!(lex::text_pieces("a  b\\%c"), vec!["a ", "b", "%c"]);
+        assert_eq!(lex::text_pieces(r"caf\'e"), vec!["caf", "é"]);
     }
 
     #[test]
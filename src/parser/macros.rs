@@ -1,14 +1,12 @@
 use std::collections::HashMap;
 
-use thiserror::Error;
+use crate::parser::{lex, Argument, CharToken, ErrorKind, InnerResult, Token};
 
-use crate::parser::Token;
-
-const MAX_EXPANSION: usize = 32;
-
-struct MacroContext<'a> {
+#[derive(Debug)]
+pub(super) struct MacroContext<'a> {
     definitions: HashMap<&'a str, MacroDef<'a>>,
     assignments: HashMap<&'a str, Token<'a>>,
+    paired_delimiters: HashMap<&'a str, (char, char)>,
 }
 
 // QUESTIONS:
@@ -21,13 +19,12 @@ struct MacroContext<'a> {
 //  (for us however, it needs to copy the entry).
 // What does \relax do?
 // > https://tex.stackexchange.com/questions/86385/what-is-the-difference-between-relax-and
-//
-// TODO: Make sure that we do not own an assignment and a definition at the same time.
 impl<'a> MacroContext<'a> {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             definitions: HashMap::new(),
             assignments: HashMap::new(),
+            paired_delimiters: HashMap::new(),
         }
     }
 
@@ -40,13 +37,16 @@ impl<'a> MacroContext<'a> {
     ///
     /// - The replacement text must be properly balanced.
     /// - The parameter text must not contain '{' or '}'.
-    fn define(
+    ///
+    /// For `\edef`, `max_expansion_depth` is the depth up to which the macros of the replacement
+    /// text are expanded, and `None` for `\def`.
+    pub(super) fn define(
         &mut self,
         name: &'a str,
         mut parameter_text: &'a str,
         replacement_text: &'a str,
-        expand_replacement: bool,
-    ) -> Result<()> {
+        max_expansion_depth: Option<usize>,
+    ) -> InnerResult<()> {
         // Check for the '#{' rule of the last parameter (TeXBook p. 204).
         let last_param_brace_delimited = parameter_text.ends_with('#');
         if last_param_brace_delimited {
@@ -65,8 +65,8 @@ impl<'a> MacroContext<'a> {
             None
         } else {
             // The parameter text is already guaranteed to not contain '{'.
-            if prefix.find(|c| c == '}').is_some() {
-                return Err(MacroContextError::BracesInParamText);
+            if prefix.contains('}') {
+                return Err(ErrorKind::MacroParameters);
             };
             Some(prefix)
         };
@@ -74,35 +74,35 @@ impl<'a> MacroContext<'a> {
         // Parse the arguments, making sure that they are in order and that the number of arguments
         // is less than 10.
         let parameters: Vec<_> = parameters
-            .map(|(i, arg)| -> Result<Parameter> {
+            .map(|(i, arg)| -> InnerResult<Parameter> {
                 let mut chars = arg.chars();
                 let param_index = chars
                     .next()
                     .and_then(|c| c.is_ascii_digit().then_some(c as u8 - b'0'))
-                    .ok_or(MacroContextError::StandaloneHashSign)?;
+                    .ok_or(ErrorKind::MacroParameters)?;
                 if param_index != i as u8 {
-                    return Err(MacroContextError::IncorrectMacroParams(
-                        param_index,
-                        i as u8,
-                    ));
+                    return Err(ErrorKind::MacroParameters);
                 };
                 let suffix = chars.as_str();
                 if suffix.is_empty() {
                     Ok(None)
                 } else {
                     // The parameter text is already guaranteed to not contain '{'.
-                    if suffix.find(|c| c == '}').is_some() {
-                        return Err(MacroContextError::BracesInParamText);
+                    if suffix.contains('}') {
+                        return Err(ErrorKind::MacroParameters);
                     };
                     Ok(Some(suffix))
                 }
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect::<InnerResult<Vec<_>>>()?;
 
         // Parse the replacement text, making sure that it is properly balanced.
-        // TODO: expand replacement text when necessary
+        //
+        // When the replacement text is expanded (i.e., for `\edef`), the macros without parameters
+        // are replaced by their current replacement text, itself fully expanded. Other control
+        // sequences are kept as is.
         let mut replacement_splits = replacement_text
-            .split_inclusive(|c| matches!(c, '#' | '\\'))
+            .split_inclusive(['#', '\\'])
             .peekable();
         let mut replacement_tokens: Vec<ReplacementToken> = Vec::new();
 
@@ -117,7 +117,7 @@ impl<'a> MacroContext<'a> {
                 '#' => {
                     let next_split = replacement_splits
                         .peek_mut()
-                        .ok_or(MacroContextError::StandaloneHashSign)?;
+                        .ok_or(ErrorKind::MacroParameters)?;
                     let first_char = next_split
                         .chars()
                         .next()
@@ -128,10 +128,7 @@ impl<'a> MacroContext<'a> {
                     } else if first_char.is_ascii_digit() {
                         let param_index = first_char as u8 - b'0';
                         if param_index > parameters.len() as u8 || param_index == 0 {
-                            return Err(MacroContextError::IncorrectReplacementParams(
-                                param_index,
-                                parameters.len() as u8,
-                            ));
+                            return Err(ErrorKind::MacroParameters);
                         };
 
                         if let Some(t) = replacement_tokens.last_mut() {
@@ -158,28 +155,52 @@ impl<'a> MacroContext<'a> {
                             break;
                         }
                     } else {
-                        return Err(MacroContextError::StandaloneHashSign);
+                        return Err(ErrorKind::MacroParameters);
                     }
                 }
                 '\\' => {
-                    // TODO: this should be changed when allowing for expansion.
                     let next_split = replacement_splits
-                        .peek()
+                        .peek_mut()
                         .expect("the last character of the replacement text cannot be a backslash");
-                    // The next split can only be 1 byte long if it only contains a splitting character.
-                    if next_split.len() == 1 {
+                    let (control_sequence, rest) = control_sequence_name(next_split);
+                    let definition = self
+                        .definitions
+                        .get(control_sequence)
+                        .filter(|def| def.takes_no_arguments());
+
+                    if let (Some(definition), Some(max_depth)) = (definition, max_expansion_depth) {
+                        // Replace the control sequence with the replacement text of its
+                        // definition.
+                        if let Some(ReplacementToken::String(s)) = replacement_tokens.last_mut() {
+                            *s = &s[..s.len() - 1];
+                            if s.is_empty() {
+                                replacement_tokens.pop();
+                            }
+                        }
+                        self.expand_replacement(
+                            &definition.replacement,
+                            &mut replacement_tokens,
+                            max_depth,
+                        )?;
+
+                        if rest.is_empty() {
+                            replacement_splits.next();
+                        } else {
+                            *next_split = rest;
+                        }
+                    } else if next_split.len() == 1 {
+                        // The next split can only be 1 byte long if it only contains a splitting
+                        // character.
                         replacement_tokens.push(ReplacementToken::String(next_split));
                         replacement_splits.next();
                     }
-
-                    if expand_replacement {
-                        todo!("potentially expand");
-                    }
                 }
                 _ => {}
             }
         }
 
+        self.assignments.remove(name);
+        self.paired_delimiters.remove(name);
         self.definitions.insert(
             name,
             MacroDef {
@@ -192,14 +213,143 @@ impl<'a> MacroContext<'a> {
         Ok(())
     }
 
-    /// Assign a new control sequence to a token.
-    fn assign(&mut self, name: &'a str, alias_for: Token<'a>) -> Result<()> {
-        self.assignments.insert(name, alias_for);
+    /// Push the replacement text of a macro without parameters to the tokens, replacing the macros
+    /// without parameters it contains by their own expanded replacement text.
+    ///
+    /// Fails if the expansion is nested deeper than `max_depth`, e.g., for a recursive macro.
+    fn expand_replacement(
+        &self,
+        replacement: &[ReplacementToken<'a>],
+        tokens: &mut Vec<ReplacementToken<'a>>,
+        max_depth: usize,
+    ) -> InnerResult<()> {
+        let max_depth = max_depth.checked_sub(1).ok_or(ErrorKind::NestingTooDeep)?;
+        // Whether the previous piece ends with the backslash of a control sequence, such that
+        // the current piece starts with its name.
+        let mut escaped = false;
+        for token in replacement {
+            let ReplacementToken::String(piece) = *token else {
+                tokens.push(token.clone());
+                escaped = false;
+                continue;
+            };
+            let (name, rest) = if escaped {
+                control_sequence_name(piece)
+            } else {
+                ("", piece)
+            };
+            escaped = piece[name.len()..].ends_with('\\');
+            let definition = self
+                .definitions
+                .get(name)
+                .filter(|def| !name.is_empty() && def.takes_no_arguments());
+
+            if let Some(definition) = definition {
+                if let Some(ReplacementToken::String(s)) = tokens.last_mut() {
+                    *s = &s[..s.len() - 1];
+                    if s.is_empty() {
+                        tokens.pop();
+                    }
+                }
+                self.expand_replacement(&definition.replacement, tokens, max_depth)?;
+                if !rest.is_empty() {
+                    tokens.push(ReplacementToken::String(rest));
+                }
+            } else {
+                tokens.push(token.clone());
+            }
+        }
         Ok(())
     }
+
+    /// Assign a new control sequence to a token.
+    ///
+    /// The control sequence takes the current meaning of the token, such that reassigning the
+    /// token afterwards does not change the meaning of the control sequence.
+    pub(super) fn assign(&mut self, name: &'a str, alias_for: Token<'a>) {
+        if let Token::ControlSequence(cs) = alias_for {
+            if let Some(definition) = self.definitions.get(cs).cloned() {
+                self.assignments.remove(name);
+                self.paired_delimiters.remove(name);
+                self.definitions.insert(name, definition);
+                return;
+            }
+        }
+        let meaning = match alias_for {
+            Token::ControlSequence(cs) => self.meaning(cs).unwrap_or(alias_for),
+            // Only keep the character itself, and not the input following it.
+            Token::Character(c) => {
+                let len = char::from(c).len_utf8();
+                Token::Character(CharToken::from_str(&c.as_str()[..len]))
+            }
+        };
+        self.definitions.remove(name);
+        self.paired_delimiters.remove(name);
+        self.assignments.insert(name, meaning);
+    }
+
+    /// Declare a command surrounding its argument with the given delimiters, as with mathtools'
+    /// `\DeclarePairedDelimiter`.
+    pub(super) fn declare_paired_delimiter(&mut self, name: &'a str, left: char, right: char) {
+        self.definitions.remove(name);
+        self.assignments.remove(name);
+        self.paired_delimiters.insert(name, (left, right));
+    }
+
+    /// Return the delimiters of the command declared by `\DeclarePairedDelimiter`, if any.
+    pub(super) fn paired_delimiter(&self, name: &str) -> Option<(char, char)> {
+        self.paired_delimiters.get(name).copied()
+    }
+
+    /// Whether the control sequence is a macro defined by `\def` or `\edef`.
+    pub(super) fn is_macro(&self, name: &str) -> bool {
+        self.definitions.contains_key(name)
+    }
+
+    /// Whether both control sequences are macros with the same parameter text and replacement
+    /// text, as compared by `\ifx`.
+    pub(super) fn same_definition(&self, left: &str, right: &str) -> bool {
+        match (self.definitions.get(left), self.definitions.get(right)) {
+            (Some(left), Some(right)) => {
+                left.prefix == right.prefix
+                    && left.parameters == right.parameters
+                    && left.last_param_brace_delimited == right.last_param_brace_delimited
+                    && left.replacement_text() == right.replacement_text()
+            }
+            _ => false,
+        }
+    }
+
+    /// Read the arguments of the macro from the input, and return the pieces of its replacement
+    /// text, with the arguments in place of the parameters.
+    ///
+    /// Returns `None` if the control sequence is not a macro.
+    pub(super) fn expand(
+        &self,
+        name: &str,
+        input: &mut &'a str,
+    ) -> Option<InnerResult<Vec<&'a str>>> {
+        let definition = self.definitions.get(name)?;
+        Some(definition.arguments(input).map(|arguments| {
+            definition
+                .replacement
+                .iter()
+                .map(|token| match token {
+                    ReplacementToken::String(s) => *s,
+                    ReplacementToken::Parameter(index) => arguments[*index as usize - 1],
+                })
+                .filter(|piece| !piece.is_empty())
+                .collect()
+        }))
+    }
+
+    /// Return the token the control sequence was assigned to, if any.
+    pub(super) fn meaning(&self, name: &str) -> Option<Token<'a>> {
+        self.assignments.get(name).copied()
+    }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 struct MacroDef<'a> {
     prefix: Option<&'a str>,
     parameters: Vec<Parameter<'a>>,
@@ -207,6 +357,118 @@ struct MacroDef<'a> {
     replacement: Vec<ReplacementToken<'a>>,
 }
 
+impl<'a> MacroDef<'a> {
+    /// Whether the macro is used without any argument, such that it can be expanded in place.
+    fn takes_no_arguments(&self) -> bool {
+        self.prefix.is_none() && self.parameters.is_empty() && !self.last_param_brace_delimited
+    }
+
+    /// The replacement text of the macro, written as in its definition.
+    ///
+    /// The pieces of the replacement text are split differently depending on how the macro was
+    /// defined, e.g., by `\def` or `\edef`.
+    fn replacement_text(&self) -> String {
+        self.replacement
+            .iter()
+            .map(|token| match token {
+                ReplacementToken::String(s) => s.replace('#', "##"),
+                ReplacementToken::Parameter(index) => format!("#{index}"),
+            })
+            .collect()
+    }
+
+    /// Read the arguments of the macro from the input, matching its parameter text (TeXBook
+    /// p. 203).
+    fn arguments(&self, input: &mut &'a str) -> InnerResult<Vec<&'a str>> {
+        if let Some(prefix) = self.prefix {
+            *input = input
+                .strip_prefix(prefix)
+                .ok_or(ErrorKind::MacroArguments)?;
+        }
+        let mut arguments = Vec::with_capacity(self.parameters.len());
+        for (index, suffix) in self.parameters.iter().enumerate() {
+            let last = index + 1 == self.parameters.len();
+            let brace_delimited = last && self.last_param_brace_delimited;
+            let argument = match (suffix, brace_delimited) {
+                (None, false) => undelimited_argument(input)?,
+                (Some(delimiter), false) => delimited_argument(input, delimiter)?,
+                (delimiter, true) => {
+                    delimited_argument(input, &format!("{}{{", delimiter.unwrap_or_default()))?
+                }
+            };
+            arguments.push(argument);
+        }
+        if self.parameters.is_empty()
+            && self.last_param_brace_delimited
+            && !input.starts_with('{')
+        {
+            return Err(ErrorKind::MacroArguments);
+        }
+        Ok(arguments)
+    }
+}
+
+/// Split the text following a backslash into the name of the control sequence and the rest of
+/// the text, without the spaces following a control word.
+fn control_sequence_name(text: &str) -> (&str, &str) {
+    let name_len = text.chars().take_while(char::is_ascii_alphabetic).count();
+    if name_len > 0 {
+        (&text[..name_len], text[name_len..].trim_start())
+    } else {
+        let name_len = text.chars().next().map_or(0, char::len_utf8);
+        text.split_at(name_len)
+    }
+}
+
+/// Read an undelimited argument, which is either a single token or a group whose braces are
+/// removed.
+fn undelimited_argument<'a>(input: &mut &'a str) -> InnerResult<&'a str> {
+    let start = input.trim_start();
+    match lex::argument(input).map_err(|_| ErrorKind::MacroArguments)? {
+        Argument::Group(group) => Ok(group),
+        Argument::Token(_) => Ok(start[..start.len() - input.len()].trim_end()),
+    }
+}
+
+/// Remove the braces around the argument, if they match each other.
+fn strip_braces(argument: &str) -> &str {
+    let Some(mut inner) = argument.strip_prefix('{') else {
+        return argument;
+    };
+    match lex::group_content(&mut inner, "{", "}") {
+        Ok(group) if inner.is_empty() => group,
+        _ => argument,
+    }
+}
+
+/// Read an argument delimited by the given text, which is not part of the argument, unless it is
+/// the `{` ending the parameter text.
+///
+/// The delimiter must not be inside of a group of the argument, and braces around the whole
+/// argument are removed.
+fn delimited_argument<'a>(input: &mut &'a str, delimiter: &str) -> InnerResult<&'a str> {
+    let content = *input;
+    let mut chars = content.char_indices();
+    let mut depth = 0usize;
+    while let Some((position, c)) = chars.next() {
+        if depth == 0 && content[position..].starts_with(delimiter) {
+            let argument = &content[..position];
+            // The `{` ending the parameter text is left in the input.
+            *input = &content[position + delimiter.strip_suffix('{').unwrap_or(delimiter).len()..];
+            return Ok(strip_braces(argument));
+        }
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.checked_sub(1).ok_or(ErrorKind::MacroArguments)?,
+            '\\' => {
+                chars.next();
+            }
+            _ => (),
+        }
+    }
+    Err(ErrorKind::MacroArguments)
+}
+
 /// Some if the argument has a suffix, None otherwise.
 type Parameter<'a> = Option<&'a str>;
 
@@ -216,22 +478,6 @@ enum ReplacementToken<'a> {
     String(&'a str),
 }
 
-#[derive(Debug, Error)]
-enum MacroContextError {
-    #[error("macro definition of parameters contains '{{' or '}}'")]
-    BracesInParamText,
-    #[error("macro definition found parameter #{0} but expected #{1}")]
-    IncorrectMacroParams(u8, u8),
-    #[error(
-        "macro definition found parameter #{0} but expected a parameter in the range [#1, #{1}]"
-    )]
-    IncorrectReplacementParams(u8, u8),
-    #[error("macro definition contains a standalone '#'")]
-    StandaloneHashSign,
-}
-
-type Result<T> = std::result::Result<T, MacroContextError>;
-
 #[cfg(test)]
 mod tests {
     use super::{MacroContext, ReplacementToken};
@@ -239,7 +485,7 @@ mod tests {
     #[test]
     fn no_params() {
         let mut ctx = MacroContext::new();
-        ctx.define("foo", "", "\\this {} is a ## test", false)
+        ctx.define("foo", "", "\\this {} is a ## test", None)
             .map_err(|e| eprintln!("{e}"))
             .unwrap();
 
@@ -265,14 +511,14 @@ mod tests {
             "foo",
             "this#1test#2. should #",
             "\\this {} is a ## test#1",
-            false,
+            None,
         )
         .map_err(|e| eprintln!("{e}"))
         .unwrap();
 
         let def = ctx.definitions.get("foo").unwrap();
         assert_eq!(def.prefix, Some("this"));
-        assert_eq!(def.parameters, vec![Some("test".into()), Some(". should ")]);
+        assert_eq!(def.parameters, vec![Some("test"), Some(". should ")]);
         assert!(def.last_param_brace_delimited);
         assert_eq!(
             def.replacement,
@@ -290,15 +536,15 @@ mod tests {
     #[test]
     fn texbook() {
         let mut ctx = MacroContext::new();
-        ctx.define("cs", r"AB#1#2C$#3\$ ", r"#3{ab#1}#1 c##\x #2", false)
+        ctx.define("cs", r"AB#1#2C$#3\$ ", r"#3{ab#1}#1 c##\x #2", None)
             .map_err(|e| eprintln!("{e}"))
             .unwrap();
 
         let def = ctx.definitions.get("cs").unwrap();
-        assert_eq!(def.prefix, Some("AB".into()));
+        assert_eq!(def.prefix, Some("AB"));
         assert_eq!(
             def.parameters,
-            vec![None, Some("C$".into()), Some(r"\$ ".into())]
+            vec![None, Some("C$"), Some(r"\$ ")]
         );
         assert_eq!(
             def.replacement,
@@ -316,10 +562,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn expanded_replacement() {
+        let mut ctx = MacroContext::new();
+        ctx.define("a", "", "x + y", None).unwrap();
+        ctx.define("f", "#1", "f(#1)", None).unwrap();
+        ctx.define("b", "", r"\a \a z\f{\a}\\", Some(256)).unwrap();
+
+        let def = ctx.definitions.get("b").unwrap();
+        assert_eq!(
+            def.replacement,
+            vec![
+                ReplacementToken::String("x + y"),
+                ReplacementToken::String("x + y"),
+                ReplacementToken::String(r"z\"),
+                ReplacementToken::String("f{"),
+                ReplacementToken::String("x + y"),
+                ReplacementToken::String(r"}\"),
+                ReplacementToken::String(r"\"),
+            ]
+        );
+
+        // The replacement text of `\edef` does not change when `\a` is redefined.
+        ctx.define("a", "", "w", None).unwrap();
+        let def = ctx.definitions.get("b").unwrap();
+        assert_eq!(def.replacement[0], ReplacementToken::String("x + y"));
+
+        ctx.define("c", "", r"\a", None).unwrap();
+        let def = ctx.definitions.get("c").unwrap();
+        assert_eq!(
+            def.replacement,
+            vec![ReplacementToken::String(r"\"), ReplacementToken::String("a")]
+        );
+
+        // The macros in the replacement text of `\c` are expanded as well, with their current
+        // definition.
+        ctx.define("d", "", r"(\c)", Some(256)).unwrap();
+        ctx.define("a", "", "v", None).unwrap();
+        let def = ctx.definitions.get("d").unwrap();
+        assert_eq!(
+            def.replacement,
+            vec![
+                ReplacementToken::String("("),
+                ReplacementToken::String("w"),
+                ReplacementToken::String(")"),
+            ]
+        );
+
+        // A recursive macro cannot be expanded.
+        ctx.define("e", "", r"x\e", None).unwrap();
+        assert!(ctx.define("f", "", r"\e", Some(256)).is_err());
+    }
+
     #[test]
     fn brace_delim_no_text() {
         let mut ctx = MacroContext::new();
-        ctx.define("foo", "#", "2 + 2 = 4", false)
+        ctx.define("foo", "#", "2 + 2 = 4", None)
             .map_err(|e| eprintln!("{e}"))
             .unwrap();
 
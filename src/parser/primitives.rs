@@ -4,7 +4,8 @@
 use core::panic;
 
 use crate::{
-    attribute::{DimensionUnit, Font},
+    attribute::{tex_to_scaled_points, DimensionUnit, Font},
+    config::{DisplayMode, ErrorPolicy},
     event::{
        Grouping as G,
        Operator as O,
@@ -18,14 +19,21 @@ use crate::{
        StateChange as SC,
        ColorTarget as CT,
        ColorChange as CC,
+       LapDirection as LD,
+       BoxAlignment as BA,
+       StrikeKind as SK,
+       TextFont as TF,
+       AtomClass as AC,
+       AnnotationKind as AK,
        Grouping,
     },
 };
 
 use super::{
     lex,
-    tables::{control_sequence_delimiter_map, is_char_delimiter, is_operator, is_primitive_color, token_to_delim},
-    Argument, CharToken, ErrorKind, InnerResult, Instruction as I, Parser, Token,
+    symbols,
+    tables::{control_sequence_delimiter_class, control_sequence_delimiter_map, is_char_delimiter, is_operator, is_primitive_color, negated_relation, primes, token_to_delim},
+    fraction_style, is_known_primitive, script_style, Argument, CharToken, ErrorKind, InnerResult, Instruction as I, Parser, TextBreak, Token,
 };
 
 /// Return an `Operator` event with the given content and default modifiers.
@@ -82,21 +90,31 @@ impl<'a> Parser<'a> {
             }
             '$' => return Err(ErrorKind::MathShift),
             '#' => return Err(ErrorKind::HashSign),
-            '&' if self.allows_alignment() => E::Alignment,
+            '&' if self.allows_alignment() => {
+                self.buffer.push(I::Event(E::Alignment));
+                self.cell_content();
+                return Ok(());
+            }
             '{' => {
                 let str = self.current_string();
                 let group = lex::group_content(str, "{", "}")?;
-                self.buffer.extend([
-                    I::Event(E::Begin(G::Normal)),
-                    I::SubGroup { content: group, allows_alignment: false },
-                    I::Event(E::End)
-                ]);
+                if group.trim().is_empty() && self.prescripts()? {
+                    return Ok(())
+                }
+                self.buffer.push(I::Event(E::Begin(G::Normal)));
+                self.group_content(group, false);
+                self.buffer.push(I::Event(E::End));
                 return Ok(())
             },
             '}' => {
                 return Err(ErrorKind::UnbalancedGroup(None))
             },
-            '\'' => E::Content(C::Operator(op!('′'))),
+            '\'' => {
+                let count = 1 + lex::primes(self.current_string());
+                self.push_primes(count);
+                return Ok(())
+            },
+            '~' => self.non_breaking_space(),
 
             c if is_char_delimiter(c) => E::Content(C::Operator(op!(c, {stretchy: Some(false)}))),
             c if is_operator(c) => E::Content(C::Operator(op!(c))),
@@ -113,7 +131,11 @@ impl<'a> Parser<'a> {
                     len -= 1;
                 }
                 let (number, rest) = content.split_at(len);
-                *self.current_string() = rest;
+                // A digit written in `^^` notation is not followed by the rest of the input, and
+                // is a number on its own.
+                if len > 1 {
+                    *self.current_string() = rest;
+                }
                 self.buffer
                     .push(I::Event(E::Content(C::Number(number))));
                 return Ok(())
@@ -124,8 +146,45 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    /// Handle a supported control sequence, pushing instructions to the provided stack.
+    /// Handle a control sequence, pushing instructions to the provided stack.
+    ///
+    /// Control sequences assigned by `\let` or `\futurelet` are handled as the token they were
+    /// assigned to.
     pub(super) fn handle_primitive(&mut self, control_sequence: &'a str) -> InnerResult<()> {
+        if let Some(delimiters) = self.macro_context.paired_delimiter(control_sequence) {
+            return self.paired_delimiter(delimiters);
+        }
+        let mut input = *self.current_string();
+        if let Some(pieces) = self.macro_context.expand(control_sequence, &mut input) {
+            *self.current_string() = input;
+            let content = self.join_pieces(&pieces?)?;
+            // The expansion is parsed on its own, like the branch of a conditional, and can thus
+            // contain its own infix command.
+            if !self.infix_fraction(content, self.allows_middle()) {
+                self.buffer.push(I::SubGroup {
+                    content,
+                    allows_alignment: self.allows_alignment(),
+                    allows_middle: self.allows_middle(),
+                    style: self.buffered_style(),
+                });
+            }
+            self.state.skip_suffixes = true;
+            return Ok(());
+        }
+        match self.macro_context.meaning(control_sequence) {
+            Some(Token::ControlSequence(cs)) => self.primitive(cs),
+            // The digits following the control sequence are not part of the number.
+            Some(Token::Character(c)) if char::from(c).is_ascii_digit() => {
+                self.buffer.push(I::Event(E::Content(C::Number(c.as_str()))));
+                Ok(())
+            }
+            Some(Token::Character(c)) => self.handle_char_token(c),
+            None => self.primitive(control_sequence),
+        }
+    }
+
+    /// Handle a supported control sequence, pushing instructions to the provided stack.
+    fn primitive(&mut self, control_sequence: &'a str) -> InnerResult<()> {
         let event = match control_sequence {
             "arccos" | "cos" | "csc" | "exp" | "ker" | "sinh" | "arcsin" | "cosh" | "deg"
             | "lg" | "ln" | "arctan" | "cot" | "det" | "hom" | "log" | "sec" | "tan" | "arg"
@@ -137,6 +196,19 @@ impl<'a> Parser<'a> {
                 self.state.above_below_suffix_default = true;
                 E::Content(C::Identifier(ID::Str(control_sequence)))
             }
+            "injlim" | "projlim" => {
+                self.state.allow_suffix_modifiers = true;
+                self.state.above_below_suffix_default = true;
+                E::Content(C::Identifier(ID::Str(if control_sequence == "injlim" {
+                    "inj lim"
+                } else {
+                    "proj lim"
+                })))
+            }
+            "varliminf" => return self.decorated_limit(ST::Subscript, op!('_')),
+            "varlimsup" => return self.decorated_limit(ST::Superscript, op!('‾')),
+            "varinjlim" => return self.decorated_limit(ST::Subscript, op!('→')),
+            "varprojlim" => return self.decorated_limit(ST::Subscript, op!('←')),
             "operatorname" => {
                 self.state.allow_suffix_modifiers = true;
                 let argument = lex::argument(self.current_string())?;
@@ -152,13 +224,59 @@ impl<'a> Parser<'a> {
                     }
                 }
             }
-            "bmod" => E::Content(C::Identifier(ID::Str("mod"))),
-            "pmod" => {
+            "adjustlimits" => {
+                let str = self.current_string();
+                let start = str.trim_start();
+                *str = start;
+                lex::limited_operator(str)?;
+                lex::limited_operator(str)?;
+                let content = &start[..start.len() - str.len()];
+                self.buffer.extend([
+                    I::Event(E::Begin(G::AdjustLimits)),
+                    I::SubGroup { content, allows_alignment: false, allows_middle: false, style: self.buffered_style() },
+                    I::Event(E::End),
+                ]);
+                return Ok(());
+            }
+            // Limit controls following an operator are handled with its suffixes.
+            "limits" | "nolimits" => return Err(ErrorKind::LimitControl),
+            "bmod" => {
+                self.buffer.extend([
+                    I::Event(E::Begin(G::AtomClass(AC::Binary))),
+                    I::Event(E::Content(C::Identifier(ID::Str("mod")))),
+                ]);
+                E::End
+            }
+            "pmod" | "mod" | "pod" => {
                 let argument = lex::argument(self.current_string())?;
+                // The space preceding the modulo is wider in display mode.
+                let display = self.config.display_mode == DisplayMode::Block;
+                let space = match (control_sequence, display) {
+                    (_, true) => 18.,
+                    ("mod", false) => 12.,
+                    _ => 8.,
+                };
+                self.buffer.push(I::Event(E::Space {
+                    width: Some((space / 18., DimensionUnit::Em)),
+                    height: None,
+                    depth: None,
+                }));
+                if control_sequence == "mod" {
+                    self.buffer
+                        .push(I::Event(E::Content(C::Identifier(ID::Str("mod")))));
+                    // The scripts following the modulus are its own.
+                    self.state.suffix_base = self.buffer.len();
+                    return self.handle_argument(argument);
+                }
+                self.state.suffix_base = self.buffer.len();
                 self.buffer.extend([
                     I::Event(E::Begin(G::Internal)),
                     I::Event(operator(op!('('))),
                 ]);
+                if control_sequence == "pmod" {
+                    self.buffer
+                        .push(I::Event(E::Content(C::Identifier(ID::Str("mod")))));
+                }
                 self.handle_argument(argument)?;
                 self.buffer.extend([
                     I::Event(operator(op!(')'))),
@@ -169,103 +287,6 @@ impl<'a> Parser<'a> {
 
             // TODO: Operators with '*', for operatorname* and friends
 
-            /////////////////////////
-            // Non-Latin Alphabets //
-            /////////////////////////
-            // Lowercase Greek letters
-            "alpha" => ident('α'),
-            "beta" => ident('β'),
-            "gamma" => ident('γ'),
-            "delta" => ident('δ'),
-            "epsilon" => ident('ϵ'),
-            "zeta" => ident('ζ'),
-            "eta" => ident('η'),
-            "theta" => ident('θ'),
-            "iota" => ident('ι'),
-            "kappa" => ident('κ'),
-            "lambda" => ident('λ'),
-            "mu" => ident('µ'),
-            "nu" => ident('ν'),
-            "xi" => ident('ξ'),
-            "pi" => ident('π'),
-            "rho" => ident('ρ'),
-            "sigma" => ident('σ'),
-            "tau" => ident('τ'),
-            "upsilon" => ident('υ'),
-            "phi" => ident('φ'),
-            "chi" => ident('χ'),
-            "psi" => ident('ψ'),
-            "omega" => ident('ω'),
-            "omicron" => ident('ο'),
-            // Uppercase Greek letters
-            "Alpha" => ident('Α'),
-            "Beta" => ident('Β'),
-            "Gamma" => ident('Γ'),
-            "Delta" => ident('Δ'),
-            "Epsilon" => ident('Ε'),
-            "Zeta" => ident('Ζ'),
-            "Eta" => ident('Η'),
-            "Theta" => ident('Θ'),
-            "Iota" => ident('Ι'),
-            "Kappa" => ident('Κ'),
-            "Lambda" => ident('Λ'),
-            "Mu" => ident('Μ'),
-            "Nu" => ident('Ν'),
-            "Xi" => ident('Ξ'),
-            "Pi" => ident('Π'),
-            "Rho" => ident('Ρ'),
-            "Sigma" => ident('Σ'),
-            "Tau" => ident('Τ'),
-            "Upsilon" => ident('Υ'),
-            "Phi" => ident('Φ'),
-            "Chi" => ident('Χ'),
-            "Psi" => ident('Ψ'),
-            "Omega" => ident('Ω'),
-            "Omicron" => ident('Ο'),
-            // Lowercase Greek Variants
-            "varepsilon" => ident('ε'),
-            "vartheta" => ident('ϑ'),
-            "varkappa" => ident('ϰ'),
-            "varrho" => ident('ϱ'),
-            "varsigma" => ident('ς'),
-            "varpi" => ident('ϖ'),
-            "varphi" => ident('ϕ'),
-            // Uppercase Greek Variants
-            "varGamma" => ident('𝛤'),
-            "varDelta" => ident('𝛥'),
-            "varTheta" => ident('𝛩'),
-            "varLambda" => ident('𝛬'),
-            "varXi" => ident('𝛯'),
-            "varPi" => ident('𝛱'),
-            "varSigma" => ident('𝛴'),
-            "varUpsilon" => ident('𝛶'),
-            "varPhi" => ident('𝛷'),
-            "varPsi" => ident('𝛹'),
-            "varOmega" => ident('𝛺'),
-
-            // Hebrew letters
-            "aleph" => ident('ℵ'),
-            "beth" => ident('ℶ'),
-            "gimel" => ident('ℷ'),
-            "daleth" => ident('ℸ'),
-            // Other symbols
-            "digamma" => ident('ϝ'),
-            "eth" => ident('ð'),
-            "ell" => ident('ℓ'),
-            "nabla" => ident('∇'),
-            "partial" => ident('∂'),
-            "Finv" => ident('Ⅎ'),
-            "Game" => ident('ℷ'),
-            "hbar" | "hslash" => ident('ℏ'),
-            "imath" => ident('ı'),
-            "jmath" => ident('ȷ'),
-            "Im" => ident('ℑ'),
-            "Re" => ident('ℜ'),
-            "wp" => ident('℘'),
-            "Bbbk" => ident('𝕜'),
-            "Angstrom" => ident('Å'),
-            "backepsilon" => ident('϶'),
-
             ///////////////////////////
             // Symbols & Punctuation //
             ///////////////////////////
@@ -274,80 +295,6 @@ impl<'a> Parser<'a> {
             } else {
                 operator(op!('⋯'))
             }
-            "ldots" | "dotso" | "dotsc" => operator(op!('…')),
-            "cdots" | "dotsi" | "dotsm" | "dotsb" | "idotsin" => operator(op!('⋯')),
-            "ddots" => operator(op!('⋱')),
-            "iddots" => operator(op!('⋰')),
-            "vdots" => operator(op!('⋮')),
-            "mathellipsis" => operator(op!('…')),
-            "infty" => ident('∞'),
-            "checkmark" => ident('✓'),
-            "ballotx" => ident('✗'),
-            "dagger" | "dag" => ident('†'),
-            "ddagger" | "ddag" => ident('‡'),
-            "angle" => ident('∠'),
-            "measuredangle" => ident('∡'),
-            "lq" => ident('‘'),
-            "Box" => ident('□'),
-            "sphericalangle" => ident('∢'),
-            "square" => ident('□'),
-            "top" => ident('⊤'),
-            "rq" => ident('′'),
-            "blacksquare" => ident('■'),
-            "bot" => ident('⊥'),
-            "triangledown" => ident('▽'),
-            "Bot" => ident('⫫'),
-            "triangleleft" => ident('◃'),
-            "triangleright" => ident('▹'),
-            "cent" => ident('¢'),
-            "colon" | "ratio" | "vcentcolon" => ident(':'),
-            "bigtriangledown" => ident('▽'),
-            "pounds" | "mathsterling" => ident('£'),
-            "bigtriangleup" => ident('△'),
-            "blacktriangle" => ident('▲'),
-            "blacktriangledown" => ident('▼'),
-            "yen" => ident('¥'),
-            "blacktriangleleft" => ident('◀'),
-            "euro" => ident('€'),
-            "blacktriangleright" => ident('▶'),
-            "Diamond" => ident('◊'),
-            "degree" => ident('°'),
-            "lozenge" => ident('◊'),
-            "blacklozenge" => ident('⧫'),
-            "mho" => ident('℧'),
-            "bigstar" => ident('★'),
-            "diagdown" => ident('╲'),
-            "maltese" => ident('✠'),
-            "diagup" => ident('╱'),
-            "P" => ident('¶'),
-            "clubsuit" => ident('♣'),
-            "varclubsuit" => ident('♧'),
-            "S" => ident('§'),
-            "diamondsuit" => ident('♢'),
-            "vardiamondsuit" => ident('♦'),
-            "copyright" => ident('©'),
-            "heartsuit" => ident('♡'),
-            "varheartsuit" => ident('♥'),
-            "circledR" => ident('®'),
-            "spadesuit" => ident('♠'),
-            "varspadesuit" => ident('♤'),
-            "circledS" => ident('Ⓢ'),
-            "female" => ident('♀'),
-            "male" => ident('♂'),
-            "astrosun" => ident('☉'),
-            "sun" => ident('☼'),
-            "leftmoon" => ident('☾'),
-            "rightmoon" => ident('☽'),
-            "smiley" => ident('☺'),
-            "Earth" => ident('⊕'),
-            "flat" => ident('♭'),
-            "standardstate" => ident('⦵'),
-            "natural" => ident('♮'),
-            "sharp" => ident('♯'),
-            "permil" => ident('‰'),
-            "QED" => ident('∎'),
-            "lightning" => ident('↯'),
-            "diameter" => ident('⌀'),
 
             ////////////////////////
             // Font state changes //
@@ -359,6 +306,9 @@ impl<'a> Parser<'a> {
             "rm" => self.font_change(Font::UpRight),
             "sf" => self.font_change(Font::SansSerif),
             "tt" => self.font_change(Font::Monospace),
+            "sc" => self.font_change(Font::SmallCaps),
+            "sl" => self.font_change(Font::Slanted),
+            "bbit" => self.font_change(Font::DoubleStruckItalic),
             // amsfonts font changes (old behavior a.k.a NFSS 1)
             // unicode-math font changes (old behavior a.k.a NFSS 1)
             // changes, as described in https://mirror.csclub.uwaterloo.ca/CTAN/macros/unicodetex/latex/unicode-math/unicode-math.pdf
@@ -376,6 +326,7 @@ impl<'a> Parser<'a> {
             }
             "mathtt" | "symtt" => return self.font_group(Some(Font::Monospace)),
             "mathbb" | "symbb" => return self.font_group(Some(Font::DoubleStruck)),
+            "mathbbit" | "symbbit" => return self.font_group(Some(Font::DoubleStruckItalic)),
             "mathfrak" | "symfrak" => return self.font_group(Some(Font::Fraktur)),
             "mathbfcal" | "symbfcal" => return self.font_group(Some(Font::BoldScript)),
             "mathsfit" | "symsfit" => return self.font_group(Some(Font::SansSerifItalic)),
@@ -392,6 +343,25 @@ impl<'a> Parser<'a> {
             "textstyle" => self.style_change(S::Text),
             "scriptstyle" => self.style_change(S::Script),
             "scriptscriptstyle" => self.style_change(S::ScriptScript),
+            "cramped" => {
+                let str = self.current_string();
+                let style = match lex::optional_argument(str)?.map(str::trim) {
+                    None => None,
+                    Some(r"\displaystyle") => Some(S::Display),
+                    Some(r"\textstyle") => Some(S::Text),
+                    Some(r"\scriptstyle") => Some(S::Script),
+                    Some(r"\scriptscriptstyle") => Some(S::ScriptScript),
+                    Some(_) => return Err(ErrorKind::Argument),
+                };
+                let argument = lex::argument(str)?;
+                self.buffer.push(I::Event(E::Begin(G::Normal)));
+                if let Some(style) = style {
+                    self.buffer.push(I::Event(E::StateChange(SC::Style(style))));
+                }
+                self.buffer.push(I::Event(E::StateChange(SC::Cramped)));
+                self.handle_argument(argument)?;
+                E::End
+            }
 
             ////////////////////////
             // Color state change //
@@ -432,6 +402,35 @@ impl<'a> Parser<'a> {
                 self.handle_argument(modified)?;
                 E::End
             }
+            "htmlClass" | "htmlId" | "htmlStyle" => {
+                if !self.config.trust {
+                    return Err(ErrorKind::Untrusted);
+                }
+                let str = self.current_string();
+                let Argument::Group(value) = lex::argument(str)? else {
+                    return Err(ErrorKind::Argument);
+                };
+                let content = lex::argument(str)?;
+                let kind = match control_sequence {
+                    "htmlClass" => AK::Class,
+                    "htmlId" => AK::Id,
+                    _ => AK::Style,
+                };
+                self.buffer.push(I::Event(E::Annotation { kind, value }));
+                return self.handle_argument(content);
+            }
+            "href" => {
+                let str = self.current_string();
+                let Argument::Group(url) = lex::argument(str)? else {
+                    return Err(ErrorKind::Argument);
+                };
+                let content = lex::argument(str)?;
+                if !self.config.trust && !is_safe_url(url) {
+                    return Err(ErrorKind::UntrustedUrl);
+                }
+                self.buffer.push(I::Event(E::Link { url: url.trim() }));
+                return self.handle_argument(content);
+            }
             "colorbox" => {
                 let Argument::Group(color) =
                     lex::argument(self.current_string())?
@@ -448,6 +447,120 @@ impl<'a> Parser<'a> {
                 self.text_argument()?;
                 E::End
             }
+            "mathord" | "mathop" | "mathbin" | "mathrel" | "mathopen" | "mathclose"
+            | "mathpunct" | "mathinner" => {
+                let class = match control_sequence {
+                    "mathord" => AC::Ordinary,
+                    "mathop" => {
+                        self.state.allow_suffix_modifiers = true;
+                        self.state.above_below_suffix_default = true;
+                        AC::Operator
+                    }
+                    "mathbin" => AC::Binary,
+                    "mathrel" => AC::Relation,
+                    "mathopen" => AC::Opening,
+                    "mathclose" => AC::Closing,
+                    "mathpunct" => AC::Punctuation,
+                    "mathinner" => AC::Inner,
+                    _ => unreachable!(),
+                };
+                self.buffer.push(I::Event(E::Begin(G::AtomClass(class))));
+                match lex::argument(self.current_string())? {
+                    Argument::Group(group) => self.group_content(group, false),
+                    argument => self.handle_argument(argument)?,
+                }
+                E::End
+            }
+            "boxed" => {
+                let argument = lex::argument(self.current_string())?;
+                self.buffer.push(I::Event(E::Visual(V::Boxed)));
+                self.handle_argument(argument)?;
+                return Ok(());
+            }
+            "fbox" => {
+                self.buffer.push(I::Event(E::Visual(V::Framed {
+                    width: None,
+                    alignment: BA::Center,
+                })));
+                return self.text_argument();
+            }
+            "framebox" => {
+                let str = self.current_string();
+                let width = match lex::optional_argument(str)? {
+                    Some(mut width) => {
+                        let dimension = lex::dimension(&mut width)?;
+                        if !width.trim().is_empty() {
+                            return Err(ErrorKind::DimensionArgument);
+                        }
+                        Some(dimension)
+                    }
+                    None => None,
+                };
+                let alignment = match lex::optional_argument(str)?.map(str::trim) {
+                    Some("l") => BA::Left,
+                    Some("r") => BA::Right,
+                    // Stretching the interword spaces of the content is not supported, so it is
+                    // centered instead.
+                    Some("c" | "s") | None => BA::Center,
+                    Some(_) => return Err(ErrorKind::Argument),
+                };
+                self.buffer.push(I::Event(E::Visual(V::Framed { width, alignment })));
+                return self.text_argument();
+            }
+            "rlap" => {
+                self.buffer.push(I::Event(E::Visual(V::Lap(LD::Right))));
+                return self.text_argument();
+            }
+            "llap" => {
+                self.buffer.push(I::Event(E::Visual(V::Lap(LD::Left))));
+                return self.text_argument();
+            }
+            "clap" => {
+                self.buffer.push(I::Event(E::Visual(V::Lap(LD::Center))));
+                return self.text_argument();
+            }
+            "mathrlap" => {
+                self.buffer.push(I::Event(E::Visual(V::Lap(LD::Right))));
+                let argument = lex::argument(self.current_string())?;
+                return self.handle_argument(argument);
+            }
+            "mathllap" => {
+                self.buffer.push(I::Event(E::Visual(V::Lap(LD::Left))));
+                let argument = lex::argument(self.current_string())?;
+                return self.handle_argument(argument);
+            }
+            "mathclap" => {
+                self.buffer.push(I::Event(E::Visual(V::Lap(LD::Center))));
+                let argument = lex::argument(self.current_string())?;
+                return self.handle_argument(argument);
+            }
+            "raise" | "lower" => {
+                let str = self.current_string();
+                let (value, unit) = lex::dimension(str)?;
+                let value = if control_sequence == "lower" { -value } else { value };
+                self.buffer.push(I::Event(E::Visual(V::Raise((value, unit)))));
+                let argument = lex::argument(self.current_string())?;
+                return self.handle_argument(argument);
+            }
+            "raisebox" => {
+                let Argument::Group(mut dimension) = lex::argument(self.current_string())? else {
+                    return Err(ErrorKind::DimensionArgument);
+                };
+                let dimension = lex::dimension(&mut dimension).and_then(|dim| {
+                    if dimension.trim().is_empty() {
+                        Ok(dim)
+                    } else {
+                        Err(ErrorKind::DimensionArgument)
+                    }
+                })?;
+                self.buffer.push(I::Event(E::Visual(V::Raise(dimension))));
+                return self.text_argument();
+            }
+            "vcenter" => {
+                let argument = lex::argument(self.current_string())?;
+                self.buffer.push(I::Event(E::Visual(V::VCenter)));
+                return self.handle_argument(argument);
+            }
             "fcolorbox" => {
                 let str = self.current_string();
                 let Argument::Group(frame_color) =
@@ -465,7 +578,7 @@ impl<'a> Parser<'a> {
                 }
                 self.buffer.extend([I::Event(E::Begin(G::Normal)), I::Event(E::StateChange(SC::Color(CC {
                     color: frame_color,
-                    target: CT::Text,
+                    target: CT::Border,
                 }))), I::Event(E::StateChange(SC::Color(CC {
                     color: background_color,
                     target: CT::Background,
@@ -479,10 +592,23 @@ impl<'a> Parser<'a> {
             ///////////////////////////////
             // Sizes taken from `texzilla`
             // Big left and right seem to not care about which delimiter is used. i.e., \bigl) and \bigr) are the same.
-            "big" | "bigl" | "bigr" | "bigm" => return self.em_sized_delim(1.2),
-            "Big" | "Bigl" | "Bigr" | "Bigm" => return self.em_sized_delim(1.8),
-            "bigg" | "biggl" | "biggr" | "biggm" => return self.em_sized_delim(2.4),
-            "Bigg" | "Biggl" | "Biggr" | "Biggm" => return self.em_sized_delim(3.0),
+            "big" => return self.em_sized_delim(1.2, None),
+            "Big" => return self.em_sized_delim(1.8, None),
+            "bigg" => return self.em_sized_delim(2.4, None),
+            "Bigg" => return self.em_sized_delim(3.0, None),
+            // The `l`, `r` and `m` variants are opening, closing and relation atoms respectively.
+            "bigl" => return self.em_sized_delim(1.2, Some(AC::Opening)),
+            "Bigl" => return self.em_sized_delim(1.8, Some(AC::Opening)),
+            "biggl" => return self.em_sized_delim(2.4, Some(AC::Opening)),
+            "Biggl" => return self.em_sized_delim(3.0, Some(AC::Opening)),
+            "bigr" => return self.em_sized_delim(1.2, Some(AC::Closing)),
+            "Bigr" => return self.em_sized_delim(1.8, Some(AC::Closing)),
+            "biggr" => return self.em_sized_delim(2.4, Some(AC::Closing)),
+            "Biggr" => return self.em_sized_delim(3.0, Some(AC::Closing)),
+            "bigm" => return self.em_sized_delim(1.2, Some(AC::Relation)),
+            "Bigm" => return self.em_sized_delim(1.8, Some(AC::Relation)),
+            "biggm" => return self.em_sized_delim(2.4, Some(AC::Relation)),
+            "Biggm" => return self.em_sized_delim(3.0, Some(AC::Relation)),
 
             "left" => {
                 let curr_str = self.current_string();
@@ -509,7 +635,7 @@ impl<'a> Parser<'a> {
                     Some(E::Content(C::Operator(op!(delimiter))))
                 };
 
-                self.buffer.push(I::SubGroup { content: group_content, allows_alignment: false });
+                self.group_content(group_content, true);
                 if let Some(delim) = delim {
                     self.buffer.push(I::Event(delim));
                 }
@@ -518,8 +644,13 @@ impl<'a> Parser<'a> {
                 return Ok(());
             }
             "middle" => {
+                if !self.allows_middle() {
+                    return Err(ErrorKind::MiddleOutsideLeftRight);
+                }
                 let delimiter = lex::delimiter(self.current_string())?;
-                operator(op!(delimiter))
+                // The delimiter is not at the edge of the group, so it must be explicitly
+                // stretched to the height of the whole group.
+                operator(op!(delimiter, {stretchy: Some(true)}))
             }
             "right" => {
                 return Err(ErrorKind::UnbalancedGroup(None));
@@ -529,46 +660,15 @@ impl<'a> Parser<'a> {
             // Big Operators //
             ///////////////////
             // NOTE: All of the following operators allow limit modifiers.
-            // The following operators have above and below limits by default.
-            "sum" => self.big_operator(op!('∑', {deny_movable_limits: true}), true),
-            "prod" => self.big_operator(op!('∏', {deny_movable_limits: true}), true),
-            "coprod" => self.big_operator(op!('∐', {deny_movable_limits: true}), true),
-            "bigvee" => self.big_operator(op!('⋁', {deny_movable_limits: true}), true),
-            "bigwedge" => self.big_operator(op!('⋀', {deny_movable_limits: true}), true),
-            "bigcup" => self.big_operator(op!('⋃', {deny_movable_limits: true}), true),
-            "bigcap" => self.big_operator(op!('⋂', {deny_movable_limits: true}), true),
-            "biguplus" => self.big_operator(op!('⨄', {deny_movable_limits: true}), true),
-            "bigoplus" => self.big_operator(op!('⨁', {deny_movable_limits: true}), true),
-            "bigotimes" => self.big_operator(op!('⨂', {deny_movable_limits: true}), true),
-            "bigodot" => self.big_operator(op!('⨀', {deny_movable_limits: true}), true),
-            "bigsqcup" => self.big_operator(op!('⨆', {deny_movable_limits: true}), true),
-            "bigsqcap" => self.big_operator(op!('⨅', {deny_movable_limits: true}), true),
-            "bigtimes" => self.big_operator(op!('⨉', {deny_movable_limits: true}), true),
+            cs if symbols::large_operator(cs).is_some() => {
+                let (content, above_below) = symbols::large_operator(cs).unwrap();
+                // Only the operators with above and below limits by default deny movable limits.
+                self.big_operator(op!(content, {deny_movable_limits: above_below}), above_below)
+            }
             "intop" => self.big_operator(op!('∫'), true),
-            // The following operators do not have above and below limits by default.
-            "int" => self.big_operator(op!('∫'), false),
-            "iint" => self.big_operator(op!('∬'), false),
-            "iiint" => self.big_operator(op!('∭'), false),
             "smallint" => {
                 self.big_operator(op!('∫', {size: Some((0.7, DimensionUnit::Em))}), false)
             }
-            "iiiint" => self.big_operator(op!('⨌'), false),
-            "intcap" => self.big_operator(op!('⨙'), false),
-            "intcup" => self.big_operator(op!('⨚'), false),
-            "oint" => self.big_operator(op!('∮'), false),
-            "varointclockwise" => self.big_operator(op!('∲'), false),
-            "intclockwise" => self.big_operator(op!('∱'), false),
-            "oiint" => self.big_operator(op!('∯'), false),
-            "pointint" => self.big_operator(op!('⨕'), false),
-            "rppolint" => self.big_operator(op!('⨒'), false),
-            "scpolint" => self.big_operator(op!('⨓'), false),
-            "oiiint" => self.big_operator(op!('∰'), false),
-            "intlarhk" => self.big_operator(op!('⨗'), false),
-            "sqint" => self.big_operator(op!('⨖'), false),
-            "intx" => self.big_operator(op!('⨘'), false),
-            "intbar" => self.big_operator(op!('⨍'), false),
-            "intBar" => self.big_operator(op!('⨎'), false),
-            "fint" => self.big_operator(op!('⨏'), false),
 
             /////////////
             // Accents //
@@ -580,6 +680,8 @@ impl<'a> Parser<'a> {
             "check" => return self.accent(op!('ˇ', {stretchy: Some(false)})),
             "dot" => return self.accent(op!('˙')),
             "ddot" => return self.accent(op!('¨')),
+            "dddot" => return self.accent(op!('\u{20DB}')),
+            "ddddot" => return self.accent(op!('\u{20DC}')),
             "grave" => return self.accent(op!('`')),
             "hat" => return self.accent(op!('^', {stretchy: Some(false)})),
             "tilde" => return self.accent(op!('~', {stretchy: Some(false)})),
@@ -606,18 +708,29 @@ impl<'a> Parser<'a> {
             // Groups
             "overgroup" => return self.accent(op!('⏠')),
             "undergroup" => return self.underscript(op!('⏡')),
-            "overbrace" => return self.accent(op!('⏞')),
-            "underbrace" => return self.underscript(op!('⏟')),
+            // Braces are stretched to the width of their argument, and their scripts are set
+            // above and below them, as if followed by `\limits`.
+            "overbrace" => {
+                self.overscript(op!('⏞', {stretchy: Some(true)}))?;
+                self.state.limits = true;
+                self.state.allow_suffix_modifiers = true;
+                return Ok(());
+            }
+            "underbrace" => {
+                self.underscript(op!('⏟', {stretchy: Some(true)}))?;
+                self.state.limits = true;
+                self.state.allow_suffix_modifiers = true;
+                return Ok(());
+            }
             "underparen" => return self.underscript(op!('⏝')),
-
-            // Primes
-            "prime" => operator(op!('′')),
-            "dprime" => operator(op!('″')),
-            "trprime" => operator(op!('‴')),
-            "qprime" => operator(op!('⁗')),
-            "backprime" => operator(op!('‵')),
-            "backdprime" => operator(op!('‶')),
-            "backtrprime" => operator(op!('‷')),
+            "overbracket" => {
+                self.bracket_dimensions()?;
+                return self.accent(op!('⎴'));
+            }
+            "underbracket" => {
+                self.bracket_dimensions()?;
+                return self.underscript(op!('⎵'));
+            }
 
             /////////////
             // Spacing //
@@ -657,7 +770,7 @@ impl<'a> Parser<'a> {
                 height: Some((0.7, DimensionUnit::Em)),
                 depth: Some((0.3, DimensionUnit::Em)),
             },
-            "~" | "nobreakspace" => E::Content(C::Text("&nbsp;")),
+            "~" | "nobreakspace" => self.non_breaking_space(),
             // Variable spacing
             "kern" => {
                 let dimension = lex::dimension(self.current_string())?;
@@ -691,8 +804,8 @@ impl<'a> Parser<'a> {
             "mskip" => {
                 let glue = lex::glue(self.current_string())?;
                 if glue.0.1 == DimensionUnit::Mu
-                    && glue.1.map_or(true, |(_, unit)| unit == DimensionUnit::Mu)
-                    && glue.2.map_or(true, |(_, unit)| unit == DimensionUnit::Mu) {
+                    && matches!(glue.1, None | Some((_, DimensionUnit::Mu)))
+                    && matches!(glue.2, None | Some((_, DimensionUnit::Mu))) {
                     E::Space {
                         width: Some(glue.0),
                         height: None,
@@ -732,254 +845,17 @@ impl<'a> Parser<'a> {
                 depth: None,
             },
 
-            ////////////////////////
-            // Logic & Set Theory //
-            ////////////////////////
-            "forall" => operator(op!('∀')),
-            "complement" => operator(op!('∁')),
-            "therefore" => operator(op!('∴')),
-            "emptyset" => operator(op!('∅')),
-            "exists" => operator(op!('∃')),
-            "subset" => operator(op!('⊂')),
-            "because" => operator(op!('∵')),
-            "varnothing" => operator(op!('⌀')),
-            "nexists" => operator(op!('∄')),
-            "supset" => operator(op!('⊃')),
-            "mapsto" => operator(op!('↦')),
-            "implies" => operator(op!('⟹')),
-            "in" | "isin" => operator(op!('∈')),
-            "mid" => operator(op!('∣')),
-            "to" => operator(op!('→')),
-            "impliedby" => operator(op!('⟸')),
-            "ni" => operator(op!('∋')),
-            "land" => operator(op!('∧')),
-            "gets" => operator(op!('←')),
-            "iff" => operator(op!('⟺')),
-            "notni" => operator(op!('∌')),
-            "neg" | "lnot" => operator(op!('¬')),
-            "strictif" => operator(op!('⥽')),
-            "strictfi" => operator(op!('⥼')),
-
             //////////////////////
             // Binary Operators //
             //////////////////////
-            "ldotp" => operator(op!('.')),
-            "cdotp" => operator(op!('·')),
-            "cdot" => operator(op!('⋅')),
-            "centerdot" => operator(op!('·')),
-            "circ" => operator(op!('∘')),
-            "bullet" => operator(op!('∙')),
-            "circledast" => operator(op!('⊛')),
-            "circledcirc" => operator(op!('⊚')),
-            "circleddash" => operator(op!('⊝')),
-            "bigcirc" => operator(op!('◯')),
-            "leftthreetimes" => operator(op!('⋋')),
-            "rhd" => operator(op!('⊳')),
-            "lhd" => operator(op!('⊲')),
-            "leftouterjoin" => operator(op!('⟕')),
-            "rightouterjoin" => operator(op!('⟖')),
-            "rightthreetimes" => operator(op!('⋌')),
-            "rtimes" => operator(op!('⋊')),
-            "ltimes" => operator(op!('⋉')),
-            "leftmodels" => operator(op!('⊨')),
-            "amalg" => operator(op!('⨿')),
-            "ast" => operator(op!('*')),
-            "asymp" => operator(op!('≍')),
-            "And" => operator(op!('&')),
-            "lor" => operator(op!('∨')),
-            "setminus" => operator(op!('∖')),
-            "Cup" => operator(op!('⋓')),
-            "cup" => operator(op!('∪')),
-            "sqcup" => operator(op!('⊔')),
-            "sqcap" => operator(op!('⊓')),
-            "lessdot" => operator(op!('⋖')),
             "smallsetminus" => operator(op!('∖', {size: Some((0.7, DimensionUnit::Em))})),
-            "barwedge" => operator(op!('⌅')),
-            "curlyvee" => operator(op!('⋎')),
-            "curlywedge" => operator(op!('⋏')),
-            "sslash" => operator(op!('⫽')),
-            "bowtie" | "Join" => operator(op!('⋈')),
-            "div" => operator(op!('÷')),
-            "mp" => operator(op!('∓')),
-            "times" => operator(op!('×')),
-            "boxdot" => operator(op!('⊡')),
-            "divideontimes" => operator(op!('⋇')),
-            "odot" => operator(op!('⊙')),
-            "unlhd" => operator(op!('⊴')),
-            "boxminus" => operator(op!('⊟')),
-            "dotplus" => operator(op!('∔')),
-            "ominus" => operator(op!('⊖')),
-            "unrhd" => operator(op!('⊵')),
-            "boxplus" => operator(op!('⊞')),
-            "doublebarwedge" => operator(op!('⩞')),
-            "oplus" => operator(op!('⊕')),
-            "uplus" => operator(op!('⊎')),
-            "boxtimes" => operator(op!('⊠')),
-            "doublecap" => operator(op!('⋒')),
-            "otimes" => operator(op!('⊗')),
-            "vee" => operator(op!('∨')),
-            "veebar" => operator(op!('⊻')),
-            "Cap" => operator(op!('⋒')),
-            "fullouterjoin" => operator(op!('⟗')),
-            "parr" => operator(op!('⅋')),
-            "wedge" => operator(op!('∧')),
-            "cap" => operator(op!('∩')),
-            "gtrdot" => operator(op!('⋗')),
-            "pm" => operator(op!('±')),
-            "with" => operator(op!('&')),
-            "intercal" => operator(op!('⊺')),
-            "wr" => operator(op!('≀')),
-            "circledvert" => operator(op!('⦶')),
-            "blackhourglass" => operator(op!('⧗')),
-            "circlehbar" => operator(op!('⦵')),
-            "operp" => operator(op!('⦹')),
-            "boxast" => operator(op!('⧆')),
-            "concavediamond" => operator(op!('⟡')),
-            "boxbox" => operator(op!('⧈')),
-            "concavediamondtickleft" => operator(op!('⟢')),
-            "oslash" => operator(op!('⊘')),
-            "boxcircle" => operator(op!('⧇')),
-            "concavediamondtickright" => operator(op!('⟣')),
-            "diamond" => operator(op!('⋄')),
-            "Otimes" => operator(op!('⨷')),
-            "hourglass" => operator(op!('⧖')),
-            "otimeshat" => operator(op!('⨶')),
-            "triangletimes" => operator(op!('⨻')),
-            "lozengeminus" => operator(op!('⟠')),
-            "star" => operator(op!('⋆')),
-            "obar" => operator(op!('⌽')),
-            "triangle" | "vartriangle" => operator(op!('△')),
-            "obslash" => operator(op!('⦸')),
-            "triangleminus" => operator(op!('⨺')),
-            "odiv" => operator(op!('⨸')),
-            "triangleplus" => operator(op!('⨹')),
-            "circledequal" => operator(op!('⊜')),
-            "ogreaterthan" => operator(op!('⧁')),
-            "whitesquaretickleft" => operator(op!('⟤')),
-            "circledparallel" => operator(op!('⦷')),
-            "olessthan" => operator(op!('⧀')),
-            "whitesquaretickright" => operator(op!('⟥')),
 
             ///////////////
             // Relations //
             ///////////////
-            "eqcirc" => operator(op!('≖')),
-            "lessgtr" => operator(op!('≶')),
-            "smile" | "sincoh" => operator(op!('⌣')),
-            "eqcolon" | "minuscolon" => operator(op!('∹')),
-            "lesssim" => operator(op!('≲')),
-            "sqsubset" => operator(op!('⊏')),
-            "ll" => operator(op!('≪')),
-            "sqsubseteq" => operator(op!('⊑')),
-            "eqqcolon" => operator(op!('≕')),
-            "lll" => operator(op!('⋘')),
-            "sqsupset" => operator(op!('⊐')),
-            "llless" => operator(op!('⋘')),
-            "sqsupseteq" => operator(op!('⊒')),
-            "approx" => operator(op!('≈')),
-            "eqdef" => operator(op!('≝')),
-            "lt" => operator(op!('<')),
-            "stareq" => operator(op!('≛')),
-            "approxeq" => operator(op!('≊')),
-            "eqsim" => operator(op!('≂')),
-            "measeq" => operator(op!('≞')),
-            "Subset" => operator(op!('⋐')),
-            "arceq" => operator(op!('≘')),
-            "eqslantgtr" => operator(op!('⪖')),
-            "eqslantless" => operator(op!('⪕')),
-            "models" => operator(op!('⊨')),
-            "subseteq" => operator(op!('⊆')),
-            "backcong" => operator(op!('≌')),
-            "equiv" => operator(op!('≡')),
-            "multimap" => operator(op!('⊸')),
-            "subseteqq" => operator(op!('⫅')),
-            "fallingdotseq" => operator(op!('≒')),
-            "multimapboth" => operator(op!('⧟')),
-            "succ" => operator(op!('≻')),
-            "backsim" => operator(op!('∽')),
-            "frown" => operator(op!('⌢')),
-            "multimapinv" => operator(op!('⟜')),
-            "succapprox" => operator(op!('⪸')),
-            "backsimeq" => operator(op!('⋍')),
-            "ge" => operator(op!('≥')),
-            "origof" => operator(op!('⊶')),
-            "succcurlyeq" => operator(op!('≽')),
-            "between" => operator(op!('≬')),
-            "geq" => operator(op!('≥')),
-            "owns" => operator(op!('∋')),
-            "succeq" => operator(op!('⪰')),
-            "bumpeq" => operator(op!('≏')),
-            "geqq" => operator(op!('≧')),
-            "parallel" => operator(op!('∥')),
-            "succsim" => operator(op!('≿')),
-            "Bumpeq" => operator(op!('≎')),
-            "geqslant" => operator(op!('⩾')),
-            "perp" => operator(op!('⟂')),
-            "Supset" => operator(op!('⋑')),
-            "circeq" => operator(op!('≗')),
-            "gg" => operator(op!('≫')),
-            "Perp" => operator(op!('⫫')),
-            "coh" => operator(op!('⌢')),
-            "ggg" => operator(op!('⋙')),
-            "pitchfork" => operator(op!('⋔')),
-            "supseteq" => operator(op!('⊇')),
-            "gggtr" => operator(op!('⋙')),
-            "prec" => operator(op!('≺')),
-            "supseteqq" => operator(op!('⫆')),
-            "gt" => operator(op!('>')),
-            "precapprox" => operator(op!('⪷')),
-            "thickapprox" => operator(op!('≈')),
-            "gtrapprox" => operator(op!('⪆')),
-            "preccurlyeq" => operator(op!('≼')),
-            "thicksim" => operator(op!('∼')),
-            "gtreqless" => operator(op!('⋛')),
-            "preceq" => operator(op!('⪯')),
-            "trianglelefteq" => operator(op!('⊴')),
-            "coloneqq" | "colonequals" => operator(op!('≔')),
-            "gtreqqless" => operator(op!('⪌')),
-            "precsim" => operator(op!('≾')),
-            "triangleq" => operator(op!('≜')),
-            "Coloneqq" | "coloncolonequals" => operator(op!('⩴')),
-            "gtrless" => operator(op!('≷')),
-            "propto" => operator(op!('∝')),
-            "trianglerighteq" => operator(op!('⊵')),
-            "gtrsim" => operator(op!('≳')),
-            "questeq" => operator(op!('≟')),
-            "varpropto" => operator(op!('∝')),
-            "imageof" => operator(op!('⊷')),
-            "cong" => operator(op!('≅')),
-            "risingdotseq" => operator(op!('≓')),
-            "vartriangleleft" => operator(op!('⊲')),
-            "curlyeqprec" => operator(op!('⋞')),
-            "scoh" => operator(op!('⌢')),
-            "vartriangleright" => operator(op!('⊳')),
-            "curlyeqsucc" => operator(op!('⋟')),
-            "le" => operator(op!('≤')),
             "shortmid" => operator(op!('∣', {size:Some((0.7, DimensionUnit::Em))})),
             "shortparallel" => operator(op!('∥', {size:Some((0.7, DimensionUnit::Em))})),
-            "vdash" => operator(op!('⊢')),
-            "dashv" => operator(op!('⊣')),
-            "leq" => operator(op!('≤')),
-            "vDash" => operator(op!('⊨')),
-            "dblcolon" | "coloncolon" => operator(op!('∷')),
-            "leqq" => operator(op!('≦')),
-            "sim" => operator(op!('∼')),
-            "Vdash" => operator(op!('⊩')),
-            "doteq" => operator(op!('≐')),
-            "leqslant" => operator(op!('⩽')),
-            "simeq" => operator(op!('≃')),
-            "Dash" => operator(op!('⊫')),
-            "Doteq" => operator(op!('≑')),
-            "lessapprox" => operator(op!('⪅')),
-            "Vvdash" => operator(op!('⊪')),
-            "doteqdot" => operator(op!('≑')),
-            "lesseqgtr" => operator(op!('⋚')),
-            "smallfrown" => operator(op!('⌢')),
-            "veeeq" => operator(op!('≚')),
-            "eqeq" => operator(op!('⩵')),
-            "lesseqqgtr" => operator(op!('⪋')),
             "smallsmile" => operator(op!('⌣', {size:Some((0.7, DimensionUnit::Em))})),
-            "wedgeq" => operator(op!('≙')),
             "Eqcolon" | "minuscoloncolon" => {
                 self.multi_event([
                     E::Content(C::Operator(
@@ -1130,165 +1006,34 @@ impl<'a> Parser<'a> {
                 return Ok(());
             }
             // Negated relations
-            "gnapprox" => operator(op!('⪊')),
-            "ngeqslant" => operator(op!('≱')),
-            "nsubset" => operator(op!('⊄')),
-            "nVdash" => operator(op!('⊮')),
-            "gneq" => operator(op!('⪈')),
-            "ngtr" => operator(op!('≯')),
-            "nsubseteq" => operator(op!('⊈')),
-            "precnapprox" => operator(op!('⪹')),
-            "gneqq" => operator(op!('≩')),
-            "nleq" => operator(op!('≰')),
-            "nsubseteqq" => operator(op!('⊈')),
-            "precneqq" => operator(op!('⪵')),
-            "gnsim" => operator(op!('⋧')),
-            "nleqq" => operator(op!('≰')),
-            "nsucc" => operator(op!('⊁')),
-            "precnsim" => operator(op!('⋨')),
-            "nleqslant" => operator(op!('≰')),
-            "nsucceq" => operator(op!('⋡')),
-            "subsetneq" => operator(op!('⊊')),
-            "lnapprox" => operator(op!('⪉')),
-            "nless" => operator(op!('≮')),
-            "nsupset" => operator(op!('⊅')),
-            "subsetneqq" => operator(op!('⫋')),
-            "lneq" => operator(op!('⪇')),
-            "nmid" => operator(op!('∤')),
-            "nsupseteq" => operator(op!('⊉')),
-            "succnapprox" => operator(op!('⪺')),
-            "lneqq" => operator(op!('≨')),
-            "notin" => operator(op!('∉')),
-            "nsupseteqq" => operator(op!('⊉')),
-            "succneqq" => operator(op!('⪶')),
-            "lnsim" => operator(op!('⋦')),
-            "ntriangleleft" => operator(op!('⋪')),
-            "succnsim" => operator(op!('⋩')),
-            "nparallel" => operator(op!('∦')),
-            "ntrianglelefteq" => operator(op!('⋬')),
-            "supsetneq" => operator(op!('⊋')),
-            "ncong" => operator(op!('≆')),
-            "nprec" => operator(op!('⊀')),
-            "ntriangleright" => operator(op!('⋫')),
-            "supsetneqq" => operator(op!('⫌')),
-            "ne" => operator(op!('≠')),
-            "npreceq" => operator(op!('⋠')),
-            "ntrianglerighteq" => operator(op!('⋭')),
-            "neq" => operator(op!('≠')),
-            "nshortmid" => operator(op!('∤')),
-            "nvdash" => operator(op!('⊬')),
-            "ngeq" => operator(op!('≱')),
             "nshortparallel" => operator(op!('∦', {size: Some((0.7, DimensionUnit::Em))})),
-            "nvDash" => operator(op!('⊭')),
-            "varsupsetneq" => operator(op!('⊋')),
-            "ngeqq" => operator(op!('≱')),
-            "nsim" => operator(op!('≁')),
-            "nVDash" => operator(op!('⊯')),
             "varsupsetneqq" => operator(op!('⫌', {unicode_variant: true})),
             "varsubsetneqq" => operator(op!('⫋', {unicode_variant: true})),
             "varsubsetneq" => operator(op!('⊊', {unicode_variant: true})),
             "gvertneqq" => operator(op!('≩', {unicode_variant: true})),
             "lvertneqq" => operator(op!('≨', {unicode_variant: true})),
 
-            ////////////
-            // Arrows //
-            ////////////
-            "circlearrowleft" => operator(op!('↺')),
-            "Leftrightarrow" => operator(op!('⇔')),
-            "restriction" => operator(op!('↾')),
-            "circlearrowright" => operator(op!('↻')),
-            "leftrightarrows" => operator(op!('⇆')),
-            "rightarrow" => operator(op!('→')),
-            "curvearrowleft" => operator(op!('↶')),
-            "leftrightharpoons" => operator(op!('⇋')),
-            "Rightarrow" => operator(op!('⇒')),
-            "curvearrowright" => operator(op!('↷')),
-            "leftrightsquigarrow" => operator(op!('↭')),
-            "rightarrowtail" => operator(op!('↣')),
-            "dashleftarrow" => operator(op!('⇠')),
-            "Lleftarrow" => operator(op!('⇚')),
-            "rightharpoondown" => operator(op!('⇁')),
-            "dashrightarrow" => operator(op!('⇢')),
-            "longleftarrow" => operator(op!('⟵')),
-            "rightharpoonup" => operator(op!('⇀')),
-            "downarrow" => operator(op!('↓')),
-            "Longleftarrow" => operator(op!('⟸')),
-            "rightleftarrows" => operator(op!('⇄')),
-            "Downarrow" => operator(op!('⇓')),
-            "longleftrightarrow" => operator(op!('⟷')),
-            "rightleftharpoons" => operator(op!('⇌')),
-            "downdownarrows" => operator(op!('⇊')),
-            "Longleftrightarrow" => operator(op!('⟺')),
-            "rightrightarrows" => operator(op!('⇉')),
-            "downharpoonleft" => operator(op!('⇃')),
-            "longmapsto" => operator(op!('⟼')),
-            "rightsquigarrow" => operator(op!('⇝')),
-            "downharpoonright" => operator(op!('⇂')),
-            "longrightarrow" => operator(op!('⟶')),
-            "Rrightarrow" => operator(op!('⇛')),
-            "Longrightarrow" => operator(op!('⟹')),
-            "Rsh" => operator(op!('↱')),
-            "hookleftarrow" => operator(op!('↩')),
-            "looparrowleft" => operator(op!('↫')),
-            "searrow" => operator(op!('↘')),
-            "hookrightarrow" => operator(op!('↪')),
-            "looparrowright" => operator(op!('↬')),
-            "swarrow" => operator(op!('↙')),
-            "Lsh" => operator(op!('↰')),
-            "mapsfrom" => operator(op!('↤')),
-            "twoheadleftarrow" => operator(op!('↞')),
-            "twoheadrightarrow" => operator(op!('↠')),
-            "leadsto" => operator(op!('⇝')),
-            "nearrow" => operator(op!('↗')),
-            "uparrow" => operator(op!('↑')),
-            "leftarrow" => operator(op!('←')),
-            "nleftarrow" => operator(op!('↚')),
-            "Uparrow" => operator(op!('⇑')),
-            "Leftarrow" => operator(op!('⇐')),
-            "nLeftarrow" => operator(op!('⇍')),
-            "updownarrow" => operator(op!('↕')),
-            "leftarrowtail" => operator(op!('↢')),
-            "nleftrightarrow" => operator(op!('↮')),
-            "Updownarrow" => operator(op!('⇕')),
-            "leftharpoondown" => operator(op!('↽')),
-            "nLeftrightarrow" => operator(op!('⇎')),
-            "upharpoonleft" => operator(op!('↿')),
-            "leftharpoonup" => operator(op!('↼')),
-            "nrightarrow" => operator(op!('↛')),
-            "upharpoonright" => operator(op!('↾')),
-            "leftleftarrows" => operator(op!('⇇')),
-            "nRightarrow" => operator(op!('⇏')),
-            "upuparrows" => operator(op!('⇈')),
-            "leftrightarrow" => operator(op!('↔')),
-            "nwarrow" => operator(op!('↖')),
-
             ///////////////
             // Fractions //
             ///////////////
+            // Infix commands are handled when the content of their group is pushed, reaching
+            // them here means that either their arguments are invalid, or that there is more than
+            // one of them in the group.
+            "over" | "atop" | "above" | "choose" | "overwithdelims" | "atopwithdelims"
+            | "abovewithdelims" => {
+                lex::infix_arguments(control_sequence, self.current_string())?;
+                return Err(ErrorKind::AmbiguousInfix);
+            }
             "frac" => {
-                return self.fraction_like(None);
+                return self.fraction_like(V::Fraction(None));
             }
             // TODO: better errors for this
             "genfrac" => {
                 let str = self.current_string();
                 let ldelim_argument = lex::argument(str)?;
-                let ldelim = match ldelim_argument {
-                    Argument::Token(token) => Some(token_to_delim(token).ok_or(ErrorKind::Delimiter)?),
-                    Argument::Group(group) => if group.is_empty() {
-                        None
-                    } else {
-                        return Err(ErrorKind::Delimiter);
-                    },
-                };
+                let ldelim = optional_delimiter(ldelim_argument)?;
                 let rdelim_argument = lex::argument(str)?;
-                let rdelim = match rdelim_argument {
-                    Argument::Token(token) => Some(token_to_delim(token).ok_or(ErrorKind::Delimiter)?),
-                    Argument::Group(group) => if group.is_empty() {
-                        None
-                    } else {
-                        return Err(ErrorKind::Delimiter);
-                    },
-                };
+                let rdelim = optional_delimiter(rdelim_argument)?;
                 let bar_size_argument = lex::argument(str)?;
                 let bar_size = match bar_size_argument {
                     Argument::Token(_) => return Err(ErrorKind::DimensionArgument),
@@ -1333,7 +1078,7 @@ impl<'a> Parser<'a> {
                     self.buffer.push(I::Event(E::Content(C::Operator(op!(ldelim)))));
                 }
                 
-                self.fraction_like(bar_size)?;
+                self.fraction_like(V::Fraction(bar_size))?;
                 
                 if let Some(rdelim) = rdelim {
                     self.buffer.push(I::Event(E::Content(C::Operator(op!(rdelim)))));
@@ -1344,29 +1089,50 @@ impl<'a> Parser<'a> {
             "binom" => {
                 self.buffer.extend([I::Event(E::Begin(G::LeftRight)),
                                     I::Event(E::Content(C::Operator(op!('('))))]);
-                self.fraction_like(None)?;
+                self.fraction_like(V::Fraction(None))?;
                 self.buffer.extend([I::Event(E::Content(C::Operator(op!(')')))),
                                     I::Event(E::End)]);
                 return Ok(())
             }
             "cfrac" => {
+                let alignment = match lex::optional_argument(self.current_string())?.map(str::trim) {
+                    Some("l") => BA::Left,
+                    Some("r") => BA::Right,
+                    Some("c") | None => BA::Center,
+                    Some(_) => return Err(ErrorKind::Argument),
+                };
                 self.buffer.extend([I::Event(E::Begin(G::Internal)),
                                     I::Event(E::StateChange(SC::Style(S::Display)))]);
-                self.fraction_like(None)?;
+                // Unlike other fractions, the numerator and denominator stay in display style.
+                self.fraction_arguments(V::ContinuedFraction(alignment), S::Display)?;
                 self.buffer.push(I::Event(E::End));
                 return Ok(())
             }
             "tfrac" => {
                 self.buffer.extend([I::Event(E::Begin(G::Internal)),
                                     I::Event(E::StateChange(SC::Style(S::Text)))]);
-                self.fraction_like(None)?;
+                self.fraction_like(V::Fraction(None))?;
                 self.buffer.push(I::Event(E::End));
                 return Ok(())
             }
             "dfrac" => {
                 self.buffer.extend([I::Event(E::Begin(G::Internal)),
-                                    I::Event(E::StateChange(SC::Style(S::Script)))]);
-                self.fraction_like(None)?;
+                                    I::Event(E::StateChange(SC::Style(S::Display)))]);
+                self.fraction_like(V::Fraction(None))?;
+                self.buffer.push(I::Event(E::End));
+                return Ok(())
+            }
+            "splitfrac" => {
+                self.buffer.extend([I::Event(E::Begin(G::Internal)),
+                                    I::Event(E::StateChange(SC::Style(S::Text)))]);
+                self.fraction_like(V::SplitFraction)?;
+                self.buffer.push(I::Event(E::End));
+                return Ok(())
+            }
+            "splitdfrac" => {
+                self.buffer.extend([I::Event(E::Begin(G::Internal)),
+                                    I::Event(E::StateChange(SC::Style(S::Display)))]);
+                self.fraction_like(V::SplitFraction)?;
                 self.buffer.push(I::Event(E::End));
                 return Ok(())
             }
@@ -1375,21 +1141,25 @@ impl<'a> Parser<'a> {
                     ty: ST::Superscript,
                     position: SP::AboveBelow,
                 }));
+                // The script comes first in the source, but after the base in the events.
                 let over = lex::argument(self.current_string())?;
-                self.handle_argument(over)?;
                 let base = lex::argument(self.current_string())?;
                 self.handle_argument(base)?;
+                self.handle_argument(over)?;
                 return Ok(());
             }
+            "prescript" => return self.prescript(),
+            "tensor" => return self.tensor(),
             "underset" => {
                 self.buffer.push(I::Event(E::Script {
                     ty: ST::Subscript,
                     position: SP::AboveBelow,
                 }));
+                // The script comes first in the source, but after the base in the events.
                 let under = lex::argument(self.current_string())?;
-                self.handle_argument(under)?;
                 let base = lex::argument(self.current_string())?;
                 self.handle_argument(base)?;
+                self.handle_argument(under)?;
                 return Ok(());
             }
 
@@ -1407,6 +1177,8 @@ impl<'a> Parser<'a> {
                     self.buffer.push(I::SubGroup {
                         content: index,
                         allows_alignment: false,
+                        allows_middle: false,
+                        style: S::ScriptScript,
                     });
                 } else {
                     self.buffer
@@ -1441,11 +1213,61 @@ impl<'a> Parser<'a> {
             ),
             "|" => operator(op!('∥', {stretchy: Some(false)})),
             "text" => return self.text_argument(),
+            "textbf" | "textit" | "textsc" | "textrm" | "textsf" | "texttt" | "emph" => {
+                let font = text_font(control_sequence).expect("the name is a text font command");
+                return self.text_font_argument(font);
+            }
+            "mathchoice" => {
+                let str = self.current_string();
+                let display = lex::argument(str)?;
+                let text = lex::argument(str)?;
+                let script = lex::argument(str)?;
+                let script_script = lex::argument(str)?;
+                return self.handle_argument(match self.buffered_style() {
+                    S::Display => display,
+                    S::Text => text,
+                    S::Script => script,
+                    S::ScriptScript => script_script,
+                });
+            }
+            "hbox" | "mbox" => {
+                // Unlike `\text`, these boxes are not shrunk in scripts.
+                self.buffer.extend([
+                    I::Event(E::Begin(G::Normal)),
+                    I::Event(E::StateChange(SC::Style(S::Text))),
+                ]);
+                self.text_argument()?;
+                E::End
+            }
+            "cancel" | "bcancel" | "xcancel" | "sout" => {
+                self.buffer.push(I::Event(E::Visual(V::Strike(match control_sequence {
+                    "cancel" => SK::UpDiagonal,
+                    "bcancel" => SK::DownDiagonal,
+                    "xcancel" => SK::Cross,
+                    _ => SK::Horizontal,
+                }))));
+                let argument = lex::argument(self.current_string())?;
+                self.handle_argument(argument)?;
+                return Ok(());
+            }
             "not" => {
-                self.buffer
-                    .push(I::Event(E::Visual(V::Negation)));
+                let start = self.buffer.len();
                 let argument = lex::argument(self.current_string())?;
                 self.handle_argument(argument)?;
+                // An argument producing no element, as in `\not\def\a{x}`, leaves nothing to
+                // negate.
+                if self.buffer.len() == start {
+                    return Err(ErrorKind::Argument);
+                }
+                // A relation with a precomposed negation is replaced by it, and anything else is
+                // overlaid with a slash.
+                if let [I::Event(E::Content(C::Operator(operator)))] = &mut self.buffer[start..] {
+                    if let Some(negated) = negated_relation(operator.content) {
+                        operator.content = negated;
+                        return Ok(());
+                    }
+                }
+                self.buffer.insert(start, I::Event(E::Visual(V::Negation)));
                 return Ok(());
             }
             "char" => {
@@ -1457,6 +1279,73 @@ impl<'a> Parser<'a> {
                                 char::from_u32(number as u32).expect("the number is a valid char since it is less than 256")
                                 )))
             },
+            "let" => {
+                let (control_sequence, token) = lex::let_assignment(self.current_string())?;
+                self.macro_context.assign(control_sequence, token);
+                self.state.skip_suffixes = true;
+                return Ok(());
+            }
+            "def" | "gdef" | "edef" | "xdef" => {
+                let (name, parameter_text, replacement_text) =
+                    lex::definition(self.current_string())?;
+                self.macro_context.define(
+                    name,
+                    parameter_text,
+                    replacement_text,
+                    matches!(control_sequence, "edef" | "xdef")
+                        .then_some(self.config.max_nesting_depth),
+                )?;
+                self.state.skip_suffixes = true;
+                return Ok(());
+            }
+            "DeclarePairedDelimiter" => {
+                let (control_sequence, left, right) =
+                    lex::paired_delimiter_declaration(self.current_string())?;
+                self.macro_context
+                    .declare_paired_delimiter(control_sequence, left, right);
+                self.state.skip_suffixes = true;
+                return Ok(());
+            }
+            "futurelet" => {
+                let (control_sequence, _, token) =
+                    lex::futurelet_assignment(self.current_string())?;
+                self.macro_context.assign(control_sequence, token);
+                self.state.skip_suffixes = true;
+                return Ok(());
+            }
+            "if" | "ifx" | "ifnum" | "ifdim" | "iftrue" | "iffalse" => {
+                let condition = self.condition(control_sequence)?;
+                let (true_branch, false_branch) =
+                    lex::conditional_branches(self.current_string())?;
+                let branch = if condition {
+                    Some(true_branch)
+                } else {
+                    false_branch
+                };
+                if let Some(content) = branch {
+                    self.buffer.push(I::SubGroup {
+                        content,
+                        allows_alignment: self.allows_alignment(),
+                        allows_middle: self.allows_middle(),
+                        style: self.buffered_style(),
+                    });
+                }
+                self.state.skip_suffixes = true;
+                return Ok(());
+            }
+            "else" | "or" | "fi" => return Err(ErrorKind::ConditionalEnd),
+            "csname" => {
+                let name = lex::control_sequence_name(self.current_string())?;
+                // An undefined control sequence constructed by `\csname` means `\relax`.
+                if self.macro_context.meaning(name).is_none()
+                    && !self.macro_context.is_macro(name)
+                    && !is_known_primitive(name)
+                {
+                    self.macro_context.assign(name, Token::ControlSequence("relax"));
+                }
+                return self.handle_primitive(name);
+            }
+            "endcsname" => return Err(ErrorKind::ControlSequenceName),
             "relax" => {
                 return if self.state.invalidate_relax {
                     Err(ErrorKind::Relax)
@@ -1471,7 +1360,7 @@ impl<'a> Parser<'a> {
                 let group = lex::group_content(str, "begingroup", "endgroup")?;
                 self.buffer.extend([
                     I::Event(E::Begin(G::Normal)),
-                    I::SubGroup { content: group, allows_alignment: false },
+                    I::SubGroup { content: group, allows_alignment: false, allows_middle: false, style: self.buffered_style() },
                     I::Event(E::End),
                 ]);
                 return Ok(());
@@ -1484,7 +1373,12 @@ impl<'a> Parser<'a> {
                 };
                 let mut closing = None;
                 let environment = match argument {
-                    "array" => Grouping::Array,
+                    "array" => {
+                        let Argument::Group(spec) = lex::argument(self.current_string())? else {
+                            return Err(ErrorKind::ArrayColumns);
+                        };
+                        Grouping::Array(lex::array_columns(spec)?)
+                    }
                     "matrix" => Grouping::Matrix,
                     "pmatrix" => {
                         self.buffer.extend([
@@ -1531,14 +1425,21 @@ impl<'a> Parser<'a> {
                     _ => return Err(ErrorKind::Environment),
                 };
                 // TODO: correctly spot deeper environment of the same type.
-                let content = lex::group_content(
+                let mut content = lex::group_content(
                     self.current_string(),
                     &format!(r"\begin{{{argument}}}"),
                     &format!(r"\end{{{argument}}}")
                 )?;
+                self.buffer.push(I::Event(E::Begin(environment)));
+                // Each cell of the environment may be a fraction formed by an infix command.
+                if lex::contains_infix(content) {
+                    let cell = lex::cell(content);
+                    if self.infix_fraction(cell, false) {
+                        content = &content[cell.len()..];
+                    }
+                }
                 self.buffer.extend([
-                    I::Event(E::Begin(environment)),
-                    I::SubGroup { content, allows_alignment: true },
+                    I::SubGroup { content, allows_alignment: true, allows_middle: false, style: self.buffered_style() },
                     I::Event(E::End)
                 ]);
                 if let Some(closing) = closing {
@@ -1550,17 +1451,48 @@ impl<'a> Parser<'a> {
                 return Ok(());
             }
             "end" => return Err(ErrorKind::UnbalancedGroup(None)),
-            "\\" | "cr" if self.allows_alignment() => E::NewLine,
+            "\\" | "cr" if self.allows_alignment() => {
+                self.buffer.push(I::Event(E::NewLine));
+                self.cell_content();
+                return Ok(());
+            }
+
+            // Symbols
+            cs if symbols::identifier(cs).is_some() => ident(symbols::identifier(cs).unwrap()),
+            cs if symbols::operator(cs).is_some() => {
+                operator(op!(symbols::operator(cs).unwrap().0))
+            }
 
             // Delimiters
+            "lvert" | "rvert" | "lVert" | "rVert" => {
+                // The fences are opening or closing delimiters, as in `\mathopen{|}`.
+                let class = control_sequence_delimiter_class(control_sequence)
+                    .expect("the control sequence is a delimiter");
+                let delimiter = control_sequence_delimiter_map(control_sequence)
+                    .expect("the control sequence is a delimiter");
+                self.buffer.extend([
+                    I::Event(E::Begin(G::AtomClass(class))),
+                    I::Event(E::Content(C::Operator(op!(delimiter, {stretchy: Some(false)})))),
+                    I::Event(E::End),
+                ]);
+                return Ok(())
+            }
             cs if control_sequence_delimiter_map(cs).is_some() => {
                 operator(op!(control_sequence_delimiter_map(cs).unwrap(), {stretchy: Some(false)}))
             }
 
             // Spacing
-            c if c.trim_start().is_empty() => E::Content(C::Text("&nbsp;")),
+            c if c.trim_start().is_empty() => self.non_breaking_space(),
 
-            _ => return Err(ErrorKind::UnknownPrimitive),
+            _ => match self.config.on_unknown_command {
+                ErrorPolicy::Fail => return Err(ErrorKind::UnknownPrimitive),
+                ErrorPolicy::Skip => {
+                    self.state.skip_suffixes = true;
+                    return Ok(());
+                }
+                ErrorPolicy::EmitPlaceholder => E::UnknownCommand(control_sequence),
+                ErrorPolicy::EmitIdentifier => E::Content(C::Identifier(ID::Str(control_sequence))),
+            },
         };
         self.buffer.push(I::Event(event));
         Ok(())
@@ -1574,17 +1506,107 @@ impl<'a> Parser<'a> {
         self.buffer.push(I::Event(E::End));
     }
 
+    /// Push the operators of the given number of primes, grouped if they do not fit in a single
+    /// precomposed character.
+    pub(super) fn push_primes(&mut self, count: usize) {
+        let grouped = count > 4;
+        if grouped {
+            self.buffer.push(I::Event(E::Begin(G::Normal)));
+        }
+        self.buffer.extend(
+            primes(count).map(|prime| I::Event(E::Content(C::Operator(op!(prime))))),
+        );
+        if grouped {
+            self.buffer.push(I::Event(E::End));
+        }
+    }
+
+    /// Return the event of a non-breaking space, as produced by `~` and `\ `.
+    fn non_breaking_space(&self) -> E<'a> {
+        E::Content(C::Text(if self.config.nbsp_entity {
+            "&nbsp;"
+        } else {
+            "\u{a0}"
+        }))
+    }
+
     /// Return a delimiter with the given size from the next character in the parser.
-    fn em_sized_delim(&mut self, size: f32) -> InnerResult<()> {
+    ///
+    /// If a class is given, the delimiter is wrapped in a group forming an atom of that class.
+    fn em_sized_delim(&mut self, size: f32, class: Option<AC>) -> InnerResult<()> {
         let current = self.current_string();
         let delimiter = lex::delimiter(current)?;
-        self.buffer
-            .push(I::Event(E::Content(C::Operator(
-                op!(delimiter, {size: Some((size, DimensionUnit::Em))}),
-            ))));
+        let delimiter = I::Event(E::Content(C::Operator(
+            op!(delimiter, {size: Some((size, DimensionUnit::Em))}),
+        )));
+        match class {
+            Some(class) => self.buffer.extend([
+                I::Event(E::Begin(G::AtomClass(class))),
+                delimiter,
+                I::Event(E::End),
+            ]),
+            None => self.buffer.push(delimiter),
+        }
+        Ok(())
+    }
+
+    /// Surround the argument of a command declared by `\DeclarePairedDelimiter` with its
+    /// delimiters.
+    ///
+    /// The delimiters are stretched around the argument in the starred form (e.g., `\abs*{x}`),
+    /// and sized by the optional argument otherwise (e.g., `\abs[\big]{x}`).
+    fn paired_delimiter(&mut self, (left, right): (char, char)) -> InnerResult<()> {
+        let str = self.current_string();
+        let starred = match str.trim_start().strip_prefix('*') {
+            Some(rest) => {
+                *str = rest;
+                true
+            }
+            None => false,
+        };
+        let size = match lex::optional_argument(str)? {
+            Some(size) if !starred => {
+                Some(delimiter_size(size.trim()).ok_or(ErrorKind::DelimiterSize)?)
+            }
+            _ => None,
+        };
+        let argument = lex::argument(self.current_string())?;
+
+        if starred {
+            self.buffer.extend([
+                I::Event(E::Begin(G::LeftRight)),
+                I::Event(E::Content(C::Operator(op!(left)))),
+            ]);
+        } else {
+            self.buffer.push(I::Event(E::Begin(G::Normal)));
+            self.sized_delimiter(left, size, AC::Opening);
+        }
+        match argument {
+            Argument::Group(content) => self.group_content(content, starred),
+            argument => self.handle_argument(argument)?,
+        }
+        if starred {
+            self.buffer.push(I::Event(E::Content(C::Operator(op!(right)))));
+        } else {
+            self.sized_delimiter(right, size, AC::Closing);
+        }
+        self.buffer.push(I::Event(E::End));
         Ok(())
     }
 
+    /// Push a delimiter forming an atom of the given class, at the given size if any.
+    fn sized_delimiter(&mut self, delimiter: char, size: Option<f32>, class: AC) {
+        let delimiter = match size {
+            Some(size) => op!(delimiter, {size: Some((size, DimensionUnit::Em))}),
+            None => op!(delimiter, {stretchy: Some(false)}),
+        };
+        self.buffer.extend([
+            I::Event(E::Begin(G::AtomClass(class))),
+            I::Event(E::Content(C::Operator(delimiter))),
+            I::Event(E::End),
+        ]);
+    }
+
     /// Override the `font_state` for the argument to the command.
     fn font_group(&mut self, font: Option<Font>) -> InnerResult<()> {
         let argument = lex::argument(self.current_string())?;
@@ -1600,7 +1622,7 @@ impl<'a> Parser<'a> {
                 };
             }
             Argument::Group(group) => {
-                self.buffer.push(I::SubGroup { content: group, allows_alignment: false });
+                self.buffer.push(I::SubGroup { content: group, allows_alignment: false, allows_middle: false, style: self.buffered_style() });
             }
         };
         self.buffer.push(I::Event(E::End));
@@ -1609,6 +1631,18 @@ impl<'a> Parser<'a> {
 
     /// Accent commands. parse the argument, and overset the accent.
     fn accent(&mut self, accent: O) -> InnerResult<()> {
+        let argument = lex::argument(self.current_string())?;
+        self.buffer.push(I::Event(E::Visual(V::Accent)));
+        self.handle_argument(argument)?;
+        self.buffer
+            .push(I::Event(E::Content(C::Operator(
+                accent,
+            ))));
+        Ok(())
+    }
+
+    /// Overscript commands. parse the argument, and overset the content.
+    fn overscript(&mut self, content: O) -> InnerResult<()> {
         let argument = lex::argument(self.current_string())?;
         self.buffer.push(I::Event(E::Script {
             ty: ST::Superscript,
@@ -1617,8 +1651,9 @@ impl<'a> Parser<'a> {
         self.handle_argument(argument)?;
         self.buffer
             .push(I::Event(E::Content(C::Operator(
-                accent,
+                content,
             ))));
+
         Ok(())
     }
 
@@ -1638,12 +1673,48 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    /// Parse the optional `[rule thickness][bracket height]` arguments of `\overbracket` and
+    /// `\underbracket`.
+    ///
+    /// The dimensions are validated but not used, since `mathml` does not allow for the shape of
+    /// a stretchy operator to be customized.
+    fn bracket_dimensions(&mut self) -> InnerResult<()> {
+        for _ in 0..2 {
+            let Some(mut argument) = lex::optional_argument(self.current_string())? else {
+                break;
+            };
+            if argument.trim().is_empty() {
+                continue;
+            }
+            lex::dimension(&mut argument)?;
+            if !argument.trim().is_empty() {
+                return Err(ErrorKind::DimensionArgument);
+            }
+        }
+        Ok(())
+    }
+
     fn big_operator(&mut self, op: O, above_below: bool) -> E<'a> {
         self.state.allow_suffix_modifiers = true;
         self.state.above_below_suffix_default = above_below;
         operator(op)
     }
 
+    /// A `\lim` operator with a line or an arrow placed above or below it, as in `\varliminf`.
+    fn decorated_limit(&mut self, ty: ST, decoration: O) -> InnerResult<()> {
+        self.state.allow_suffix_modifiers = true;
+        self.state.above_below_suffix_default = true;
+        self.buffer.extend([
+            I::Event(E::Script {
+                ty,
+                position: SP::AboveBelow,
+            }),
+            I::Event(E::Content(C::Identifier(ID::Str("lim")))),
+            I::Event(operator(decoration)),
+        ]);
+        Ok(())
+    }
+
     fn font_change(&mut self, font: Font) -> E<'a> {
         self.state.skip_suffixes = true;
         E::StateChange(SC::Font(Some(font)))
@@ -1651,33 +1722,350 @@ impl<'a> Parser<'a> {
 
     fn style_change(&mut self, style: S) -> E<'a> {
         self.state.skip_suffixes = true;
+        if let Some(I::SubGroup { style: current, .. }) = self.instruction_stack.last_mut() {
+            *current = style;
+        }
         E::StateChange(SC::Style(style))
     }
 
     fn text_argument(&mut self) -> InnerResult<()> {
-        let argument = lex::argument(self.current_string())?;
-        self.buffer
-            .push(I::Event(E::Content(C::Text(
-                match argument {
-                    Argument::Token(Token::Character(c)) => c.as_str(),
-                    Argument::Group(inner) => inner,
-                    _ => return Err(ErrorKind::ControlSequenceAsArgument),
-                },
-            ))));
+        let content = text_argument_content(lex::argument(self.current_string())?)?;
+        let (text, next) = self.text_span(&mut { content })?;
+        if next.is_none() {
+            let start = self.buffer.len();
+            self.text(text);
+            // The pieces of the text form a single element.
+            if self.buffer.len() - start > 1 {
+                self.buffer.insert(start, I::Event(E::Begin(G::Internal)));
+                self.buffer.push(I::Event(E::End));
+            }
+            return Ok(());
+        }
+
+        self.buffer.push(I::Event(E::Begin(G::Normal)));
+        self.text_content(content)?;
+        self.buffer.push(I::Event(E::End));
+        Ok(())
+    }
+
+    /// Parse the argument of a text font command (e.g., `\textbf`), and push it in a group
+    /// starting with the font change.
+    fn text_font_argument(&mut self, font: TF) -> InnerResult<()> {
+        let content = text_argument_content(lex::argument(self.current_string())?)?;
+        self.buffer.extend([
+            I::Event(E::Begin(G::Normal)),
+            I::Event(E::StateChange(SC::TextFont(font))),
+        ]);
+        self.text_content(content)?;
+        self.buffer.push(I::Event(E::End));
+        Ok(())
+    }
+
+    /// Push the spans of text in the content of a text mode argument, along with the inline
+    /// formulas and text font commands between them.
+    ///
+    /// Inline formulas are typeset in math mode, in their own group.
+    fn text_content(&mut self, mut content: &'a str) -> InnerResult<()> {
+        loop {
+            let (text, next) = self.text_span(&mut content)?;
+            if !text.is_empty() {
+                self.text(text);
+            }
+            match next {
+                None => return Ok(()),
+                Some(TextBreak::Math(math)) => {
+                    self.buffer.push(I::Event(E::Begin(G::Normal)));
+                    self.group_content(math, false);
+                    self.buffer.push(I::Event(E::End));
+                }
+                Some(TextBreak::ControlSequence(name)) => {
+                    let font = text_font(name).expect("the name is a text font command");
+                    let argument = text_argument_content(lex::argument(&mut content)?)?;
+                    self.buffer.extend([
+                        I::Event(E::Begin(G::Normal)),
+                        I::Event(E::StateChange(SC::TextFont(font))),
+                    ]);
+                    self.text_content(argument)?;
+                    self.buffer.push(I::Event(E::End));
+                }
+            }
+        }
+    }
+
+    /// Push the text a span of text mode content stands for (see [`lex::text_pieces`]).
+    ///
+    /// The pieces of the text are given as a single event when they follow each other in the
+    /// input or when the parser has a [`Storage`], and as consecutive events otherwise.
+    ///
+    /// [`Storage`]: super::Storage
+    fn text(&mut self, span: &'a str) {
+        let pieces = lex::text_pieces(span);
+        match self.join_pieces(&pieces) {
+            Ok(text) => self.buffer.push(I::Event(E::Content(C::Text(text)))),
+            Err(_) => self.buffer.extend(
+                pieces
+                    .into_iter()
+                    .map(|piece| I::Event(E::Content(C::Text(piece)))),
+            ),
+        }
+    }
+
+    /// Parse the next span of text in the content of a text mode argument.
+    ///
+    /// If the inline formulas of the content are unbalanced, the error starts at the delimiter
+    /// which is not matched, inside of the argument.
+    fn text_span(
+        &mut self,
+        content: &mut &'a str,
+    ) -> InnerResult<(&'a str, Option<TextBreak<'a>>)> {
+        lex::text_span(content, is_text_command).inspect_err(|_| {
+            if let Some(offset) = Self::input_offset(self.input, content) {
+                self.token_start = offset;
+            }
+        })
+    }
+
+    /// Parse the arguments of `\prescript`, and push the base followed by its prescripts.
+    ///
+    /// Empty scripts are omitted, and the base is pushed on its own if both scripts are empty.
+    fn prescript(&mut self) -> InnerResult<()> {
+        let style = script_style(self.buffered_style());
+        let start = self.buffer.len();
+        let previous_style = self.state.argument_style.replace((start, style));
+        // The arguments are parsed in order, and the scripts are moved after the base afterwards.
+        let mut scripts = [None, None];
+        for script in &mut scripts {
+            let argument = lex::argument(self.current_string())?;
+            if !matches!(argument, Argument::Group(group) if group.trim().is_empty()) {
+                let script_start = self.buffer.len();
+                self.handle_argument(argument)?;
+                *script = Some(script_start..self.buffer.len());
+            }
+        }
+        self.set_buffered_style(start, style);
+        self.state.argument_style = previous_style;
+
+        let base = lex::argument(self.current_string())?;
+        let base_start = self.buffer.len();
+        self.handle_argument(base)?;
+
+        let [superscript, subscript] = scripts;
+        let ty = match (&subscript, &superscript) {
+            (None, None) => return Ok(()),
+            (Some(_), None) => ST::Subscript,
+            (None, Some(_)) => ST::Superscript,
+            (Some(_), Some(_)) => ST::SubSuperscript,
+        };
+        let arguments: Vec<_> = self.buffer.drain(start..base_start).collect();
+        self.buffer.insert(start, I::Event(E::Script {
+            ty,
+            position: SP::Prescript,
+        }));
+        for script in [subscript, superscript].into_iter().flatten() {
+            self.buffer
+                .extend_from_slice(&arguments[script.start - start..script.end - start]);
+        }
+        Ok(())
+    }
+
+    /// Parse the arguments of `\tensor`, and push the base followed by its indices.
+    ///
+    /// Each subscript or superscript of the indices (e.g., `^i_j^k`) is placed after the previous
+    /// ones, producing the same events as `T^i{}_j{}^k`.
+    fn tensor(&mut self) -> InnerResult<()> {
+        let str = self.current_string();
+        let base = lex::argument(str)?;
+        let Argument::Group(mut indices) = lex::argument(str)? else {
+            return Err(ErrorKind::Argument);
+        };
+        let start = self.buffer.len();
+        self.handle_argument(base)?;
+
+        let style = script_style(self.buffered_style());
+        let indices_start = self.buffer.len();
+        let previous_style = self.state.argument_style.replace((indices_start, style));
+        let mut types = Vec::new();
+        loop {
+            indices = indices.trim_start();
+            let (ty, missing) = match indices.chars().next() {
+                Some('^') => (ST::Superscript, ErrorKind::EmptySuperscript),
+                Some('_') => (ST::Subscript, ErrorKind::EmptySubscript),
+                Some(_) => return Err(ErrorKind::Argument),
+                None => break,
+            };
+            indices = &indices[1..];
+            let argument = match lex::argument(&mut indices) {
+                Ok(Argument::Token(Token::Character(c))) if matches!(c.into(), '^' | '_') => {
+                    return Err(missing)
+                }
+                Err(ErrorKind::EndOfInput) => return Err(missing),
+                argument => argument?,
+            };
+            self.handle_argument(argument)?;
+            types.push(ty);
+        }
+        self.set_buffered_style(indices_start, style);
+        self.state.argument_style = previous_style;
+
+        // The last index comes first, and the first index is the only one placed to the right of
+        // the base itself.
+        let scripts = types.iter().rev().enumerate().map(|(index, &ty)| {
+            I::Event(E::Script {
+                ty,
+                position: if index + 1 == types.len() { SP::Right } else { SP::Tensor },
+            })
+        });
+        self.buffer.splice(start..start, scripts);
         Ok(())
     }
 
-    fn fraction_like(&mut self, bar_size: Option<(f32, DimensionUnit)>) -> InnerResult<()> {
-        self.buffer.push(I::Event(E::Visual(V::Fraction(bar_size))));
+    /// Parse and evaluate the condition following the given conditional (TeXBook p. 209).
+    fn condition(&mut self, conditional: &str) -> InnerResult<bool> {
+        let str = self.current_string();
+        Ok(match conditional {
+            "iftrue" => true,
+            "iffalse" => false,
+            "ifnum" => {
+                let left = lex::integer(str)?;
+                let relation = lex::relation(str)?;
+                let right = lex::integer(str)?;
+                left.cmp(&right) == relation
+            }
+            "ifdim" => {
+                let left = tex_to_scaled_points(lex::dimension(str)?);
+                let relation = lex::relation(str)?;
+                let right = tex_to_scaled_points(lex::dimension(str)?);
+                left.cmp(&right) == relation
+            }
+            _ => {
+                let left = lex::token(str)?;
+                let right = lex::token(str)?;
+                // Tokens are compared using the meaning they were given by `\let`.
+                let meaning = |token| match token {
+                    Token::ControlSequence(cs) => self.macro_context.meaning(cs).unwrap_or(token),
+                    Token::Character(_) => token,
+                };
+                match (meaning(left), meaning(right)) {
+                    (Token::Character(left), Token::Character(right)) => {
+                        char::from(left) == char::from(right)
+                    }
+                    // `\if` compares character codes, and control sequences have none.
+                    (Token::ControlSequence(_), Token::ControlSequence(_)) if conditional == "if" => {
+                        true
+                    }
+                    // Macros are compared by their definitions.
+                    (Token::ControlSequence(left), Token::ControlSequence(right))
+                        if self.macro_context.is_macro(left)
+                            || self.macro_context.is_macro(right) =>
+                    {
+                        self.macro_context.same_definition(left, right)
+                    }
+                    (Token::ControlSequence(left), Token::ControlSequence(right)) => left == right,
+                    _ => false,
+                }
+            }
+        })
+    }
+
+    /// Push the given visual event, followed by the two arguments of the fraction.
+    fn fraction_like(&mut self, visual: V) -> InnerResult<()> {
+        let style = fraction_style(self.buffered_style());
+        self.fraction_arguments(visual, style)
+    }
+
+    /// Push the given visual event, followed by the two arguments of the fraction in the given
+    /// style.
+    fn fraction_arguments(&mut self, visual: V, style: S) -> InnerResult<()> {
+        self.buffer.push(I::Event(E::Visual(visual)));
+        let start = self.buffer.len();
+        let previous_style = self.state.argument_style.replace((start, style));
         let numerator = lex::argument(self.current_string())?;
         self.handle_argument(numerator)?;
         let denominator = lex::argument(self.current_string())?;
         self.handle_argument(denominator)?;
+        self.set_buffered_style(start, style);
+        self.state.argument_style = previous_style;
         Ok(())
     }
 }
 
+/// Parses a delimiter given as an argument, where an empty group means that there is no
+/// delimiter (e.g., `\genfrac{(}{}...`).
+fn optional_delimiter(argument: Argument) -> InnerResult<Option<char>> {
+    match argument {
+        Argument::Token(token) => Some(token_to_delim(token).ok_or(ErrorKind::Delimiter)).transpose(),
+        Argument::Group(mut group) => {
+            if group.trim().is_empty() {
+                return Ok(None);
+            }
+            let delim = lex::delimiter(&mut group)?;
+            if !group.trim().is_empty() {
+                return Err(ErrorKind::Delimiter);
+            }
+            Ok(Some(delim))
+        }
+    }
+}
+
 #[inline]
+/// The text font set by the given text font command (e.g., `\textbf`).
+fn text_font(name: &str) -> Option<TF> {
+    Some(match name {
+        "textbf" => TF::Bold,
+        "textit" => TF::Italic,
+        "textsc" => TF::SmallCaps,
+        "textrm" => TF::Roman,
+        "textsf" => TF::SansSerif,
+        "texttt" => TF::Monospace,
+        "emph" => TF::Emphasis,
+        _ => return None,
+    })
+}
+
+/// The content of a text mode argument, which cannot be a control sequence.
+fn text_argument_content(argument: Argument<'_>) -> InnerResult<&str> {
+    match argument {
+        Argument::Token(Token::Character(c)) => Ok(&c.as_str()[..char::from(c).len_utf8()]),
+        Argument::Group(inner) => Ok(inner),
+        Argument::Token(Token::ControlSequence(_)) => Err(ErrorKind::ControlSequenceAsArgument),
+    }
+}
+
+/// Whether the URL of a link is relative, or uses a protocol which cannot run scripts.
+///
+/// Like browsers, the protocol is read ignoring whitespace and control characters, so that
+/// `java\tscript:` is not mistaken for a relative URL.
+fn is_safe_url(url: &str) -> bool {
+    let Some(end) = url.find([':', '/', '?', '#']) else {
+        return true;
+    };
+    if !url[end..].starts_with(':') {
+        return true;
+    }
+    let protocol: String = url[..end]
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    matches!(protocol.as_str(), "http" | "https" | "mailto")
+}
+
+/// Whether the control sequence is handled in text mode, rather than kept in the text.
+fn is_text_command(name: &str) -> bool {
+    text_font(name).is_some()
+}
+
+/// The size of the delimiters produced by the given command, as in `\big(`.
+fn delimiter_size(command: &str) -> Option<f32> {
+    Some(match command {
+        r"\big" => 1.2,
+        r"\Big" => 1.8,
+        r"\bigg" => 2.4,
+        r"\Bigg" => 3.0,
+        _ => return None,
+    })
+}
+
 fn ident(ident: char) -> E<'static> {
     E::Content(C::Identifier(ID::Char(ident)))
 }
@@ -1688,15 +2076,8 @@ fn operator(operator: O) -> E<'static> {
 }
 
 // TODO implementations:
-// - `raise`, `lower`
-// - `hbox`, `mbox`?
-// - `vcenter`
 // - `rule`
 // - `math_` atoms
-// - `mathchoice` (TeXbook p. 151)
 
 // Unimplemented primitives:
-// `sl` (slanted) font: https://tug.org/texinfohtml/latex2e.html#index-_005csl
-// `bbit` (double-struck italic) font
 // `symliteral` wtf is this? (in unicode-math)
-// `sc` (small caps) font: https://tug.org/texinfohtml/latex2e.html#index-_005csc
@@ -1,24 +1,25 @@
+use crate::event::Style;
+
 /// State belonging to the parser that is reset every call to the `next` method of the parser.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct ParserState {
     /// Whether the parser is currently parsing an operator that allows for its suffixes to be
     /// modifies by the commands `\nolimits`, `\limits`, and `\displaylimits`.
     pub allow_suffix_modifiers: bool,
     /// Whether the suffixes of the operator are set above and below the operator by default.
     pub above_below_suffix_default: bool,
+    /// Whether the suffixes of the element are set above and below it, even in inline mode, as
+    /// if `\limits` was used.
+    pub limits: bool,
     /// Whether the parser should skip suffix parsing for the current event.
     pub skip_suffixes: bool,
     /// Whether we can parse the `\relax` command.
     pub invalidate_relax: bool,
-}
-
-impl Default for ParserState {
-    fn default() -> Self {
-        Self {
-            allow_suffix_modifiers: false,
-            above_below_suffix_default: false,
-            skip_suffixes: false,
-            invalidate_relax: false,
-        }
-    }
+    /// The style of the argument being parsed, when it is an argument typeset in a different
+    /// style than the current group (e.g., a script), along with the position in the buffer where
+    /// the argument starts.
+    pub argument_style: Option<(usize, Style)>,
+    /// The position in the buffer where the element receiving the suffixes starts, when the
+    /// events preceding it are not part of it (e.g., the space preceding `\pmod`).
+    pub suffix_base: usize,
 }
@@ -0,0 +1,44 @@
+use std::cell::RefCell;
+
+/// Storage for the strings produced by the expansion of macros defined in the input (e.g., with
+/// `\def`), which the events of a [`Parser`] can borrow.
+///
+/// Macros whose expansion is a contiguous part of the input, such as macros without parameters,
+/// do not need a storage. Other macros can only be expanded by a parser created with
+/// [`Parser::with_storage`].
+///
+/// The strings are kept until the storage is dropped.
+///
+/// ```rust
+/// use pulldown_latex::{config::ParserConfig, parser::Storage, Parser};
+///
+/// let storage = Storage::new();
+/// let input = r"\def\pair#1#2{(#1, #2)} \pair{a}{b}";
+/// let parser = Parser::with_storage(input, ParserConfig::default(), &storage);
+/// assert!(parser.collect::<Result<Vec<_>, _>>().is_ok());
+/// ```
+///
+/// [`Parser`]: crate::parser::Parser
+/// [`Parser::with_storage`]: crate::parser::Parser::with_storage
+#[derive(Debug, Default)]
+pub struct Storage {
+    strings: RefCell<Vec<String>>,
+}
+
+impl Storage {
+    /// Create an empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store the string, and return a reference to it which lives as long as the storage.
+    pub(super) fn store(&self, string: String) -> &str {
+        let mut strings = self.strings.borrow_mut();
+        strings.push(string);
+        let stored: *const str = strings.last().expect("the string was pushed").as_str();
+        // Safety: the content of a `String` is allocated on the heap, and does not move when the
+        // `String` itself is moved as the vector grows. The strings are never modified or removed
+        // before the storage is dropped, and the reference cannot outlive the storage.
+        unsafe { &*stored }
+    }
+}
@@ -0,0 +1,629 @@
+//! Commands producing a single symbol, such as `\alpha` or `\leq`.
+
+use crate::event::AtomClass::{self, Binary, Inner, Ordinary, Punctuation, Relation};
+
+/// Defines a function returning the symbol produced by a command, along with a table of the
+/// commands and their symbols in the same order, from which the reverse lookup is built.
+macro_rules! symbol_table {
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $lookup:ident -> $symbol:ty, static $table:ident {
+            $($($name:literal)|+ => $value:expr,)*
+        }
+    ) => {
+        $(#[$attr])*
+        $vis fn $lookup(control_sequence: &str) -> Option<$symbol> {
+            Some(match control_sequence {
+                $($($name)|+ => $value,)*
+                _ => return None,
+            })
+        }
+
+        $vis static $table: &[(&str, $symbol)] = &[$($(($name, $value),)+)*];
+    };
+}
+
+pub(super) use symbol_table;
+
+symbol_table! {
+    /// Returns the character of the identifier produced by the given command, if it produces a
+    /// single identifier.
+    pub fn identifier -> char, static IDENTIFIERS {
+        // Lowercase Greek letters
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "rho" => 'ρ',
+        "sigma" => 'σ',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "omicron" => 'ο',
+
+        // Uppercase Greek letters
+        "Alpha" => 'Α',
+        "Beta" => 'Β',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Epsilon" => 'Ε',
+        "Zeta" => 'Ζ',
+        "Eta" => 'Η',
+        "Theta" => 'Θ',
+        "Iota" => 'Ι',
+        "Kappa" => 'Κ',
+        "Lambda" => 'Λ',
+        "Mu" => 'Μ',
+        "Nu" => 'Ν',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Rho" => 'Ρ',
+        "Sigma" => 'Σ',
+        "Tau" => 'Τ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Chi" => 'Χ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        "Omicron" => 'Ο',
+
+        // Lowercase Greek Variants
+        "varepsilon" => 'ε',
+        "vartheta" => 'ϑ',
+        "varkappa" => 'ϰ',
+        "varrho" => 'ϱ',
+        "varsigma" => 'ς',
+        "varpi" => 'ϖ',
+        "varphi" => 'φ',
+
+        // Uppercase Greek Variants
+        "varGamma" => '𝛤',
+        "varDelta" => '𝛥',
+        "varTheta" => '𝛩',
+        "varLambda" => '𝛬',
+        "varXi" => '𝛯',
+        "varPi" => '𝛱',
+        "varSigma" => '𝛴',
+        "varUpsilon" => '𝛶',
+        "varPhi" => '𝛷',
+        "varPsi" => '𝛹',
+        "varOmega" => '𝛺',
+
+        // Hebrew letters
+        "aleph" => 'ℵ',
+        "beth" => 'ℶ',
+        "gimel" => 'ℷ',
+        "daleth" => 'ℸ',
+
+        // Other symbols
+        "digamma" => 'ϝ',
+        "eth" => 'ð',
+        "ell" => 'ℓ',
+        "nabla" => '∇',
+        "partial" => '∂',
+        "Finv" => 'Ⅎ',
+        "Game" => 'ℷ',
+        "hbar" | "hslash" => 'ℏ',
+        "imath" => 'ı',
+        "jmath" => 'ȷ',
+        "Im" => 'ℑ',
+        "Re" => 'ℜ',
+        "wp" => '℘',
+        "Bbbk" => '𝕜',
+        "Angstrom" => 'Å',
+        "backepsilon" => '϶',
+
+        // Symbols & Punctuation
+        "infty" => '∞',
+        "checkmark" => '✓',
+        "ballotx" => '✗',
+        "dagger" | "dag" => '†',
+        "ddagger" | "ddag" => '‡',
+        "angle" => '∠',
+        "measuredangle" => '∡',
+        "lq" => '‘',
+        "sphericalangle" => '∢',
+        "square" | "Box" => '□',
+        "top" => '⊤',
+        "rq" => '’',
+        "blacksquare" => '■',
+        "bot" => '⊥',
+        "triangledown" => '▽',
+        "Bot" => '⫫',
+        "triangleleft" => '◃',
+        "triangleright" => '▹',
+        "cent" => '¢',
+        "colon" | "ratio" | "vcentcolon" => ':',
+        "bigtriangledown" => '▽',
+        "pounds" | "mathsterling" => '£',
+        "bigtriangleup" => '△',
+        "blacktriangle" => '▲',
+        "blacktriangledown" => '▼',
+        "yen" => '¥',
+        "blacktriangleleft" => '◀',
+        "euro" => '€',
+        "blacktriangleright" => '▶',
+        "degree" => '°',
+        "lozenge" | "Diamond" => '◊',
+        "blacklozenge" => '⧫',
+        "mho" => '℧',
+        "bigstar" => '★',
+        "diagdown" => '╲',
+        "maltese" => '✠',
+        "diagup" => '╱',
+        "P" => '¶',
+        "clubsuit" => '♣',
+        "varclubsuit" => '♧',
+        "S" => '§',
+        "diamondsuit" => '♢',
+        "vardiamondsuit" => '♦',
+        "copyright" => '©',
+        "heartsuit" => '♡',
+        "varheartsuit" => '♥',
+        "circledR" => '®',
+        "spadesuit" => '♠',
+        "varspadesuit" => '♤',
+        "circledS" => 'Ⓢ',
+        "female" => '♀',
+        "male" => '♂',
+        "astrosun" => '☉',
+        "sun" => '☼',
+        "leftmoon" => '☾',
+        "rightmoon" => '☽',
+        "smiley" => '☺',
+        "Earth" => '⊕',
+        "flat" => '♭',
+        "standardstate" => '⦵',
+        "natural" => '♮',
+        "sharp" => '♯',
+        "permil" => '‰',
+        "QED" => '∎',
+        "lightning" => '↯',
+        "diameter" => '⌀',
+    }
+}
+
+symbol_table! {
+    /// Returns the character of the large operator produced by the given command, along with
+    /// whether its limits are placed above and below it by default, if the command produces a
+    /// large operator.
+    pub fn large_operator -> (char, bool), static LARGE_OPERATORS {
+        // The following operators have above and below limits by default.
+        "sum" => ('∑', true),
+        "prod" => ('∏', true),
+        "coprod" => ('∐', true),
+        "bigvee" => ('⋁', true),
+        "bigwedge" => ('⋀', true),
+        "bigcup" => ('⋃', true),
+        "bigcap" => ('⋂', true),
+        "biguplus" => ('⨄', true),
+        "bigoplus" => ('⨁', true),
+        "bigotimes" => ('⨂', true),
+        "bigodot" => ('⨀', true),
+        "bigsqcup" => ('⨆', true),
+        "bigsqcap" => ('⨅', true),
+        "bigtimes" => ('⨉', true),
+
+        // The following operators do not have above and below limits by default.
+        "int" => ('∫', false),
+        "iint" => ('∬', false),
+        "iiint" => ('∭', false),
+        "iiiint" => ('⨌', false),
+        "intcap" => ('⨙', false),
+        "intcup" => ('⨚', false),
+        "oint" => ('∮', false),
+        "varointclockwise" => ('∲', false),
+        "intclockwise" => ('∱', false),
+        "oiint" => ('∯', false),
+        "pointint" => ('⨕', false),
+        "rppolint" => ('⨒', false),
+        "scpolint" => ('⨓', false),
+        "oiiint" => ('∰', false),
+        "intlarhk" => ('⨗', false),
+        "sqint" => ('⨖', false),
+        "intx" => ('⨘', false),
+        "intbar" => ('⨍', false),
+        "intBar" => ('⨎', false),
+        "fint" => ('⨏', false),
+    }
+}
+
+symbol_table! {
+    /// Returns the character of the operator produced by the given command, along with the class
+    /// of the operator, if the command produces a single operator.
+    pub fn operator -> (char, AtomClass), static OPERATORS {
+        // Symbols & Punctuation
+        "ldots" | "dotso" | "dotsc" => ('…', Inner),
+        "cdots" | "dotsi" | "dotsm" | "dotsb" | "idotsin" => ('⋯', Inner),
+        "ddots" => ('⋱', Inner),
+        "iddots" => ('⋰', Inner),
+        "vdots" => ('⋮', Ordinary),
+        "mathellipsis" => ('…', Inner),
+
+        // Primes
+        "prime" => ('′', Ordinary),
+        "dprime" => ('″', Ordinary),
+        "trprime" => ('‴', Ordinary),
+        "qprime" => ('⁗', Ordinary),
+        "backprime" => ('‵', Ordinary),
+        "backdprime" => ('‶', Ordinary),
+        "backtrprime" => ('‷', Ordinary),
+
+        // Logic & Set Theory
+        "forall" => ('∀', Ordinary),
+        "complement" => ('∁', Ordinary),
+        "therefore" => ('∴', Relation),
+        "emptyset" => ('∅', Ordinary),
+        "exists" => ('∃', Ordinary),
+        "subset" => ('⊂', Relation),
+        "because" => ('∵', Relation),
+        "varnothing" => ('⌀', Ordinary),
+        "nexists" => ('∄', Ordinary),
+        "supset" => ('⊃', Relation),
+        "mapsto" => ('↦', Relation),
+        "in" | "isin" => ('∈', Relation),
+        "mid" => ('∣', Relation),
+        "ni" => ('∋', Relation),
+        "notni" => ('∌', Relation),
+        "neg" | "lnot" => ('¬', Ordinary),
+        "strictif" => ('⥽', Relation),
+        "strictfi" => ('⥼', Relation),
+
+        // Binary Operators
+        "ldotp" => ('.', Punctuation),
+        "cdotp" => ('·', Punctuation),
+        "cdot" => ('⋅', Binary),
+        "centerdot" => ('·', Binary),
+        "circ" => ('∘', Binary),
+        "bullet" => ('∙', Binary),
+        "circledast" => ('⊛', Binary),
+        "circledcirc" => ('⊚', Binary),
+        "circleddash" => ('⊝', Binary),
+        "bigcirc" => ('◯', Binary),
+        "leftthreetimes" => ('⋋', Binary),
+        "rhd" => ('⊳', Binary),
+        "lhd" => ('⊲', Binary),
+        "leftouterjoin" => ('⟕', Binary),
+        "rightouterjoin" => ('⟖', Binary),
+        "rightthreetimes" => ('⋌', Binary),
+        "rtimes" => ('⋊', Binary),
+        "ltimes" => ('⋉', Binary),
+        "amalg" => ('⨿', Binary),
+        "ast" => ('*', Binary),
+        "asymp" => ('≍', Binary),
+        "And" => ('&', Binary),
+        "setminus" => ('∖', Binary),
+        "Cup" => ('⋓', Binary),
+        "cup" => ('∪', Binary),
+        "sqcup" => ('⊔', Binary),
+        "sqcap" => ('⊓', Binary),
+        "lessdot" => ('⋖', Binary),
+        "barwedge" => ('⌅', Binary),
+        "curlyvee" => ('⋎', Binary),
+        "curlywedge" => ('⋏', Binary),
+        "sslash" => ('⫽', Binary),
+        "bowtie" | "Join" => ('⋈', Binary),
+        "div" => ('÷', Binary),
+        "mp" => ('∓', Binary),
+        "times" => ('×', Binary),
+        "boxdot" => ('⊡', Binary),
+        "divideontimes" => ('⋇', Binary),
+        "odot" => ('⊙', Binary),
+        "unlhd" => ('⊴', Binary),
+        "boxminus" => ('⊟', Binary),
+        "dotplus" => ('∔', Binary),
+        "ominus" => ('⊖', Binary),
+        "unrhd" => ('⊵', Binary),
+        "boxplus" => ('⊞', Binary),
+        "doublebarwedge" => ('⩞', Binary),
+        "oplus" => ('⊕', Binary),
+        "uplus" => ('⊎', Binary),
+        "boxtimes" => ('⊠', Binary),
+        "otimes" => ('⊗', Binary),
+        "vee" | "lor" => ('∨', Binary),
+        "veebar" => ('⊻', Binary),
+        "Cap" | "doublecap" => ('⋒', Binary),
+        "fullouterjoin" => ('⟗', Binary),
+        "parr" => ('⅋', Binary),
+        "wedge" | "land" => ('∧', Binary),
+        "cap" => ('∩', Binary),
+        "gtrdot" => ('⋗', Binary),
+        "pm" => ('±', Binary),
+        "with" => ('&', Binary),
+        "intercal" => ('⊺', Binary),
+        "wr" => ('≀', Binary),
+        "circledvert" => ('⦶', Binary),
+        "blackhourglass" => ('⧗', Binary),
+        "circlehbar" => ('⦵', Binary),
+        "operp" => ('⦹', Binary),
+        "boxast" => ('⧆', Binary),
+        "concavediamond" => ('⟡', Binary),
+        "boxbox" => ('⧈', Binary),
+        "concavediamondtickleft" => ('⟢', Binary),
+        "oslash" => ('⊘', Binary),
+        "boxcircle" => ('⧇', Binary),
+        "concavediamondtickright" => ('⟣', Binary),
+        "diamond" => ('⋄', Binary),
+        "Otimes" => ('⨷', Binary),
+        "hourglass" => ('⧖', Binary),
+        "otimeshat" => ('⨶', Binary),
+        "triangletimes" => ('⨻', Binary),
+        "lozengeminus" => ('⟠', Binary),
+        "star" => ('⋆', Binary),
+        "obar" => ('⌽', Binary),
+        "triangle" | "vartriangle" => ('△', Binary),
+        "obslash" => ('⦸', Binary),
+        "triangleminus" => ('⨺', Binary),
+        "odiv" => ('⨸', Binary),
+        "triangleplus" => ('⨹', Binary),
+        "circledequal" => ('⊜', Binary),
+        "ogreaterthan" => ('⧁', Binary),
+        "whitesquaretickleft" => ('⟤', Binary),
+        "circledparallel" => ('⦷', Binary),
+        "olessthan" => ('⧀', Binary),
+        "whitesquaretickright" => ('⟥', Binary),
+
+        // Relations
+        "eqcirc" => ('≖', Relation),
+        "lessgtr" => ('≶', Relation),
+        "smile" | "sincoh" => ('⌣', Relation),
+        "eqcolon" | "minuscolon" => ('∹', Relation),
+        "lesssim" => ('≲', Relation),
+        "sqsubset" => ('⊏', Relation),
+        "ll" => ('≪', Relation),
+        "sqsubseteq" => ('⊑', Relation),
+        "eqqcolon" => ('≕', Relation),
+        "lll" => ('⋘', Relation),
+        "sqsupset" => ('⊐', Relation),
+        "llless" => ('⋘', Relation),
+        "sqsupseteq" => ('⊒', Relation),
+        "approx" => ('≈', Relation),
+        "eqdef" => ('≝', Relation),
+        "lt" => ('<', Relation),
+        "stareq" => ('≛', Relation),
+        "approxeq" => ('≊', Relation),
+        "eqsim" => ('≂', Relation),
+        "measeq" => ('≞', Relation),
+        "Subset" => ('⋐', Relation),
+        "arceq" => ('≘', Relation),
+        "eqslantgtr" => ('⪖', Relation),
+        "eqslantless" => ('⪕', Relation),
+        "models" => ('⊨', Relation),
+        "leftmodels" => ('⊨', Binary),
+        "subseteq" => ('⊆', Relation),
+        "backcong" => ('≌', Relation),
+        "equiv" => ('≡', Relation),
+        "multimap" => ('⊸', Relation),
+        "subseteqq" => ('⫅', Relation),
+        "fallingdotseq" => ('≒', Relation),
+        "multimapboth" => ('⧟', Relation),
+        "succ" => ('≻', Relation),
+        "backsim" => ('∽', Relation),
+        "frown" => ('⌢', Relation),
+        "multimapinv" => ('⟜', Relation),
+        "succapprox" => ('⪸', Relation),
+        "backsimeq" => ('⋍', Relation),
+        "origof" => ('⊶', Relation),
+        "succcurlyeq" => ('≽', Relation),
+        "between" => ('≬', Relation),
+        "geq" | "ge" => ('≥', Relation),
+        "owns" => ('∋', Relation),
+        "succeq" => ('⪰', Relation),
+        "bumpeq" => ('≏', Relation),
+        "geqq" => ('≧', Relation),
+        "parallel" => ('∥', Relation),
+        "succsim" => ('≿', Relation),
+        "Bumpeq" => ('≎', Relation),
+        "geqslant" => ('⩾', Relation),
+        "perp" => ('⟂', Relation),
+        "Supset" => ('⋑', Relation),
+        "circeq" => ('≗', Relation),
+        "gg" => ('≫', Relation),
+        "Perp" => ('⫫', Relation),
+        "coh" => ('⌢', Relation),
+        "ggg" => ('⋙', Relation),
+        "pitchfork" => ('⋔', Relation),
+        "supseteq" => ('⊇', Relation),
+        "gggtr" => ('⋙', Relation),
+        "prec" => ('≺', Relation),
+        "supseteqq" => ('⫆', Relation),
+        "gt" => ('>', Relation),
+        "precapprox" => ('⪷', Relation),
+        "thickapprox" => ('≈', Relation),
+        "gtrapprox" => ('⪆', Relation),
+        "preccurlyeq" => ('≼', Relation),
+        "gtreqless" => ('⋛', Relation),
+        "preceq" => ('⪯', Relation),
+        "trianglelefteq" => ('⊴', Relation),
+        "coloneqq" | "colonequals" => ('≔', Relation),
+        "gtreqqless" => ('⪌', Relation),
+        "precsim" => ('≾', Relation),
+        "triangleq" => ('≜', Relation),
+        "Coloneqq" | "coloncolonequals" => ('⩴', Relation),
+        "gtrless" => ('≷', Relation),
+        "propto" => ('∝', Relation),
+        "trianglerighteq" => ('⊵', Relation),
+        "gtrsim" => ('≳', Relation),
+        "questeq" => ('≟', Relation),
+        "varpropto" => ('∝', Relation),
+        "imageof" => ('⊷', Relation),
+        "cong" => ('≅', Relation),
+        "risingdotseq" => ('≓', Relation),
+        "vartriangleleft" => ('⊲', Relation),
+        "curlyeqprec" => ('⋞', Relation),
+        "scoh" => ('⌢', Relation),
+        "vartriangleright" => ('⊳', Relation),
+        "curlyeqsucc" => ('⋟', Relation),
+        "vdash" => ('⊢', Relation),
+        "dashv" => ('⊣', Relation),
+        "leq" | "le" => ('≤', Relation),
+        "vDash" => ('⊨', Relation),
+        "dblcolon" | "coloncolon" => ('∷', Relation),
+        "leqq" => ('≦', Relation),
+        "sim" | "thicksim" => ('∼', Relation),
+        "Vdash" => ('⊩', Relation),
+        "doteq" => ('≐', Relation),
+        "leqslant" => ('⩽', Relation),
+        "simeq" => ('≃', Relation),
+        "Dash" => ('⊫', Relation),
+        "lessapprox" => ('⪅', Relation),
+        "Vvdash" => ('⊪', Relation),
+        "doteqdot" | "Doteq" => ('≑', Relation),
+        "lesseqgtr" => ('⋚', Relation),
+        "smallfrown" => ('⌢', Relation),
+        "veeeq" => ('≚', Relation),
+        "eqeq" => ('⩵', Relation),
+        "lesseqqgtr" => ('⪋', Relation),
+        "wedgeq" => ('≙', Relation),
+
+        // Negated relations
+        "gnapprox" => ('⪊', Relation),
+        "nsubset" => ('⊄', Relation),
+        "nVdash" => ('⊮', Relation),
+        "gneq" => ('⪈', Relation),
+        "ngtr" => ('≯', Relation),
+        "nsubseteq" => ('⊈', Relation),
+        "precnapprox" => ('⪹', Relation),
+        "gneqq" => ('≩', Relation),
+        "nleq" => ('≰', Relation),
+        "nsubseteqq" => ('⊈', Relation),
+        "precneqq" => ('⪵', Relation),
+        "gnsim" => ('⋧', Relation),
+        "nleqq" => ('≰', Relation),
+        "nsucc" => ('⊁', Relation),
+        "precnsim" => ('⋨', Relation),
+        "nleqslant" => ('≰', Relation),
+        "nsucceq" => ('⋡', Relation),
+        "subsetneq" => ('⊊', Relation),
+        "lnapprox" => ('⪉', Relation),
+        "nless" => ('≮', Relation),
+        "nsupset" => ('⊅', Relation),
+        "subsetneqq" => ('⫋', Relation),
+        "lneq" => ('⪇', Relation),
+        "nmid" => ('∤', Relation),
+        "nsupseteq" => ('⊉', Relation),
+        "succnapprox" => ('⪺', Relation),
+        "lneqq" => ('≨', Relation),
+        "notin" => ('∉', Relation),
+        "nsupseteqq" => ('⊉', Relation),
+        "succneqq" => ('⪶', Relation),
+        "lnsim" => ('⋦', Relation),
+        "ntriangleleft" => ('⋪', Relation),
+        "succnsim" => ('⋩', Relation),
+        "nparallel" => ('∦', Relation),
+        "ntrianglelefteq" => ('⋬', Relation),
+        "supsetneq" => ('⊋', Relation),
+        "ncong" => ('≆', Relation),
+        "nprec" => ('⊀', Relation),
+        "ntriangleright" => ('⋫', Relation),
+        "supsetneqq" => ('⫌', Relation),
+        "npreceq" => ('⋠', Relation),
+        "ntrianglerighteq" => ('⋭', Relation),
+        "neq" | "ne" => ('≠', Relation),
+        "nshortmid" => ('∤', Relation),
+        "nvdash" => ('⊬', Relation),
+        "ngeq" | "ngeqslant" => ('≱', Relation),
+        "nvDash" => ('⊭', Relation),
+        "varsupsetneq" => ('⊋', Relation),
+        "ngeqq" => ('≱', Relation),
+        "nsim" => ('≁', Relation),
+        "nVDash" => ('⊯', Relation),
+
+        // Arrows
+        "circlearrowleft" => ('↺', Relation),
+        "Leftrightarrow" => ('⇔', Relation),
+        "circlearrowright" => ('↻', Relation),
+        "leftrightarrows" => ('⇆', Relation),
+        "rightarrow" | "to" => ('→', Relation),
+        "curvearrowleft" => ('↶', Relation),
+        "leftrightharpoons" => ('⇋', Relation),
+        "Rightarrow" => ('⇒', Relation),
+        "curvearrowright" => ('↷', Relation),
+        "leftrightsquigarrow" => ('↭', Relation),
+        "rightarrowtail" => ('↣', Relation),
+        "dashleftarrow" => ('⇠', Relation),
+        "Lleftarrow" => ('⇚', Relation),
+        "rightharpoondown" => ('⇁', Relation),
+        "dashrightarrow" => ('⇢', Relation),
+        "longleftarrow" => ('⟵', Relation),
+        "rightharpoonup" => ('⇀', Relation),
+        "downarrow" => ('↓', Relation),
+        "Longleftarrow" | "impliedby" => ('⟸', Relation),
+        "rightleftarrows" => ('⇄', Relation),
+        "Downarrow" => ('⇓', Relation),
+        "longleftrightarrow" => ('⟷', Relation),
+        "rightleftharpoons" => ('⇌', Relation),
+        "downdownarrows" => ('⇊', Relation),
+        "Longleftrightarrow" | "iff" => ('⟺', Relation),
+        "rightrightarrows" => ('⇉', Relation),
+        "downharpoonleft" => ('⇃', Relation),
+        "longmapsto" => ('⟼', Relation),
+        "rightsquigarrow" => ('⇝', Relation),
+        "downharpoonright" => ('⇂', Relation),
+        "longrightarrow" => ('⟶', Relation),
+        "Rrightarrow" => ('⇛', Relation),
+        "Longrightarrow" | "implies" => ('⟹', Relation),
+        "Rsh" => ('↱', Relation),
+        "hookleftarrow" => ('↩', Relation),
+        "looparrowleft" => ('↫', Relation),
+        "searrow" => ('↘', Relation),
+        "hookrightarrow" => ('↪', Relation),
+        "looparrowright" => ('↬', Relation),
+        "swarrow" => ('↙', Relation),
+        "Lsh" => ('↰', Relation),
+        "mapsfrom" => ('↤', Relation),
+        "twoheadleftarrow" => ('↞', Relation),
+        "twoheadrightarrow" => ('↠', Relation),
+        "leadsto" => ('⇝', Relation),
+        "nearrow" => ('↗', Relation),
+        "uparrow" => ('↑', Relation),
+        "leftarrow" | "gets" => ('←', Relation),
+        "nleftarrow" => ('↚', Relation),
+        "Uparrow" => ('⇑', Relation),
+        "Leftarrow" => ('⇐', Relation),
+        "nLeftarrow" => ('⇍', Relation),
+        "updownarrow" => ('↕', Relation),
+        "leftarrowtail" => ('↢', Relation),
+        "nleftrightarrow" => ('↮', Relation),
+        "Updownarrow" => ('⇕', Relation),
+        "leftharpoondown" => ('↽', Relation),
+        "nLeftrightarrow" => ('⇎', Relation),
+        "upharpoonleft" => ('↿', Relation),
+        "leftharpoonup" => ('↼', Relation),
+        "nrightarrow" => ('↛', Relation),
+        "upharpoonright" | "restriction" => ('↾', Relation),
+        "leftleftarrows" => ('⇇', Relation),
+        "nRightarrow" => ('⇏', Relation),
+        "upuparrows" => ('⇈', Relation),
+        "leftrightarrow" => ('↔', Relation),
+        "nwarrow" => ('↖', Relation),
+    }
+}
+
+/// Returns the name of the command producing the given character, if it is produced by one of the
+/// commands above.
+///
+/// When more than one command produces the character, the first one in the tables is returned,
+/// with operators coming before identifiers.
+pub fn name(c: char) -> Option<&'static str> {
+    let operators = OPERATORS.iter().map(|&(name, (symbol, _))| (name, symbol));
+    let large_operators = LARGE_OPERATORS.iter().map(|&(name, (symbol, _))| (name, symbol));
+    operators
+        .chain(large_operators)
+        .chain(IDENTIFIERS.iter().copied())
+        .find_map(|(name, symbol)| (symbol == c).then_some(name))
+}
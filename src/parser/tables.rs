@@ -1,83 +1,238 @@
 // TODO: Consider using `phf`s for this
 
-// Faster impl, but does not include all operators
-// #[rustfmt::skip]
-// static OPERATOR_TABLE: &[(u16, u16)] = &[
-//     (33, 34), (37, 47), (58, 59), (63, 64), (91, 96),
-//     (123, 126), (168, 168), (172, 172), (175, 180), (183, 185),
-//     (215, 215), (247, 247), (710, 711), (713, 715), (717, 717),
-//     (728, 730), (732, 733), (759, 759), (770, 770), (785, 785),
-//     (800, 800), (802, 803), (805, 805), (807, 807), (814, 814),
-//     (817, 817), (8214, 8214), (8216, 8223), (8226, 8226), (8242, 8247),
-//     (8254, 8254), (8259, 8260), (8279, 8279), (8289, 8292), (8411, 8412),
-//     (8517, 8518), (8592, 8597), (8602, 8622), (8624, 8629), (8633, 8633),
-//     (8636, 8661), (8666, 8688), (8691, 8708), (8710, 8711), (8719, 8732),
-//     (8735, 8738), (8743, 8758), (8760, 8760), (8764, 8764), (8768, 8768),
-//     (8844, 8846), (8851, 8859), (8861, 8865), (8890, 8903), (8905, 8908),
-//     (8910, 8911), (8914, 8915), (8965, 8966), (8968, 8971), (8976, 8976),
-//     (8985, 8985), (8994, 8995), (9001, 9002), (9140, 9141), (9165, 9165),
-//     (9180, 9185), (10098, 10099), (10132, 10135), (10137, 10137), (10139, 10145),
-//     (10149, 10150), (10152, 10159), (10161, 10161), (10163, 10163), (10165, 10165),
-//     (10168, 10168), (10170, 10174), (10176, 10176), (10187, 10187), (10189, 10189),
-//     (10214, 10225), (10228, 10239), (10496, 10528), (10548, 10551), (10562, 10613),
-//     (10620, 10624), (10627, 10649), (10651, 10671), (10680, 10680), (10684, 10684),
-//     (10692, 10696), (10708, 10715), (10722, 10722), (10741, 10749), (10752, 10853),
-//     (10971, 10973), (10988, 10989), (10998, 10998), (11003, 11007), (11012, 11015),
-//     (11020, 11025), (11056, 11070), (11072, 11084), (11104, 11109), (11114, 11117),
-//     (11120, 11123), (11130, 11133), (11136, 11143), (11157, 11157), (11168, 11183),
-//     (11192, 11192)
-// ];
+use std::cmp::Ordering;
 
-// pub fn is_operator(c: char) -> bool {
-//     let c = c as u16;
-//
-//     let mut left = 0;
-//     let mut size = OPERATOR_TABLE.len();
-//     let mut right = size;
-//     while left < right {
-//         let mid = left + size / 2;
-//         let (start, end) = OPERATOR_TABLE[mid];
-//
-//         if c >= start && c <= end {
-//             return true;
-//         }
-//
-//         if c < start {
-//             right = mid;
-//         } else if c > end {
-//             left = mid + 1;
-//         } else {
-//             return true;
-//         }
-//
-//         size = right - left;
-//     }
-//     false
-// }
+use super::{symbols::symbol_table, Token};
+use crate::event::AtomClass;
 
-use super::Token;
+/// Inclusive ranges of characters that are considered operators, sorted in ascending order.
+#[rustfmt::skip]
+static OPERATOR_TABLE: &[(char, char)] = &[
+    ('\u{0021}', '\u{0022}'), ('\u{0025}', '\u{002F}'), ('\u{003A}', '\u{0040}'),
+    ('\u{005B}', '\u{0060}'), ('\u{007B}', '\u{007E}'), ('\u{00A8}', '\u{00A8}'),
+    ('\u{00AC}', '\u{00AC}'), ('\u{00AF}', '\u{00B4}'), ('\u{00B7}', '\u{00B9}'),
+    ('\u{00D7}', '\u{00D7}'), ('\u{00F7}', '\u{00F7}'), ('\u{02C6}', '\u{02C7}'),
+    ('\u{02C9}', '\u{02CB}'), ('\u{02CD}', '\u{02CD}'), ('\u{02D8}', '\u{02DA}'),
+    ('\u{02DC}', '\u{02DD}'), ('\u{02F7}', '\u{02F7}'), ('\u{0302}', '\u{0302}'),
+    ('\u{0311}', '\u{0311}'), ('\u{0320}', '\u{0320}'), ('\u{0322}', '\u{0323}'),
+    ('\u{0325}', '\u{0325}'), ('\u{0327}', '\u{0327}'), ('\u{032E}', '\u{032E}'),
+    ('\u{0331}', '\u{0331}'), ('\u{2016}', '\u{2016}'), ('\u{2018}', '\u{201F}'),
+    ('\u{2022}', '\u{2022}'), ('\u{2032}', '\u{2037}'), ('\u{203E}', '\u{203E}'),
+    ('\u{2043}', '\u{2044}'), ('\u{2057}', '\u{2057}'), ('\u{2061}', '\u{2064}'),
+    ('\u{20DB}', '\u{20DC}'), ('\u{2145}', '\u{2146}'), ('\u{2190}', '\u{2204}'),
+    ('\u{2206}', '\u{220D}'), ('\u{220F}', '\u{221D}'), ('\u{221F}', '\u{223E}'),
+    ('\u{2240}', '\u{22A3}'), ('\u{22A6}', '\u{22B8}'), ('\u{22BA}', '\u{22ED}'),
+    ('\u{22F2}', '\u{22FF}'), ('\u{2301}', '\u{2301}'), ('\u{2305}', '\u{2306}'),
+    ('\u{2308}', '\u{230B}'), ('\u{2310}', '\u{2310}'), ('\u{2319}', '\u{2319}'),
+    ('\u{2322}', '\u{2323}'), ('\u{2329}', '\u{232A}'), ('\u{237C}', '\u{237C}'),
+    ('\u{238B}', '\u{238B}'), ('\u{23B4}', '\u{23B5}'), ('\u{23CD}', '\u{23CD}'),
+    ('\u{23DC}', '\u{23E1}'), ('\u{2772}', '\u{2773}'), ('\u{2794}', '\u{27A1}'),
+    ('\u{27A5}', '\u{27AF}'), ('\u{27B1}', '\u{27BE}'), ('\u{27C0}', '\u{27C0}'),
+    ('\u{27C2}', '\u{27C2}'), ('\u{27CB}', '\u{27CB}'), ('\u{27CD}', '\u{27CD}'),
+    ('\u{27E6}', '\u{27FF}'), ('\u{2900}', '\u{2999}'), ('\u{299B}', '\u{29AF}'),
+    ('\u{29B6}', '\u{29B9}'), ('\u{29BC}', '\u{29BC}'), ('\u{29C0}', '\u{29C1}'),
+    ('\u{29C4}', '\u{29C8}'), ('\u{29CE}', '\u{29DB}'), ('\u{29DF}', '\u{29DF}'),
+    ('\u{29E1}', '\u{29E6}'), ('\u{29F4}', '\u{29FD}'), ('\u{2A00}', '\u{2AEE}'),
+    ('\u{2AF2}', '\u{2B11}'), ('\u{2B30}', '\u{2B4F}'), ('\u{2B5A}', '\u{2B73}'),
+    ('\u{2B76}', '\u{2B7D}'), ('\u{2B80}', '\u{2B8F}'), ('\u{2B94}', '\u{2B95}'),
+    ('\u{2BA0}', '\u{2BB8}'), ('\u{2BD1}', '\u{2BD1}'),
+];
+
+/// The precomposed characters made of a letter and a combining accent, sorted by letter and
+/// accent.
+#[rustfmt::skip]
+static PRECOMPOSED_TABLE: &[(char, char, &str)] = &[
+    ('A', '\u{0300}', "À"), ('A', '\u{0301}', "Á"), ('A', '\u{0302}', "Â"), ('A', '\u{0303}', "Ã"),
+    ('A', '\u{0304}', "Ā"), ('A', '\u{0306}', "Ă"), ('A', '\u{0307}', "Ȧ"), ('A', '\u{0308}', "Ä"),
+    ('A', '\u{030A}', "Å"), ('A', '\u{030C}', "Ǎ"), ('A', '\u{0323}', "Ạ"), ('A', '\u{0328}', "Ą"),
+    ('B', '\u{0307}', "Ḃ"), ('B', '\u{0323}', "Ḅ"), ('B', '\u{0331}', "Ḇ"), ('C', '\u{0301}', "Ć"),
+    ('C', '\u{0302}', "Ĉ"), ('C', '\u{0307}', "Ċ"), ('C', '\u{030C}', "Č"), ('C', '\u{0327}', "Ç"),
+    ('D', '\u{0307}', "Ḋ"), ('D', '\u{030C}', "Ď"), ('D', '\u{0323}', "Ḍ"), ('D', '\u{0327}', "Ḑ"),
+    ('D', '\u{0331}', "Ḏ"), ('E', '\u{0300}', "È"), ('E', '\u{0301}', "É"), ('E', '\u{0302}', "Ê"),
+    ('E', '\u{0303}', "Ẽ"), ('E', '\u{0304}', "Ē"), ('E', '\u{0306}', "Ĕ"), ('E', '\u{0307}', "Ė"),
+    ('E', '\u{0308}', "Ë"), ('E', '\u{030C}', "Ě"), ('E', '\u{0323}', "Ẹ"), ('E', '\u{0327}', "Ȩ"),
+    ('E', '\u{0328}', "Ę"), ('F', '\u{0307}', "Ḟ"), ('G', '\u{0301}', "Ǵ"), ('G', '\u{0302}', "Ĝ"),
+    ('G', '\u{0304}', "Ḡ"), ('G', '\u{0306}', "Ğ"), ('G', '\u{0307}', "Ġ"), ('G', '\u{030C}', "Ǧ"),
+    ('G', '\u{0327}', "Ģ"), ('H', '\u{0302}', "Ĥ"), ('H', '\u{0307}', "Ḣ"), ('H', '\u{0308}', "Ḧ"),
+    ('H', '\u{030C}', "Ȟ"), ('H', '\u{0323}', "Ḥ"), ('H', '\u{0327}', "Ḩ"), ('I', '\u{0300}', "Ì"),
+    ('I', '\u{0301}', "Í"), ('I', '\u{0302}', "Î"), ('I', '\u{0303}', "Ĩ"), ('I', '\u{0304}', "Ī"),
+    ('I', '\u{0306}', "Ĭ"), ('I', '\u{0307}', "İ"), ('I', '\u{0308}', "Ï"), ('I', '\u{030C}', "Ǐ"),
+    ('I', '\u{0323}', "Ị"), ('I', '\u{0328}', "Į"), ('J', '\u{0302}', "Ĵ"), ('K', '\u{0301}', "Ḱ"),
+    ('K', '\u{030C}', "Ǩ"), ('K', '\u{0323}', "Ḳ"), ('K', '\u{0327}', "Ķ"), ('K', '\u{0331}', "Ḵ"),
+    ('L', '\u{0301}', "Ĺ"), ('L', '\u{030C}', "Ľ"), ('L', '\u{0323}', "Ḷ"), ('L', '\u{0327}', "Ļ"),
+    ('L', '\u{0331}', "Ḻ"), ('M', '\u{0301}', "Ḿ"), ('M', '\u{0307}', "Ṁ"), ('M', '\u{0323}', "Ṃ"),
+    ('N', '\u{0300}', "Ǹ"), ('N', '\u{0301}', "Ń"), ('N', '\u{0303}', "Ñ"), ('N', '\u{0307}', "Ṅ"),
+    ('N', '\u{030C}', "Ň"), ('N', '\u{0323}', "Ṇ"), ('N', '\u{0327}', "Ņ"), ('N', '\u{0331}', "Ṉ"),
+    ('O', '\u{0300}', "Ò"), ('O', '\u{0301}', "Ó"), ('O', '\u{0302}', "Ô"), ('O', '\u{0303}', "Õ"),
+    ('O', '\u{0304}', "Ō"), ('O', '\u{0306}', "Ŏ"), ('O', '\u{0307}', "Ȯ"), ('O', '\u{0308}', "Ö"),
+    ('O', '\u{030B}', "Ő"), ('O', '\u{030C}', "Ǒ"), ('O', '\u{0323}', "Ọ"), ('O', '\u{0328}', "Ǫ"),
+    ('P', '\u{0301}', "Ṕ"), ('P', '\u{0307}', "Ṗ"), ('R', '\u{0301}', "Ŕ"), ('R', '\u{0307}', "Ṙ"),
+    ('R', '\u{030C}', "Ř"), ('R', '\u{0323}', "Ṛ"), ('R', '\u{0327}', "Ŗ"), ('R', '\u{0331}', "Ṟ"),
+    ('S', '\u{0301}', "Ś"), ('S', '\u{0302}', "Ŝ"), ('S', '\u{0307}', "Ṡ"), ('S', '\u{030C}', "Š"),
+    ('S', '\u{0323}', "Ṣ"), ('S', '\u{0327}', "Ş"), ('T', '\u{0307}', "Ṫ"), ('T', '\u{030C}', "Ť"),
+    ('T', '\u{0323}', "Ṭ"), ('T', '\u{0327}', "Ţ"), ('T', '\u{0331}', "Ṯ"), ('U', '\u{0300}', "Ù"),
+    ('U', '\u{0301}', "Ú"), ('U', '\u{0302}', "Û"), ('U', '\u{0303}', "Ũ"), ('U', '\u{0304}', "Ū"),
+    ('U', '\u{0306}', "Ŭ"), ('U', '\u{0308}', "Ü"), ('U', '\u{030A}', "Ů"), ('U', '\u{030B}', "Ű"),
+    ('U', '\u{030C}', "Ǔ"), ('U', '\u{0323}', "Ụ"), ('U', '\u{0328}', "Ų"), ('V', '\u{0303}', "Ṽ"),
+    ('V', '\u{0323}', "Ṿ"), ('W', '\u{0300}', "Ẁ"), ('W', '\u{0301}', "Ẃ"), ('W', '\u{0302}', "Ŵ"),
+    ('W', '\u{0307}', "Ẇ"), ('W', '\u{0308}', "Ẅ"), ('W', '\u{0323}', "Ẉ"), ('X', '\u{0307}', "Ẋ"),
+    ('X', '\u{0308}', "Ẍ"), ('Y', '\u{0300}', "Ỳ"), ('Y', '\u{0301}', "Ý"), ('Y', '\u{0302}', "Ŷ"),
+    ('Y', '\u{0303}', "Ỹ"), ('Y', '\u{0304}', "Ȳ"), ('Y', '\u{0307}', "Ẏ"), ('Y', '\u{0308}', "Ÿ"),
+    ('Y', '\u{0323}', "Ỵ"), ('Z', '\u{0301}', "Ź"), ('Z', '\u{0302}', "Ẑ"), ('Z', '\u{0307}', "Ż"),
+    ('Z', '\u{030C}', "Ž"), ('Z', '\u{0323}', "Ẓ"), ('Z', '\u{0331}', "Ẕ"), ('a', '\u{0300}', "à"),
+    ('a', '\u{0301}', "á"), ('a', '\u{0302}', "â"), ('a', '\u{0303}', "ã"), ('a', '\u{0304}', "ā"),
+    ('a', '\u{0306}', "ă"), ('a', '\u{0307}', "ȧ"), ('a', '\u{0308}', "ä"), ('a', '\u{030A}', "å"),
+    ('a', '\u{030C}', "ǎ"), ('a', '\u{0323}', "ạ"), ('a', '\u{0328}', "ą"), ('b', '\u{0307}', "ḃ"),
+    ('b', '\u{0323}', "ḅ"), ('b', '\u{0331}', "ḇ"), ('c', '\u{0301}', "ć"), ('c', '\u{0302}', "ĉ"),
+    ('c', '\u{0307}', "ċ"), ('c', '\u{030C}', "č"), ('c', '\u{0327}', "ç"), ('d', '\u{0307}', "ḋ"),
+    ('d', '\u{030C}', "ď"), ('d', '\u{0323}', "ḍ"), ('d', '\u{0327}', "ḑ"), ('d', '\u{0331}', "ḏ"),
+    ('e', '\u{0300}', "è"), ('e', '\u{0301}', "é"), ('e', '\u{0302}', "ê"), ('e', '\u{0303}', "ẽ"),
+    ('e', '\u{0304}', "ē"), ('e', '\u{0306}', "ĕ"), ('e', '\u{0307}', "ė"), ('e', '\u{0308}', "ë"),
+    ('e', '\u{030C}', "ě"), ('e', '\u{0323}', "ẹ"), ('e', '\u{0327}', "ȩ"), ('e', '\u{0328}', "ę"),
+    ('f', '\u{0307}', "ḟ"), ('g', '\u{0301}', "ǵ"), ('g', '\u{0302}', "ĝ"), ('g', '\u{0304}', "ḡ"),
+    ('g', '\u{0306}', "ğ"), ('g', '\u{0307}', "ġ"), ('g', '\u{030C}', "ǧ"), ('g', '\u{0327}', "ģ"),
+    ('h', '\u{0302}', "ĥ"), ('h', '\u{0307}', "ḣ"), ('h', '\u{0308}', "ḧ"), ('h', '\u{030C}', "ȟ"),
+    ('h', '\u{0323}', "ḥ"), ('h', '\u{0327}', "ḩ"), ('h', '\u{0331}', "ẖ"), ('i', '\u{0300}', "ì"),
+    ('i', '\u{0301}', "í"), ('i', '\u{0302}', "î"), ('i', '\u{0303}', "ĩ"), ('i', '\u{0304}', "ī"),
+    ('i', '\u{0306}', "ĭ"), ('i', '\u{0308}', "ï"), ('i', '\u{030C}', "ǐ"), ('i', '\u{0323}', "ị"),
+    ('i', '\u{0328}', "į"), ('j', '\u{0302}', "ĵ"), ('j', '\u{030C}', "ǰ"), ('k', '\u{0301}', "ḱ"),
+    ('k', '\u{030C}', "ǩ"), ('k', '\u{0323}', "ḳ"), ('k', '\u{0327}', "ķ"), ('k', '\u{0331}', "ḵ"),
+    ('l', '\u{0301}', "ĺ"), ('l', '\u{030C}', "ľ"), ('l', '\u{0323}', "ḷ"), ('l', '\u{0327}', "ļ"),
+    ('l', '\u{0331}', "ḻ"), ('m', '\u{0301}', "ḿ"), ('m', '\u{0307}', "ṁ"), ('m', '\u{0323}', "ṃ"),
+    ('n', '\u{0300}', "ǹ"), ('n', '\u{0301}', "ń"), ('n', '\u{0303}', "ñ"), ('n', '\u{0307}', "ṅ"),
+    ('n', '\u{030C}', "ň"), ('n', '\u{0323}', "ṇ"), ('n', '\u{0327}', "ņ"), ('n', '\u{0331}', "ṉ"),
+    ('o', '\u{0300}', "ò"), ('o', '\u{0301}', "ó"), ('o', '\u{0302}', "ô"), ('o', '\u{0303}', "õ"),
+    ('o', '\u{0304}', "ō"), ('o', '\u{0306}', "ŏ"), ('o', '\u{0307}', "ȯ"), ('o', '\u{0308}', "ö"),
+    ('o', '\u{030B}', "ő"), ('o', '\u{030C}', "ǒ"), ('o', '\u{0323}', "ọ"), ('o', '\u{0328}', "ǫ"),
+    ('p', '\u{0301}', "ṕ"), ('p', '\u{0307}', "ṗ"), ('r', '\u{0301}', "ŕ"), ('r', '\u{0307}', "ṙ"),
+    ('r', '\u{030C}', "ř"), ('r', '\u{0323}', "ṛ"), ('r', '\u{0327}', "ŗ"), ('r', '\u{0331}', "ṟ"),
+    ('s', '\u{0301}', "ś"), ('s', '\u{0302}', "ŝ"), ('s', '\u{0307}', "ṡ"), ('s', '\u{030C}', "š"),
+    ('s', '\u{0323}', "ṣ"), ('s', '\u{0327}', "ş"), ('t', '\u{0307}', "ṫ"), ('t', '\u{0308}', "ẗ"),
+    ('t', '\u{030C}', "ť"), ('t', '\u{0323}', "ṭ"), ('t', '\u{0327}', "ţ"), ('t', '\u{0331}', "ṯ"),
+    ('u', '\u{0300}', "ù"), ('u', '\u{0301}', "ú"), ('u', '\u{0302}', "û"), ('u', '\u{0303}', "ũ"),
+    ('u', '\u{0304}', "ū"), ('u', '\u{0306}', "ŭ"), ('u', '\u{0308}', "ü"), ('u', '\u{030A}', "ů"),
+    ('u', '\u{030B}', "ű"), ('u', '\u{030C}', "ǔ"), ('u', '\u{0323}', "ụ"), ('u', '\u{0328}', "ų"),
+    ('v', '\u{0303}', "ṽ"), ('v', '\u{0323}', "ṿ"), ('w', '\u{0300}', "ẁ"), ('w', '\u{0301}', "ẃ"),
+    ('w', '\u{0302}', "ŵ"), ('w', '\u{0307}', "ẇ"), ('w', '\u{0308}', "ẅ"), ('w', '\u{030A}', "ẘ"),
+    ('w', '\u{0323}', "ẉ"), ('x', '\u{0307}', "ẋ"), ('x', '\u{0308}', "ẍ"), ('y', '\u{0300}', "ỳ"),
+    ('y', '\u{0301}', "ý"), ('y', '\u{0302}', "ŷ"), ('y', '\u{0303}', "ỹ"), ('y', '\u{0304}', "ȳ"),
+    ('y', '\u{0307}', "ẏ"), ('y', '\u{0308}', "ÿ"), ('y', '\u{030A}', "ẙ"), ('y', '\u{0323}', "ỵ"),
+    ('z', '\u{0301}', "ź"), ('z', '\u{0302}', "ẑ"), ('z', '\u{0307}', "ż"), ('z', '\u{030C}', "ž"),
+    ('z', '\u{0323}', "ẓ"), ('z', '\u{0331}', "ẕ"),
+];
+
+/// The combining character of the accent produced by the given text mode command (e.g., `\u{301}`
+/// for `\'`).
+pub fn text_accent(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "`" => "\u{300}",
+        "'" => "\u{301}",
+        "^" => "\u{302}",
+        "~" => "\u{303}",
+        "=" => "\u{304}",
+        "u" => "\u{306}",
+        "." => "\u{307}",
+        "\"" => "\u{308}",
+        "r" => "\u{30A}",
+        "H" => "\u{30B}",
+        "v" => "\u{30C}",
+        "d" => "\u{323}",
+        "c" => "\u{327}",
+        "k" => "\u{328}",
+        "b" => "\u{331}",
+        _ => return None,
+    })
+}
 
+/// The precomposed character made of the given letter and combining accent, if there is one.
+pub fn precomposed(letter: char, accent: char) -> Option<&'static str> {
+    PRECOMPOSED_TABLE
+        .binary_search_by(|&(l, a, _)| (l, a).cmp(&(letter, accent)))
+        .ok()
+        .map(|index| PRECOMPOSED_TABLE[index].2)
+}
+
+/// The characters of the given number of primes, using the precomposed multiple primes (e.g.,
+/// `″` for two primes) for as many of them as possible.
+pub fn primes(count: usize) -> impl Iterator<Item = char> {
+    let last = match count % 4 {
+        0 => None,
+        1 => Some('′'),
+        2 => Some('″'),
+        _ => Some('‴'),
+    };
+    core::iter::repeat_n('⁗', count / 4).chain(last)
+}
+
+/// The precomposed negation of the given relation, produced by `\not` (e.g., `≠` for `=`).
+pub fn negated_relation(relation: char) -> Option<char> {
+    Some(match relation {
+        '=' => '≠',
+        '<' => '≮',
+        '>' => '≯',
+        '≤' => '≰',
+        '≥' => '≱',
+        '≡' => '≢',
+        '∼' => '≁',
+        '≃' => '≄',
+        '≅' => '≇',
+        '≈' => '≉',
+        '≍' => '≭',
+        '∈' => '∉',
+        '∋' => '∌',
+        '⊂' => '⊄',
+        '⊃' => '⊅',
+        '⊆' => '⊈',
+        '⊇' => '⊉',
+        '⊑' => '⋢',
+        '⊒' => '⋣',
+        '≺' => '⊀',
+        '≻' => '⊁',
+        '≼' => '⋠',
+        '≽' => '⋡',
+        '⊢' => '⊬',
+        '⊨' => '⊭',
+        '⊩' => '⊮',
+        '⊫' => '⊯',
+        '∣' => '∤',
+        '∥' => '∦',
+        '⊲' => '⋪',
+        '⊳' => '⋫',
+        '⊴' => '⋬',
+        '⊵' => '⋭',
+        '≲' => '≴',
+        '≳' => '≵',
+        '≶' => '≸',
+        '≷' => '≹',
+        '←' => '↚',
+        '→' => '↛',
+        '↔' => '↮',
+        '⇐' => '⇍',
+        '⇒' => '⇏',
+        '⇔' => '⇎',
+        _ => return None,
+    })
+}
+
+/// Returns whether the given character is an operator.
 pub fn is_operator(c: char) -> bool {
-    matches!(
-    c,
-    '\u{0021}'..='\u{0022}' | '\u{0025}'..='\u{002F}' | '\u{003A}'..='\u{0040}' | '\u{005B}'..='\u{0060}' |
-    '\u{007B}'..='\u{007E}' | '\u{00A8}' | '\u{00AC}' | '\u{00AF}'..='\u{00B4}' | '\u{00B7}'..='\u{00B9}' |
-    '\u{00D7}' | '\u{00F7}' | '\u{02C6}'..='\u{02C7}' | '\u{02C9}'..='\u{02CB}' | '\u{02CD}' | '\u{02D8}'..='\u{02DA}' |
-    '\u{02DC}'..='\u{02DD}' | '\u{02F7}' | '\u{0302}' | '\u{0311}' | '\u{0320}' | '\u{0322}'..='\u{0323}' |
-    '\u{0325}' | '\u{0327}' | '\u{032E}' | '\u{0331}' | '\u{2016}' | '\u{2018}'..='\u{201F}' | '\u{2022}' |
-    '\u{2032}'..='\u{2037}' | '\u{203E}' | '\u{2043}'..='\u{2044}' | '\u{2057}' | '\u{2061}'..='\u{2064}' |
-    '\u{20DB}'..='\u{20DC}' | '\u{2145}'..='\u{2146}' | '\u{2190}'..='\u{2204}' | '\u{2206}'..='\u{220D}' |
-    '\u{220F}'..='\u{221D}' | '\u{221F}'..='\u{223E}' | '\u{2240}'..='\u{22A3}' | '\u{22A6}'..='\u{22B8}' |
-    '\u{22BA}'..='\u{22ED}' | '\u{22F2}'..='\u{22FF}' | '\u{2301}' | '\u{2305}'..='\u{2306}' | '\u{2308}'..='\u{230B}' |
-    '\u{2310}' | '\u{2319}' | '\u{2322}'..='\u{2323}' | '\u{2329}'..='\u{232A}' | '\u{237C}' | '\u{238B}' |
-    '\u{23B4}'..='\u{23B5}' | '\u{23CD}' | '\u{23DC}'..='\u{23E1}' | '\u{2772}'..='\u{2773}' | '\u{2794}'..='\u{27A1}' |
-    '\u{27A5}'..='\u{27AF}' | '\u{27B1}'..='\u{27BE}' | '\u{27C0}' | '\u{27C2}' | '\u{27CB}' | '\u{27CD}' |
-    '\u{27E6}'..='\u{27FF}' | '\u{2900}'..='\u{2999}' | '\u{299B}'..='\u{29AF}' | '\u{29B6}'..='\u{29B9}' |
-    '\u{29BC}' | '\u{29C0}'..='\u{29C1}' | '\u{29C4}'..='\u{29C8}' | '\u{29CE}'..='\u{29DB}' | '\u{29DF}' |
-    '\u{29E1}'..='\u{29E6}' | '\u{29F4}'..='\u{29FD}' | '\u{2A00}'..='\u{2AEE}' | '\u{2AF2}'..='\u{2B11}' |
-    '\u{2B30}'..='\u{2B4F}' | '\u{2B5A}'..='\u{2B73}' | '\u{2B76}'..='\u{2B7D}' | '\u{2B80}'..='\u{2B8F}' |
-    '\u{2B94}' | '\u{2B95}' | '\u{2BA0}'..='\u{2BB8}' | '\u{2BD1}'
-    )
+    OPERATOR_TABLE
+        .binary_search_by(|&(start, end)| {
+            if c < start {
+                Ordering::Greater
+            } else if c > end {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Whether the character can be the delimiter of a `\left ... \right` group, including those
+/// only written as control sequences.
+pub fn is_delimiter(c: char) -> bool {
+    is_char_delimiter(c) || matches!(c, '{' | '}' | '\\')
 }
 
 #[rustfmt::skip]
@@ -87,16 +242,16 @@ pub fn is_char_delimiter(c: char) -> bool {
           '(' | ')' | '⦇' | '⦈' | '⟮' | '⟯'
         | '[' | ']' | '⟦' | '⟧' | '⦃' | '⦄'
         | '⟨' | '⟩' | '⟪' | '⟫' | '⦉' | '⦊'
-        | '⌊' | '⌋' | '⌈' | '⌉' | '┌' | '┐'
-        | '└' | '┘' | '⎰' | '⎱' | '|' | '‖'
+        | '⌊' | '⌋' | '⌈' | '⌉' | '⌜' | '⌝'
+        | '⌞' | '⌟' | '⎰' | '⎱' | '|' | '‖'
         | '↑' | '⇑' | '↓' | '⇓' | '↕' | '⇕'
-        | '/'
+        | '⏐' | '⎪' | '/'
     )
 }
 
-/// Returns the matching delimiter for the given control sequence, if it exists.
-pub fn control_sequence_delimiter_map(cs: &str) -> Option<char> {
-    Some(match cs {
+symbol_table! {
+    /// Returns the matching delimiter for the given control sequence, if it exists.
+    pub fn control_sequence_delimiter_map -> char, static CONTROL_SEQUENCE_DELIMITERS {
         "lparen" => '(',
         "rparen" => ')',
         "llparenthesis" => '⦇',
@@ -106,8 +261,8 @@ pub fn control_sequence_delimiter_map(cs: &str) -> Option<char> {
 
         "lbrack" => '[',
         "rbrack" => ']',
-        "lBrack" => '⟦',
-        "rBrack" => '⟧',
+        "lBrack" | "llbracket" => '⟦',
+        "rBrack" | "rrbracket" => '⟧',
 
         "{" | "lbrace" => '{',
         "}" | "rbrace" => '}',
@@ -125,25 +280,53 @@ pub fn control_sequence_delimiter_map(cs: &str) -> Option<char> {
         "rfloor" => '⌋',
         "lceil" => '⌈',
         "rceil" => '⌉',
-        "ulcorner" => '┌',
-        "urcorner" => '┐',
-        "llcorner" => '└',
-        "lrcorner" => '┘',
+        "ulcorner" => '⌜',
+        "urcorner" => '⌝',
+        "llcorner" => '⌞',
+        "lrcorner" => '⌟',
 
         "lmoustache" => '⎰',
         "rmoustache" => '⎱',
         "backslash" => '\\',
 
         "vert" | "lvert" | "rvert" => '|',
-        "|" | "Vert" | "lVert" | "rVert" => '‖',
+        "|" | "Vert" | "lVert" | "rVert" | "Arrowvert" => '‖',
+        "arrowvert" => '⏐',
+        "bracevert" => '⎪',
         "uparrow" => '↑',
         "Uparrow" => '⇑',
         "downarrow" => '↓',
         "Downarrow" => '⇓',
         "updownarrow" => '↕',
         "Updownarrow" => '⇕',
-        _ => return None,
-    })
+    }
+}
+
+/// Returns the class of the given delimiter, or `None` if it is a fence, which can be used as
+/// both an opening and a closing delimiter (e.g., `|`).
+pub fn delimiter_class(delimiter: char) -> Option<AtomClass> {
+    match delimiter {
+        '(' | '⦇' | '⟮' | '[' | '⟦' | '{' | '⦃' | '⟨' | '⟪' | '⦉' | '⌊' | '⌈' | '⌜' | '⌞' | '⎰' => {
+            Some(AtomClass::Opening)
+        }
+        ')' | '⦈' | '⟯' | ']' | '⟧' | '}' | '⦄' | '⟩' | '⟫' | '⦊' | '⌋' | '⌉' | '⌝' | '⌟' | '⎱' => {
+            Some(AtomClass::Closing)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the class of the delimiter produced by the given control sequence, or `None` if it is
+/// a fence.
+///
+/// The fences `|` and `‖` are opening or closing delimiters when produced by `\lvert` and `\rvert`
+/// (resp. `\lVert` and `\rVert`).
+pub fn control_sequence_delimiter_class(cs: &str) -> Option<AtomClass> {
+    match cs {
+        "lvert" | "lVert" => Some(AtomClass::Opening),
+        "rvert" | "rVert" => Some(AtomClass::Closing),
+        _ => delimiter_class(control_sequence_delimiter_map(cs)?),
+    }
 }
 
 pub fn token_to_delim(token: Token) -> Option<char> {
@@ -315,3 +498,62 @@ pub fn is_primitive_color(color: &str) -> bool {
                 | "yellowgreen"
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::lex;
+
+    #[test]
+    fn operator_table_is_sorted() {
+        assert!(OPERATOR_TABLE.iter().all(|(start, end)| start <= end));
+        assert!(OPERATOR_TABLE
+            .windows(2)
+            .all(|window| window[0].1 < window[1].0));
+    }
+
+    #[test]
+    fn delimiter_round_trip() {
+        for (name, delimiter, class) in [
+            ("lgroup", '⟮', Some(AtomClass::Opening)),
+            ("rgroup", '⟯', Some(AtomClass::Closing)),
+            ("lmoustache", '⎰', Some(AtomClass::Opening)),
+            ("rmoustache", '⎱', Some(AtomClass::Closing)),
+            ("arrowvert", '⏐', None),
+            ("Arrowvert", '‖', None),
+            ("bracevert", '⎪', None),
+            ("llbracket", '⟦', Some(AtomClass::Opening)),
+            ("rrbracket", '⟧', Some(AtomClass::Closing)),
+            ("llparenthesis", '⦇', Some(AtomClass::Opening)),
+            ("rrparenthesis", '⦈', Some(AtomClass::Closing)),
+            ("ulcorner", '⌜', Some(AtomClass::Opening)),
+            ("urcorner", '⌝', Some(AtomClass::Closing)),
+            ("llcorner", '⌞', Some(AtomClass::Opening)),
+            ("lrcorner", '⌟', Some(AtomClass::Closing)),
+        ] {
+            let input = format!("\\{name}");
+            assert_eq!(lex::delimiter(&mut &input[..]).unwrap(), delimiter);
+            let input = delimiter.to_string();
+            assert_eq!(lex::delimiter(&mut &input[..]).unwrap(), delimiter);
+            assert_eq!(delimiter_class(delimiter), class, "{name}");
+        }
+    }
+
+    #[test]
+    fn operator_range_boundaries() {
+        assert!(!is_operator(' '));
+        assert!(is_operator('!'));
+        assert!(is_operator('"'));
+        assert!(!is_operator('#'));
+        // Between the ranges `!..="` and `%..=/`.
+        assert!(!is_operator('$'));
+        assert!(is_operator('%'));
+        assert!(is_operator('/'));
+        assert!(!is_operator('0'));
+        assert!(!is_operator('a'));
+        // Around the last range of the table.
+        assert!(is_operator('\u{2BD1}'));
+        assert!(!is_operator('\u{2BD2}'));
+        assert!(!is_operator(char::MAX));
+    }
+}
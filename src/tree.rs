@@ -0,0 +1,395 @@
+//! A tree representation of the events produced by the [`Parser`].
+//!
+//! The flat stream of events is convenient to render, but transformations which operate on whole
+//! elements (e.g., replacing every fraction by a division) are easier to write over a tree. The
+//! [`parse_tree`] and [`build_tree`] functions fold the events into a [`MathNode`], where each
+//! group, script and visual element owns its children.
+//!
+//! [`Parser`]: crate::parser::Parser
+
+use thiserror::Error;
+
+use crate::{
+    attribute::Dimension,
+    event::{
+        AnnotationKind, BoxAlignment, Content, Event, Grouping, ScriptPosition, ScriptType,
+        StateChange, Visual,
+    },
+    parser::{Parser, ParserError},
+};
+
+/// A node of the tree built from the events of the [`Parser`].
+///
+/// [`Parser`]: crate::parser::Parser
+#[derive(Debug, Clone, PartialEq)]
+pub enum MathNode<'a> {
+    /// A [`Content`] element.
+    Content(Content<'a>),
+    /// A sequence of elements, such as the whole input.
+    Row(Vec<MathNode<'a>>),
+    /// A group of elements, delimited by an [`Event::Begin`] and an [`Event::End`].
+    Group {
+        grouping: Grouping,
+        children: Vec<MathNode<'a>>,
+    },
+    /// A fraction, as produced by [`Visual::Fraction`].
+    Fraction {
+        numerator: Box<MathNode<'a>>,
+        denominator: Box<MathNode<'a>>,
+        bar: Option<Dimension>,
+    },
+    /// A fraction split across two lines, as produced by [`Visual::SplitFraction`].
+    SplitFraction {
+        first: Box<MathNode<'a>>,
+        second: Box<MathNode<'a>>,
+    },
+    /// A continued fraction, as produced by [`Visual::ContinuedFraction`].
+    ContinuedFraction {
+        numerator: Box<MathNode<'a>>,
+        denominator: Box<MathNode<'a>>,
+        alignment: BoxAlignment,
+    },
+    /// A root, as produced by [`Visual::SquareRoot`] (without an index) and [`Visual::Root`].
+    Root {
+        radicand: Box<MathNode<'a>>,
+        index: Option<Box<MathNode<'a>>>,
+    },
+    /// An accent placed above its base, as produced by [`Visual::Accent`].
+    Accent {
+        base: Box<MathNode<'a>>,
+        accent: Box<MathNode<'a>>,
+    },
+    /// Any other [`Visual`] element, which applies to a single element.
+    Visual {
+        visual: Visual,
+        content: Box<MathNode<'a>>,
+    },
+    /// A base along with its scripts, as produced by [`Event::Script`].
+    Script {
+        position: ScriptPosition,
+        base: Box<MathNode<'a>>,
+        sub: Option<Box<MathNode<'a>>>,
+        sup: Option<Box<MathNode<'a>>>,
+    },
+    /// A custom spacing element, as produced by [`Event::Space`].
+    Space {
+        width: Option<Dimension>,
+        height: Option<Dimension>,
+        depth: Option<Dimension>,
+    },
+    /// A state change, which applies to the following nodes of the same group.
+    StateChange(StateChange<'a>),
+    /// An alignment mark in a mathematical environment.
+    Alignment,
+    /// A line break in a mathematical environment.
+    NewLine,
+    /// A command that the parser does not know, as produced by [`Event::UnknownCommand`].
+    UnknownCommand(&'a str),
+    /// An element annotated with an HTML attribute, as produced by [`Event::Annotation`].
+    Annotation {
+        kind: AnnotationKind,
+        value: &'a str,
+        content: Box<MathNode<'a>>,
+    },
+    /// A link to the given URL, as produced by [`Event::Link`].
+    Link {
+        url: &'a str,
+        content: Box<MathNode<'a>>,
+    },
+}
+
+struct TreeBuilder<'a> {
+    events: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    /// Build the nodes of the current group, until its end.
+    fn children(&mut self) -> Result<Vec<MathNode<'a>>, &'static str> {
+        let mut children = Vec::new();
+        loop {
+            match self.events.next() {
+                None => return Err("the events end inside of a group"),
+                Some(Event::End) => return Ok(children),
+                Some(event) => children.push(self.node(event)?),
+            }
+        }
+    }
+
+    /// Build the node of the next element.
+    fn element(&mut self) -> Result<Box<MathNode<'a>>, &'static str> {
+        match self.events.next() {
+            None => Err("the events end before an element is complete"),
+            Some(event) => self.node(event).map(Box::new),
+        }
+    }
+
+    /// Build the node starting with the given event.
+    fn node(&mut self, event: Event<'a>) -> Result<MathNode<'a>, &'static str> {
+        Ok(match event {
+            Event::Content(content) => MathNode::Content(content),
+            Event::Begin(grouping) => MathNode::Group {
+                grouping,
+                children: self.children()?,
+            },
+            Event::End => return Err("an `End` event does not close a group"),
+            Event::Visual(visual) => self.visual(visual)?,
+            Event::Script { ty, position } => {
+                let base = self.element()?;
+                let (sub, sup) = match ty {
+                    ScriptType::Subscript => (Some(self.element()?), None),
+                    ScriptType::Superscript => (None, Some(self.element()?)),
+                    ScriptType::SubSuperscript => (Some(self.element()?), Some(self.element()?)),
+                };
+                MathNode::Script {
+                    position,
+                    base,
+                    sub,
+                    sup,
+                }
+            }
+            Event::Space {
+                width,
+                height,
+                depth,
+            } => MathNode::Space {
+                width,
+                height,
+                depth,
+            },
+            Event::StateChange(state_change) => MathNode::StateChange(state_change),
+            Event::Alignment => MathNode::Alignment,
+            Event::NewLine => MathNode::NewLine,
+            Event::UnknownCommand(name) => MathNode::UnknownCommand(name),
+            Event::Annotation { kind, value } => MathNode::Annotation {
+                kind,
+                value,
+                content: self.element()?,
+            },
+            Event::Link { url } => MathNode::Link {
+                url,
+                content: self.element()?,
+            },
+        })
+    }
+
+    fn visual(&mut self, visual: Visual) -> Result<MathNode<'a>, &'static str> {
+        Ok(match visual {
+            Visual::SquareRoot => MathNode::Root {
+                radicand: self.element()?,
+                index: None,
+            },
+            Visual::Root => MathNode::Root {
+                radicand: self.element()?,
+                index: Some(self.element()?),
+            },
+            Visual::Fraction(bar) => MathNode::Fraction {
+                numerator: self.element()?,
+                denominator: self.element()?,
+                bar,
+            },
+            Visual::SplitFraction => MathNode::SplitFraction {
+                first: self.element()?,
+                second: self.element()?,
+            },
+            Visual::ContinuedFraction(alignment) => MathNode::ContinuedFraction {
+                numerator: self.element()?,
+                denominator: self.element()?,
+                alignment,
+            },
+            Visual::Accent => MathNode::Accent {
+                base: self.element()?,
+                accent: self.element()?,
+            },
+            Visual::Negation
+            | Visual::Boxed
+            | Visual::Framed { .. }
+            | Visual::Lap(_)
+            | Visual::Raise(_)
+            | Visual::VCenter
+            | Visual::Strike(_) => MathNode::Visual {
+                visual,
+                content: self.element()?,
+            },
+        })
+    }
+}
+
+/// Build the tree of the events produced by the given [`Parser`], or any other iterator of events.
+///
+/// The returned node is a [`MathNode::Row`] containing the top-level elements. If the events
+/// contain an error, or do not form valid elements, an error is returned.
+///
+/// ```rust
+/// use pulldown_latex::{tree::{build_tree, MathNode}, Parser};
+///
+/// let tree = build_tree(Parser::new(r"\frac{1}{2}")).unwrap();
+/// let MathNode::Row(nodes) = tree else { unreachable!() };
+/// assert!(matches!(nodes[..], [MathNode::Fraction { .. }]));
+/// ```
+///
+/// [`Parser`]: crate::parser::Parser
+pub fn build_tree<'a, I, E>(parser: I) -> Result<MathNode<'a>, TreeError<E>>
+where
+    I: Iterator<Item = Result<Event<'a>, E>>,
+{
+    let events = parser
+        .collect::<Result<Vec<_>, E>>()
+        .map_err(TreeError::Event)?;
+    let mut builder = TreeBuilder {
+        events: events.into_iter(),
+    };
+    let mut nodes = Vec::new();
+    while let Some(event) = builder.events.next() {
+        nodes.push(builder.node(event).map_err(TreeError::Unsupported)?);
+    }
+    Ok(MathNode::Row(nodes))
+}
+
+/// Parse the given input, using the default configuration, and build the tree of its events.
+///
+/// See [`build_tree`] for more details.
+pub fn parse_tree(input: &str) -> Result<MathNode<'_>, TreeError<ParserError<'_>>> {
+    build_tree(Parser::new(input))
+}
+
+/// An error encountered while building the tree of events.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TreeError<E> {
+    /// An error found in the events, such as a [`ParserError`].
+    #[error(transparent)]
+    Event(E),
+    /// The events do not form valid elements, e.g., an [`Event::End`] which does not close a
+    /// group, or the events end before an element is complete.
+    #[error("unsupported event: {0}")]
+    Unsupported(&'static str),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{Identifier, Operator};
+
+    use super::*;
+
+    fn ident(c: char) -> MathNode<'static> {
+        MathNode::Content(Content::Identifier(Identifier::Char(c)))
+    }
+
+    fn number(n: &str) -> MathNode<'_> {
+        MathNode::Content(Content::Number(n))
+    }
+
+    fn row(input: &str) -> Vec<MathNode<'_>> {
+        match parse_tree(input).unwrap() {
+            MathNode::Row(nodes) => nodes,
+            node => panic!("expected a row, got {node:?}"),
+        }
+    }
+
+    #[test]
+    fn scripts() {
+        assert_eq!(
+            row("x_1^2 + y^{n}"),
+            vec![
+                MathNode::Script {
+                    position: ScriptPosition::Right,
+                    base: Box::new(ident('x')),
+                    sub: Some(Box::new(number("1"))),
+                    sup: Some(Box::new(number("2"))),
+                },
+                MathNode::Content(Content::Operator(Operator {
+                    content: '+',
+                    ..Default::default()
+                })),
+                MathNode::Script {
+                    position: ScriptPosition::Right,
+                    base: Box::new(ident('y')),
+                    sub: None,
+                    sup: Some(Box::new(MathNode::Group {
+                        grouping: Grouping::Normal,
+                        children: vec![ident('n')],
+                    })),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_visuals() {
+        assert_eq!(
+            row(r"\frac{\sqrt{a}}{\sqrt[3]b}"),
+            vec![MathNode::Fraction {
+                numerator: Box::new(MathNode::Group {
+                    grouping: Grouping::Normal,
+                    children: vec![MathNode::Root {
+                        radicand: Box::new(MathNode::Group {
+                            grouping: Grouping::Normal,
+                            children: vec![ident('a')],
+                        }),
+                        index: None,
+                    }],
+                }),
+                denominator: Box::new(MathNode::Group {
+                    grouping: Grouping::Normal,
+                    children: vec![MathNode::Root {
+                        radicand: Box::new(ident('b')),
+                        index: Some(Box::new(number("3"))),
+                    }],
+                }),
+                bar: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let nodes = row(r"\begin{matrix} a & b \\ c \end{matrix} \not\perp");
+        let [MathNode::Group {
+            grouping: Grouping::Matrix,
+            children,
+        }, MathNode::Visual {
+            visual: Visual::Negation,
+            ..
+        }] = &nodes[..]
+        else {
+            panic!("unexpected nodes: {nodes:?}");
+        };
+        assert_eq!(
+            children[..],
+            [
+                ident('a'),
+                MathNode::Alignment,
+                ident('b'),
+                MathNode::NewLine,
+                ident('c'),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = parse_tree(r"\frac{a}").unwrap_err();
+        assert!(matches!(error, TreeError::Event(_)));
+
+        let x = Event::Content(Content::Identifier(Identifier::Char('x')));
+        let superscript = Event::Script {
+            ty: ScriptType::Superscript,
+            position: ScriptPosition::Right,
+        };
+        for events in [
+            vec![Event::End],
+            vec![x, Event::End],
+            vec![Event::Begin(Grouping::Normal), x],
+            // A fraction missing its denominator.
+            vec![Event::Visual(Visual::Fraction(None)), x],
+            vec![superscript, x],
+            vec![superscript, Event::End],
+        ] {
+            let result = build_tree(events.into_iter().map(Ok::<_, std::fmt::Error>));
+            assert!(
+                matches!(result, Err(TreeError::Unsupported(_))),
+                "{result:?}"
+            );
+        }
+    }
+}
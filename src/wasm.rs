@@ -0,0 +1,110 @@
+//! Bindings to the parser and the `mathml` renderer for JavaScript, through `wasm-bindgen`.
+//!
+//! This module is only available with the `wasm` feature. The WebAssembly module and its
+//! JavaScript bindings are built with `npm run build`, as described in `package.json`.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    config::DisplayMode, event::OwnedEvent, mathml::push_mathml, parser::Storage, Parser,
+    ParserConfig, ParserError, RenderConfig,
+};
+
+/// Parse the input and render it to MathML, in display mode if `display` is true.
+///
+/// The first error found in the input is thrown as a JavaScript `Error`.
+#[wasm_bindgen]
+pub fn parse_to_mathml(input: &str, display: bool) -> Result<String, JsValue> {
+    let display_mode = if display {
+        DisplayMode::Block
+    } else {
+        DisplayMode::Inline
+    };
+    let config = ParserConfig {
+        display_mode,
+        ..Default::default()
+    };
+    let storage = Storage::new();
+    let events = Parser::with_storage(input, config, &storage)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| JsError::new(&error.to_string()))?;
+    let mut output = String::new();
+    let config = RenderConfig {
+        display_mode,
+        ..Default::default()
+    };
+    push_mathml(
+        &mut output,
+        events.into_iter().map(Ok::<_, ParserError>),
+        config,
+    )
+    .map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(output)
+}
+
+/// A parser returning the events of its input one at a time.
+///
+/// Parsing is eager: since the events produced by a [`Parser`] borrow its input, the whole input
+/// is parsed when the `WasmParser` is created, and its events are kept as [`OwnedEvent`]s until
+/// they are returned by [`WasmParser::next_event`].
+#[wasm_bindgen]
+pub struct WasmParser {
+    events: VecDeque<Result<OwnedEvent, String>>,
+    has_error: bool,
+}
+
+#[wasm_bindgen]
+impl WasmParser {
+    /// Parse the whole input, in display mode if `display` is true.
+    ///
+    /// The commands attaching HTML attributes to their content, and `\href` links to URLs of
+    /// other protocols than `http`, `https` and `mailto`, are only allowed if `trust` is true (see
+    /// [`ParserConfig::trust`]).
+    #[wasm_bindgen(constructor)]
+    pub fn new(input: &str, display: bool, trust: bool) -> Self {
+        let display_mode = if display {
+            DisplayMode::Block
+        } else {
+            DisplayMode::Inline
+        };
+        let config = ParserConfig {
+            display_mode,
+            trust,
+            ..Default::default()
+        };
+        let storage = Storage::new();
+        Self {
+            events: Parser::with_storage(input, config, &storage)
+                .map(|event| {
+                    event
+                        .map(OwnedEvent::from)
+                        .map_err(|error| error.to_string())
+                })
+                .collect(),
+            has_error: false,
+        }
+    }
+
+    /// Return the next event as a JavaScript object, or an `Error` if the next event is an error.
+    ///
+    /// Returns `undefined` once all events are returned.
+    pub fn next_event(&mut self) -> JsValue {
+        match self.events.pop_front() {
+            Some(Ok(event)) => {
+                serde_wasm_bindgen::to_value(&event).unwrap_or_else(|error| error.into())
+            }
+            Some(Err(error)) => {
+                self.has_error = true;
+                JsError::new(&error).into()
+            }
+            None => JsValue::UNDEFINED,
+        }
+    }
+
+    /// Whether an error was returned by [`WasmParser::next_event`] so far.
+    pub fn has_error(&self) -> bool {
+        self.has_error
+    }
+}
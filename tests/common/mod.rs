@@ -1,4 +1,4 @@
-#![allow(clippy::await_holding_lock)]
+#![allow(clippy::await_holding_lock, dead_code)]
 
 use std::{io::Write, mem::MaybeUninit, process::Stdio, sync::Mutex, time::Duration};
 
@@ -169,7 +169,7 @@ pub async fn cross_browser() -> anyhow::Result<()> {
         clients[i].write(client);
     }
     // Safety: The clients are initialized
-    let clients = unsafe { std::mem::transmute::<_, [Client; 3]>(clients) };
+    let clients = unsafe { std::mem::transmute::<[MaybeUninit<Client>; 3], [Client; 3]>(clients) };
 
     let rendered = &*RENDERED.lock().unwrap();
     for (table_name, rows) in rendered {